@@ -0,0 +1,585 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::fmt;
+
+    use argon2::{
+        password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+        Argon2,
+    };
+    use serde::{Deserialize, Serialize};
+
+    use crate::keyword_model::RatedKeywords;
+    use crate::session_store::{load_json, SessionStore, SessionStoreError, SESSION_TTL_SECONDS};
+    use crate::*;
+
+    const USER_KEY_PREFIX: &str = "user:";
+    const SESSION_USER_KEY_PREFIX: &str = "session_user:";
+
+    const MIN_PASSWORD_LENGTH: usize = 8;
+
+    // How many of the strongest keyword weights are carried into a new session
+    const PROFILE_KEYWORD_LIMIT: usize = 5;
+
+    #[derive(Debug)]
+    pub enum AccountError {
+        InvalidUsername(String),
+        InvalidPassword(String),
+        UsernameTaken(String),
+        InvalidCredentials,
+        NotFound(String),
+        Hashing(String),
+        Store(SessionStoreError),
+    }
+
+    impl fmt::Display for AccountError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                AccountError::InvalidUsername(reason) => write!(f, "Invalid username: {}", reason),
+                AccountError::InvalidPassword(reason) => write!(f, "Invalid password: {}", reason),
+                AccountError::UsernameTaken(username) => {
+                    write!(f, "Username {} is already taken", username)
+                }
+                AccountError::InvalidCredentials => write!(f, "Invalid username or password"),
+                AccountError::NotFound(username) => write!(f, "No account for {}", username),
+                AccountError::Hashing(err) => write!(f, "Password hashing error: {}", err),
+                AccountError::Store(err) => write!(f, "{}", err),
+            }
+        }
+    }
+
+    impl std::error::Error for AccountError {}
+
+    impl From<SessionStoreError> for AccountError {
+        fn from(err: SessionStoreError) -> Self {
+            AccountError::Store(err)
+        }
+    }
+
+    impl From<serde_json::Error> for AccountError {
+        fn from(err: serde_json::Error) -> Self {
+            AccountError::Store(SessionStoreError::Serialization(err))
+        }
+    }
+
+    /* Accounts live alongside sessions in the session store - the password is only ever kept as an argon2 hash */
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct UserAccount {
+        pub username: String,
+        pub password_hash: String,
+        #[serde(default)]
+        pub profile: TasteProfile,
+    }
+
+    fn user_key(username: &str) -> String {
+        format!("{}{}", USER_KEY_PREFIX, username)
+    }
+
+    fn session_user_key(session_id: &str) -> String {
+        format!("{}{}", SESSION_USER_KEY_PREFIX, session_id)
+    }
+
+    /* Usernames are case-insensitive - they are stored lowercased */
+    pub fn normalize_username(username: &str) -> Result<String, AccountError> {
+        let username = username.trim().to_lowercase();
+
+        if username.len() < 3 || username.len() > 32 {
+            return Err(AccountError::InvalidUsername(String::from(
+                "must be between 3 and 32 characters",
+            )));
+        }
+
+        if !username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(AccountError::InvalidUsername(String::from(
+                "only letters, numbers, '_' and '-' are allowed",
+            )));
+        }
+
+        Ok(username)
+    }
+
+    /* Argon2 is slow on purpose - hashing runs on the blocking pool so it doesn't hold up an actix worker */
+    pub async fn hash_password(password: &str) -> Result<String, AccountError> {
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(AccountError::InvalidPassword(format!(
+                "must be at least {} characters",
+                MIN_PASSWORD_LENGTH
+            )));
+        }
+
+        let password = password.to_string();
+
+        let hashed = tokio::task::spawn_blocking(move || {
+            let salt = SaltString::generate(&mut OsRng);
+
+            match Argon2::default().hash_password(password.as_bytes(), &salt) {
+                Ok(hash) => Ok(hash.to_string()),
+                Err(err) => Err(AccountError::Hashing(err.to_string())),
+            }
+        })
+        .await;
+
+        match hashed {
+            Ok(hash) => hash,
+            Err(err) => Err(AccountError::Hashing(err.to_string())),
+        }
+    }
+
+    pub async fn verify_password(password: &str, password_hash: &str) -> bool {
+        let password = password.to_string();
+        let password_hash = password_hash.to_string();
+
+        tokio::task::spawn_blocking(move || match PasswordHash::new(&password_hash) {
+            Ok(parsed_hash) => Argon2::default()
+                .verify_password(password.as_bytes(), &parsed_hash)
+                .is_ok(),
+            Err(_) => false,
+        })
+        .await
+        .unwrap_or(false)
+    }
+
+    /* Creating the key goes through update_value so two registrations for the same name can't both win */
+    pub async fn register(
+        store: &dyn SessionStore,
+        username: &str,
+        password: &str,
+    ) -> Result<UserAccount, AccountError> {
+        let username = normalize_username(username)?;
+
+        let account = UserAccount {
+            username: username.clone(),
+            password_hash: hash_password(password).await?,
+            profile: TasteProfile::default(),
+        };
+
+        let account_json = serde_json::to_string(&account)?;
+
+        let stored = store
+            .update_value(&user_key(&username), None, &|current| match current {
+                Some(existing) => Ok(Some(existing)),
+                None => Ok(Some(account_json.clone())),
+            })
+            .await?;
+
+        // The salt makes every hash unique, so anything else in the key means the name was taken
+        match stored {
+            Some(stored_json) if stored_json == account_json => Ok(account),
+            _ => Err(AccountError::UsernameTaken(username)),
+        }
+    }
+
+    pub async fn authenticate(
+        store: &dyn SessionStore,
+        username: &str,
+        password: &str,
+    ) -> Result<UserAccount, AccountError> {
+        let username = normalize_username(username).map_err(|_| AccountError::InvalidCredentials)?;
+
+        match load_account(store, &username).await {
+            Ok(account) if verify_password(password, &account.password_hash).await => Ok(account),
+            Ok(_) | Err(AccountError::NotFound(_)) => Err(AccountError::InvalidCredentials),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn load_account(
+        store: &dyn SessionStore,
+        username: &str,
+    ) -> Result<UserAccount, AccountError> {
+        match load_json(store, &user_key(username)).await? {
+            Some(account) => Ok(account),
+            None => Err(AccountError::NotFound(username.to_string())),
+        }
+    }
+
+    /* The link expires with the session itself */
+    pub async fn link_session(
+        store: &dyn SessionStore,
+        session_id: &str,
+        username: &str,
+    ) -> Result<(), AccountError> {
+        store
+            .set_value(&session_user_key(session_id), username, Some(SESSION_TTL_SECONDS))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn unlink_session(store: &dyn SessionStore, session_id: &str) -> Result<(), AccountError> {
+        store.delete_value(&session_user_key(session_id)).await?;
+
+        Ok(())
+    }
+
+    /* Signing in always moves to a fresh session ID, so an ID known before sign in can't ride along - the criteria carry over */
+    pub async fn sign_in_new_session(
+        store: &dyn SessionStore,
+        old_session_id: &str,
+        username: &str,
+    ) -> Result<String, AccountError> {
+        let criteria = store.load(old_session_id).await.unwrap_or_default();
+
+        let session_id = store.create().await?;
+        store.save(&session_id, &criteria).await?;
+        link_session(store, &session_id, username).await?;
+
+        unlink_session(store, old_session_id).await?;
+        store.delete(old_session_id).await?;
+
+        Ok(session_id)
+    }
+
+    pub async fn user_for_session(
+        store: &dyn SessionStore,
+        session_id: &str,
+    ) -> Result<Option<String>, AccountError> {
+        Ok(store.get_value(&session_user_key(session_id)).await?)
+    }
+
+    /* Per-person data (watchlist, history, etc.) follows the signed in user, or the session when signed out */
+    pub struct DataOwner {
+        id: String,
+        ttl_seconds: Option<u64>,
+    }
+
+    impl DataOwner {
+        pub fn user(username: &str) -> Self {
+            Self {
+                id: format!("user:{}", username),
+                ttl_seconds: None,
+            }
+        }
+
+        pub fn session(session_id: &str) -> Self {
+            Self {
+                id: format!("session:{}", session_id),
+                ttl_seconds: Some(SESSION_TTL_SECONDS),
+            }
+        }
+
+        pub async fn for_session(
+            store: &dyn SessionStore,
+            session_id: &str,
+        ) -> Result<Self, AccountError> {
+            match user_for_session(store, session_id).await? {
+                Some(username) => Ok(Self::user(&username)),
+                None => Ok(Self::session(session_id)),
+            }
+        }
+
+        pub fn id(&self) -> &str {
+            &self.id
+        }
+
+        /* The reverse of id() - background jobs only have what was stored */
+        pub fn from_id(id: &str) -> Option<Self> {
+            if let Some(username) = id.strip_prefix("user:") {
+                Some(Self::user(username))
+            } else {
+                id.strip_prefix("session:").map(Self::session)
+            }
+        }
+
+        pub fn key(&self, prefix: &str) -> String {
+            format!("{}{}", prefix, self.id)
+        }
+
+        /* Signed in users keep their data forever, anonymous ones lose it with the session */
+        pub fn ttl_seconds(&self) -> Option<u64> {
+            self.ttl_seconds
+        }
+    }
+
+    pub async fn update_profile<F>(
+        store: &dyn SessionStore,
+        username: &str,
+        update: F,
+    ) -> Result<TasteProfile, AccountError>
+    where
+        F: Fn(&mut TasteProfile) + Send + Sync,
+    {
+        let stored = store
+            .update_value(&user_key(username), None, &|current| match current {
+                None => Err(SessionStoreError::NotFound(username.to_string())),
+                Some(json_string) => {
+                    let mut account: UserAccount = serde_json::from_str(&json_string)?;
+                    update(&mut account.profile);
+                    Ok(Some(serde_json::to_string(&account)?))
+                }
+            })
+            .await;
+
+        match stored {
+            Ok(Some(json_string)) => Ok(serde_json::from_str::<UserAccount>(&json_string)?.profile),
+            Ok(None) | Err(SessionStoreError::NotFound(_)) => {
+                Err(AccountError::NotFound(username.to_string()))
+            }
+            Err(err) => Err(AccountError::Store(err)),
+        }
+    }
+
+    /* Liked/disliked movies are remembered once, keyword weights accumulate across sessions without decaying */
+    pub fn record_feedback(
+        profile: &mut TasteProfile,
+        liked_movies: &[i64],
+        disliked_movies: &[i64],
+        rated_keywords: &[RatedKeywords],
+    ) {
+        for id in liked_movies {
+            profile.disliked_movies.retain(|disliked| disliked != id);
+            if !profile.liked_movies.contains(id) {
+                profile.liked_movies.push(*id);
+            }
+        }
+
+        for id in disliked_movies {
+            profile.liked_movies.retain(|liked| liked != id);
+            if !profile.disliked_movies.contains(id) {
+                profile.disliked_movies.push(*id);
+            }
+        }
+
+        for rated_movie in rated_keywords {
+            for id in &rated_movie.keyword_ids {
+                *profile.keyword_weights.entry(*id).or_insert(0.0) += rated_movie.weight;
+            }
+        }
+    }
+
+    /* Pre-fills a returning user's session - genres and decade are left for the wizard since they change with the mood */
+    pub fn apply_profile(criteria: &mut RecommendationCriteria, profile: &TasteProfile) {
+        if profile.watch_providers.is_some() {
+            criteria.watch_providers = profile.watch_providers.clone();
+        }
+
+        if profile.runtime.is_some() {
+            criteria.runtime = profile.runtime.clone();
+        }
+
+        criteria.keyword_profile =
+            KeywordProfile::seeded(&profile.keyword_weights, PROFILE_KEYWORD_LIMIT);
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::session_store::MemorySessionStore;
+
+        fn rated(movie_id: i64, keyword_ids: Vec<i64>, weight: f64) -> RatedKeywords {
+            RatedKeywords {
+                movie_id,
+                media_type: MediaType::Movie,
+                keyword_ids,
+                weight,
+            }
+        }
+
+        #[test]
+        fn username_rules() {
+            assert_eq!(normalize_username(" Movie_Fan ").unwrap(), "movie_fan");
+            assert!(normalize_username("ab").is_err());
+            assert!(normalize_username("user:admin").is_err());
+        }
+
+        #[tokio::test]
+        async fn password_hash_roundtrip() {
+            let password_hash = hash_password("correct horse").await.unwrap();
+
+            assert!(password_hash.starts_with("$argon2"));
+            assert!(verify_password("correct horse", &password_hash).await);
+            assert!(!verify_password("wrong horse", &password_hash).await);
+        }
+
+        #[tokio::test]
+        async fn short_password_rejected() {
+            assert!(matches!(
+                hash_password("short").await,
+                Err(AccountError::InvalidPassword(_))
+            ));
+        }
+
+        #[tokio::test]
+        async fn register_and_authenticate() {
+            let store = MemorySessionStore::new();
+
+            register(&store, "MovieFan", "correct horse").await.unwrap();
+
+            let account = authenticate(&store, "moviefan", "correct horse").await.unwrap();
+
+            assert_eq!(account.username, "moviefan");
+            assert!(matches!(
+                authenticate(&store, "moviefan", "wrong horse").await,
+                Err(AccountError::InvalidCredentials)
+            ));
+            assert!(matches!(
+                authenticate(&store, "nobody", "correct horse").await,
+                Err(AccountError::InvalidCredentials)
+            ));
+        }
+
+        #[tokio::test]
+        async fn register_taken_username() {
+            let store = MemorySessionStore::new();
+
+            register(&store, "moviefan", "correct horse").await.unwrap();
+
+            assert!(matches!(
+                register(&store, "MOVIEFAN", "another horse").await,
+                Err(AccountError::UsernameTaken(_))
+            ));
+
+            // The original password still works
+            assert!(authenticate(&store, "moviefan", "correct horse").await.is_ok());
+        }
+
+        #[tokio::test]
+        async fn session_link() {
+            let store = MemorySessionStore::new();
+            let session_id = store.create().await.unwrap();
+
+            assert_eq!(user_for_session(&store, &session_id).await.unwrap(), None);
+
+            link_session(&store, &session_id, "moviefan").await.unwrap();
+
+            assert_eq!(
+                user_for_session(&store, &session_id).await.unwrap(),
+                Some(String::from("moviefan"))
+            );
+
+            unlink_session(&store, &session_id).await.unwrap();
+
+            assert_eq!(user_for_session(&store, &session_id).await.unwrap(), None);
+        }
+
+        #[tokio::test]
+        async fn sign_in_replaces_session() {
+            let store = MemorySessionStore::new();
+            let old_session_id = store.create().await.unwrap();
+            store
+                .update(&old_session_id, &|criteria| criteria.runtime = Some(Runtime::Quick))
+                .await
+                .unwrap();
+
+            let session_id = sign_in_new_session(&store, &old_session_id, "moviefan")
+                .await
+                .unwrap();
+
+            assert_ne!(session_id, old_session_id);
+            assert_eq!(store.load(&session_id).await.unwrap().runtime, Some(Runtime::Quick));
+            assert_eq!(
+                user_for_session(&store, &session_id).await.unwrap(),
+                Some(String::from("moviefan"))
+            );
+            // The ID from before sign in is gone rather than signed in
+            assert!(store.load(&old_session_id).await.is_err());
+            assert_eq!(user_for_session(&store, &old_session_id).await.unwrap(), None);
+        }
+
+        #[tokio::test]
+        async fn data_owner_follows_session_user() {
+            let store = MemorySessionStore::new();
+            let session_id = store.create().await.unwrap();
+
+            let anonymous = DataOwner::for_session(&store, &session_id).await.unwrap();
+
+            assert_eq!(anonymous.key("watchlist:"), format!("watchlist:session:{}", session_id));
+            assert_eq!(anonymous.ttl_seconds(), Some(SESSION_TTL_SECONDS));
+
+            link_session(&store, &session_id, "moviefan").await.unwrap();
+
+            let signed_in = DataOwner::for_session(&store, &session_id).await.unwrap();
+
+            assert_eq!(signed_in.key("watchlist:"), "watchlist:user:moviefan");
+            assert_eq!(signed_in.ttl_seconds(), None);
+
+            // Background jobs rebuild owners from their stored IDs
+            let rebuilt = DataOwner::from_id(anonymous.id()).unwrap();
+            assert_eq!(rebuilt.key("watchlist:"), anonymous.key("watchlist:"));
+            assert_eq!(rebuilt.ttl_seconds(), Some(SESSION_TTL_SECONDS));
+            assert!(DataOwner::from_id("room:abc").is_none());
+        }
+
+        #[tokio::test]
+        async fn profile_update() {
+            let store = MemorySessionStore::new();
+
+            register(&store, "moviefan", "correct horse").await.unwrap();
+
+            update_profile(&store, "moviefan", |profile| {
+                profile.watch_providers = Some(vec![8, 15]);
+                record_feedback(
+                    profile,
+                    &[1, 2],
+                    &[3],
+                    &[
+                        rated(1, vec![100, 101], 1.0),
+                        rated(3, vec![200], -1.0),
+                    ],
+                );
+            })
+            .await
+            .unwrap();
+
+            let profile = update_profile(&store, "moviefan", |profile| {
+                record_feedback(profile, &[3], &[], &[rated(3, vec![100], 1.0)]);
+            })
+            .await
+            .unwrap();
+
+            assert_eq!(profile.watch_providers, Some(vec![8, 15]));
+            assert_eq!(profile.liked_movies, vec![1, 2, 3]);
+            assert!(profile.disliked_movies.is_empty());
+            assert_eq!(profile.keyword_weights.get(&100), Some(&2.0));
+            assert_eq!(profile.keyword_weights.get(&200), Some(&-1.0));
+
+            // The password hash is left alone by profile updates
+            assert!(authenticate(&store, "moviefan", "correct horse").await.is_ok());
+        }
+
+        #[tokio::test]
+        async fn profile_update_missing_user() {
+            let store = MemorySessionStore::new();
+
+            assert!(matches!(
+                update_profile(&store, "nobody", |_| {}).await,
+                Err(AccountError::NotFound(_))
+            ));
+        }
+
+        #[test]
+        fn apply_profile_prefills_criteria() {
+            let mut profile = TasteProfile {
+                watch_providers: Some(vec![8]),
+                runtime: Some(Runtime::MovieNight),
+                ..Default::default()
+            };
+            profile.keyword_weights.insert(100, 3.0);
+            profile.keyword_weights.insert(101, 1.0);
+            profile.keyword_weights.insert(200, -2.0);
+
+            let mut criteria = RecommendationCriteria {
+                genres: Some(vec![28]),
+                ..Default::default()
+            };
+
+            apply_profile(&mut criteria, &profile);
+
+            assert_eq!(criteria.watch_providers, Some(vec![8]));
+            assert_eq!(criteria.runtime, Some(Runtime::MovieNight));
+            assert_eq!(criteria.genres, Some(vec![28]));
+            assert_eq!(
+                criteria.keyword_profile.to_feedback(PROFILE_KEYWORD_LIMIT),
+                Some(Feedback {
+                    like: Some(vec![100, 101]),
+                    dislike: Some(vec![200]),
+                })
+            );
+        }
+    }
+}
+}
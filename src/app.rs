@@ -66,17 +66,50 @@ pub fn App() -> impl IntoView {
                 <main>
                     <Routes>
                         <Route path="/" view=HomePage/>
+                        <Route path="/account" view=AccountPage ssr=SsrMode::OutOfOrder/>
+                        <Route path="/mood" view=MoodPage ssr=SsrMode::OutOfOrder/>
+                        <Route path="/providers" view=ProviderPage ssr=SsrMode::OutOfOrder/>
+                        <Route path="/decade" view=DecadePage ssr=SsrMode::OutOfOrder/>
+                        <Route path="/media" view=MediaPage/>
+                        <Route path="/runtime" view=RuntimePage/>
+                        <Route path="/language" view=LanguagePage ssr=SsrMode::OutOfOrder/>
+                        <Route path="/rating" view=CertificationPage ssr=SsrMode::OutOfOrder/>
+                        <Route path="/people" view=CreditsPage ssr=SsrMode::OutOfOrder/>
+                        <Route path="/genres" view=GenrePage ssr=SsrMode::OutOfOrder/>
                         <Route
-                            path="/providers/:session_id"
-                            view=ProviderPage
+                            path="/recommend"
+                            view=RecommendationPage
                             ssr=SsrMode::OutOfOrder
                         />
-                        <Route path="/decade/:session_id" view=DecadePage ssr=SsrMode::OutOfOrder/>
-                        <Route path="/runtime/:session_id" view=RuntimePage/>
-                        <Route path="/genres/:session_id" view=GenrePage ssr=SsrMode::OutOfOrder/>
+                        <Route path="/movie/:id" view=MovieDetailPage ssr=SsrMode::OutOfOrder/>
+                        <Route path="/watchlist" view=WatchlistPage ssr=SsrMode::OutOfOrder/>
+                        <Route path="/swipe" view=SwipePage/>
+                        <Route path="/surprise" view=SurprisePage ssr=SsrMode::OutOfOrder/>
                         <Route
-                            path="/recommend/:session_id"
-                            view=RecommendationPage
+                            path="/browse/trending"
+                            view=|| view! { <BrowsePage list=BrowseList::Trending(TrendingWindow::Day)/> }
+                            ssr=SsrMode::OutOfOrder
+                        />
+                        <Route
+                            path="/browse/trending-week"
+                            view=|| view! { <BrowsePage list=BrowseList::Trending(TrendingWindow::Week)/> }
+                            ssr=SsrMode::OutOfOrder
+                        />
+                        <Route
+                            path="/browse/now-playing"
+                            view=|| view! { <BrowsePage list=BrowseList::NowPlaying/> }
+                            ssr=SsrMode::OutOfOrder
+                        />
+                        <Route
+                            path="/browse/upcoming"
+                            view=|| view! { <BrowsePage list=BrowseList::Upcoming/> }
+                            ssr=SsrMode::OutOfOrder
+                        />
+                        <Route path="/room" view=RoomLobbyPage/>
+                        <Route path="/room/:code" view=RoomPage ssr=SsrMode::OutOfOrder/>
+                        <Route
+                            path="/shared/:share_token"
+                            view=SharedRecommendationPage
                             ssr=SsrMode::OutOfOrder
                         />
                         <Route path="/*any" view=|| view! { <h1>"Not Found"</h1> }/>
@@ -187,7 +220,7 @@ fn LoadingCards() -> impl IntoView {
 }
 
 #[component]
-fn Card<T: CardData + Clone + 'static>(#[prop(into)] card_data: Vec<T>) -> impl IntoView {
+pub fn Card<T: CardData + Clone + 'static>(#[prop(into)] card_data: Vec<T>) -> impl IntoView {
     let selected_data_signal = expect_context::<SelectedData>();
     view! {
         {card_data
@@ -0,0 +1,330 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::{
+        collections::HashMap,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::accounts::DataOwner;
+    use crate::server_functions::supported_flatrate_providers;
+    use crate::session_store::{load_json, update_json, SessionStore, SessionStoreError};
+    use crate::tmdb::Tmdb;
+    use crate::watchlist::{forget_owners, load_watchlist, watchlist_owners, Watchlist};
+    use crate::*;
+
+    const AVAILABILITY_KEY_PREFIX: &str = "availability:";
+
+    pub const DEFAULT_CHECK_INTERVAL_SECONDS: u64 = 60 * 60 * 6;
+
+    // Older changes are dropped so a title that flips back and forth doesn't grow forever
+    const MAX_CHANGES_PER_TITLE: usize = 20;
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+    #[serde(default)]
+    pub struct ProviderChange {
+        pub checked_at: u64,
+        pub added: Vec<i32>,
+        pub removed: Vec<i32>,
+    }
+
+    /* The providers a title was last seen on, and how that has changed since it was saved */
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+    #[serde(default)]
+    pub struct TitleAvailability {
+        pub providers: Vec<i32>,
+        pub last_checked: u64,
+        pub changes: Vec<ProviderChange>,
+    }
+
+    /* One per watchlist owner - alerts stay until they're dismissed or stop being true */
+    /* Titles are keyed by title_key, since movie and series IDs overlap */
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+    #[serde(default)]
+    pub struct AvailabilityHistory {
+        pub titles: HashMap<String, TitleAvailability>,
+        pub alerts: Vec<AvailabilityAlert>,
+    }
+
+    impl AvailabilityHistory {
+        /* The first check of a title is its baseline - the card already shows where it was when it was saved */
+        pub fn record(&mut self, movie: &Movie, providers: &[WatchProvider], now: u64) {
+            let mut current: Vec<i32> = providers.iter().map(|provider| provider.provider_id).collect();
+            current.sort();
+            current.dedup();
+
+            let key = title_key(movie.id, movie.media_type);
+            let title = match self.titles.get_mut(&key) {
+                Some(title) => title,
+                None => {
+                    self.titles.insert(
+                        key,
+                        TitleAvailability {
+                            providers: current,
+                            last_checked: now,
+                            changes: vec![],
+                        },
+                    );
+                    return;
+                }
+            };
+
+            let added: Vec<i32> = current
+                .iter()
+                .filter(|id| !title.providers.contains(id))
+                .copied()
+                .collect();
+            let removed: Vec<i32> = title
+                .providers
+                .iter()
+                .filter(|id| !current.contains(id))
+                .copied()
+                .collect();
+
+            if !added.is_empty() || !removed.is_empty() {
+                title.changes.push(ProviderChange {
+                    checked_at: now,
+                    added: added.clone(),
+                    removed: removed.clone(),
+                });
+                let overflow = title.changes.len().saturating_sub(MAX_CHANGES_PER_TITLE);
+                title.changes.drain(..overflow);
+            }
+
+            title.providers = current;
+            title.last_checked = now;
+
+            // A provider that has dropped the title again makes its alert wrong
+            self.alerts.retain(|alert| {
+                !is_alert_for(alert, movie) || !removed.contains(&alert.provider_id)
+            });
+
+            for provider in providers.iter().filter(|provider| added.contains(&provider.provider_id)) {
+                self.alerts.retain(|alert| {
+                    !is_alert_for(alert, movie) || alert.provider_id != provider.provider_id
+                });
+                self.alerts.push(AvailabilityAlert {
+                    movie_id: movie.id,
+                    media_type: movie.media_type,
+                    title: movie.title.clone(),
+                    provider_id: provider.provider_id,
+                    provider_name: provider.provider_name.clone(),
+                    detected_at: now,
+                });
+            }
+        }
+
+        /* Titles taken off the watchlist stop being tracked */
+        pub fn retain_saved(&mut self, watchlist: &Watchlist) {
+            let saved: Vec<String> = watchlist
+                .movies
+                .iter()
+                .map(|movie| title_key(movie.id, movie.media_type))
+                .collect();
+
+            self.titles.retain(|key, _| saved.contains(key));
+            self.alerts
+                .retain(|alert| watchlist.contains(alert.movie_id, alert.media_type));
+        }
+    }
+
+    pub fn title_key(movie_id: i64, media_type: MediaType) -> String {
+        format!("{}:{}", media_type.path(), movie_id)
+    }
+
+    fn is_alert_for(alert: &AvailabilityAlert, movie: &Movie) -> bool {
+        alert.movie_id == movie.id && alert.media_type == movie.media_type
+    }
+
+    pub fn unix_now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default()
+    }
+
+    pub async fn load_availability(
+        store: &dyn SessionStore,
+        owner: &DataOwner,
+    ) -> Result<AvailabilityHistory, SessionStoreError> {
+        Ok(load_json(store, &owner.key(AVAILABILITY_KEY_PREFIX))
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn dismiss_alerts(store: &dyn SessionStore, owner: &DataOwner) -> Result<(), SessionStoreError> {
+        update_json(
+            store,
+            &owner.key(AVAILABILITY_KEY_PREFIX),
+            owner.ttl_seconds(),
+            |history: &mut AvailabilityHistory| history.alerts.clear(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /* Supported providers for one title, or None if TMDB couldn't be reached - a failed lookup isn't "left every service" */
+    async fn current_providers(tmdb: &Tmdb, movie: &Movie) -> Option<Vec<WatchProvider>> {
+        tmdb.get_watch_providers_for(&movie.media_type, &movie.id.to_string())
+            .await
+            .ok()
+            .map(supported_flatrate_providers)
+    }
+
+    /* Re-checks every watchlist - each title is looked up once per run however many people saved it */
+    /* Returns how many watchlists were checked */
+    pub async fn check_watchlists(store: &dyn SessionStore, tmdb: &Tmdb) -> Result<usize, SessionStoreError> {
+        let mut lookups: HashMap<(MediaType, i64), Option<Vec<WatchProvider>>> = HashMap::new();
+        let mut emptied = vec![];
+        let mut checked = 0;
+
+        for owner in watchlist_owners(store).await? {
+            let watchlist = load_watchlist(store, &owner).await?;
+
+            if watchlist.movies.is_empty() {
+                emptied.push(owner);
+                continue;
+            }
+
+            let mut found = vec![];
+            for movie in &watchlist.movies {
+                let key = (movie.media_type, movie.id);
+                let providers = match lookups.get(&key) {
+                    Some(providers) => providers.clone(),
+                    None => {
+                        let providers = current_providers(tmdb, movie).await;
+                        lookups.insert(key, providers.clone());
+                        providers
+                    }
+                };
+                if let Some(providers) = providers {
+                    found.push((movie.clone(), providers));
+                }
+            }
+
+            let now = unix_now();
+            update_json(
+                store,
+                &owner.key(AVAILABILITY_KEY_PREFIX),
+                owner.ttl_seconds(),
+                |history: &mut AvailabilityHistory| {
+                    history.retain_saved(&watchlist);
+                    for (movie, providers) in &found {
+                        history.record(movie, providers, now);
+                    }
+                },
+            )
+            .await?;
+
+            checked += 1;
+        }
+
+        if !emptied.is_empty() {
+            forget_owners(store, &emptied).await?;
+        }
+
+        Ok(checked)
+    }
+
+    /* How often to re-check, from AVAILABILITY_CHECK_INTERVAL_SECONDS */
+    pub fn check_interval_from_env() -> Duration {
+        let seconds = std::env::var("AVAILABILITY_CHECK_INTERVAL_SECONDS")
+            .ok()
+            .and_then(|seconds| seconds.trim().parse::<u64>().ok())
+            .filter(|seconds| *seconds > 0)
+            .unwrap_or(DEFAULT_CHECK_INTERVAL_SECONDS);
+
+        Duration::from_secs(seconds)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn key(id: i64) -> String {
+            title_key(id, MediaType::Movie)
+        }
+
+        fn provider(provider_id: i32, provider_name: &str) -> WatchProvider {
+            WatchProvider {
+                logo_path: String::new(),
+                provider_id,
+                provider_name: provider_name.to_string(),
+            }
+        }
+
+        #[test]
+        fn alerts_on_new_providers() {
+            let mut history = AvailabilityHistory::default();
+
+            // Nothing to report the first time round
+            history.record(&test_movie(1), &[provider(8, "Netflix")], 100);
+            assert!(history.alerts.is_empty());
+
+            history.record(&test_movie(1), &[provider(8, "Netflix"), provider(15, "Hulu")], 200);
+            assert_eq!(history.alerts.len(), 1);
+            assert_eq!(history.alerts[0].provider_name, "Hulu");
+            assert_eq!(history.alerts[0].detected_at, 200);
+
+            let title = &history.titles[&key(1)];
+            assert_eq!(title.providers, vec![8, 15]);
+            assert_eq!(title.changes, vec![ProviderChange { checked_at: 200, added: vec![15], removed: vec![] }]);
+
+            // An unchanged check records nothing new
+            history.record(&test_movie(1), &[provider(15, "Hulu"), provider(8, "Netflix")], 300);
+            assert_eq!(history.titles[&key(1)].changes.len(), 1);
+            assert_eq!(history.titles[&key(1)].last_checked, 300);
+        }
+
+        #[test]
+        fn leaving_clears_alert() {
+            let mut history = AvailabilityHistory::default();
+
+            history.record(&test_movie(1), &[], 100);
+            history.record(&test_movie(1), &[provider(15, "Hulu")], 200);
+            assert_eq!(history.alerts.len(), 1);
+
+            history.record(&test_movie(1), &[], 300);
+            assert!(history.alerts.is_empty());
+            assert_eq!(history.titles[&key(1)].changes.len(), 2);
+        }
+
+        #[test]
+        fn forgets_unsaved_titles() {
+            let mut history = AvailabilityHistory::default();
+
+            history.record(&test_movie(1), &[], 100);
+            history.record(&test_movie(2), &[], 100);
+            history.record(&test_movie(2), &[provider(8, "Netflix")], 200);
+
+            history.retain_saved(&Watchlist { movies: vec![test_movie(1)] });
+
+            assert!(history.titles.contains_key(&key(1)));
+            assert!(!history.titles.contains_key(&key(2)));
+            assert!(history.alerts.is_empty());
+        }
+
+        #[test]
+        fn change_history_is_capped() {
+            let mut history = AvailabilityHistory::default();
+
+            history.record(&test_movie(1), &[], 0);
+            for check in 1..=(MAX_CHANGES_PER_TITLE as u64 + 5) {
+                let providers = match check % 2 {
+                    0 => vec![],
+                    _ => vec![provider(8, "Netflix")],
+                };
+                history.record(&test_movie(1), &providers, check);
+            }
+
+            let changes = &history.titles[&key(1)].changes;
+            assert_eq!(changes.len(), MAX_CHANGES_PER_TITLE);
+            assert_eq!(changes.last().unwrap().checked_at, MAX_CHANGES_PER_TITLE as u64 + 5);
+        }
+    }
+}
+}
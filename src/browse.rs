@@ -0,0 +1,189 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::sync::Arc;
+
+    use crate::discover::WATCH_REGION;
+    use crate::tmdb::Tmdb;
+    use crate::tmdb_helper::fetch_allowed;
+    use crate::*;
+
+    // A list page's worth - what the grid shows when nothing is filtered out
+    const BROWSE_RESULTS: usize = 20;
+
+    /* A browse list narrowed down to the session - each movie comes with its watch providers, if it has any */
+    pub async fn browse(
+        tmdb: Arc<Tmdb>,
+        list: &BrowseList,
+        criteria: &RecommendationCriteria,
+    ) -> Result<Vec<(Movie, Option<GetWatchProvidersResponse>)>, Box<dyn std::error::Error>> {
+        let language = criteria.languages.display_language();
+
+        let movies = fetch_allowed(&criteria.languages, 1, BROWSE_RESULTS, |page| {
+            let tmdb = Arc::clone(&tmdb);
+            let language = language.clone();
+            let list = *list;
+            async move {
+                let response = match list {
+                    BrowseList::Trending(window) => tmdb.get_trending(&window, &language, page).await?,
+                    BrowseList::NowPlaying => tmdb.get_now_playing(WATCH_REGION, &language, page).await?,
+                    BrowseList::Upcoming => tmdb.get_upcoming(WATCH_REGION, &language, page).await?,
+                };
+                Ok(response.results)
+            }
+        })
+        .await?;
+
+        // Lists aren't discover calls, so family mode has to check each movie's rating itself
+        let movies = match criteria.family_limit() {
+            Some(limit) => filter_by_certification(&tmdb, movies, &limit).await?,
+            None => movies,
+        };
+
+        let mut handles = vec![];
+        for movie in movies {
+            let temp_tmdb = Arc::clone(&tmdb);
+            let movie_id = movie.id.to_string();
+            handles.push((
+                movie,
+                tokio::spawn(async move { temp_tmdb.get_watch_providers_by_id(&movie_id).await.ok() }),
+            ));
+        }
+
+        let wanted = criteria.watch_providers.clone().unwrap_or_default();
+        let mut results = vec![];
+        for (movie, handle) in handles {
+            let providers = handle.await.unwrap_or_default();
+
+            if list.streaming() && !on_providers(providers.as_ref(), &wanted) {
+                continue;
+            }
+
+            results.push((movie, providers));
+        }
+
+        Ok(results)
+    }
+
+    /* No providers picked yet means anything goes */
+    pub fn on_providers(providers: Option<&GetWatchProvidersResponse>, wanted: &[i32]) -> bool {
+        if wanted.is_empty() {
+            return true;
+        }
+
+        providers
+            .map(|providers| {
+                providers
+                    .results
+                    .us
+                    .flatrate
+                    .iter()
+                    .any(|provider| wanted.contains(&provider.provider_id))
+            })
+            .unwrap_or(false)
+    }
+
+    /* Unrated movies are left out - there's no telling what's in them */
+    pub fn within_limit(certification: Option<&str>, ratings: &[Certification], max: &str) -> bool {
+        let order = |rating: &str| {
+            ratings
+                .iter()
+                .find(|known| known.certification == rating)
+                .map(|known| known.order)
+        };
+
+        match (certification.and_then(order), order(max)) {
+            (Some(rating), Some(max)) => rating <= max,
+            _ => false,
+        }
+    }
+
+    async fn filter_by_certification(
+        tmdb: &Arc<Tmdb>,
+        movies: Vec<Movie>,
+        limit: &CertificationLimit,
+    ) -> Result<Vec<Movie>, Box<dyn std::error::Error>> {
+        let ratings = tmdb
+            .get_certifications()
+            .await?
+            .certifications
+            .remove(&limit.country)
+            .unwrap_or_default();
+
+        let mut handles = vec![];
+        for movie in movies {
+            let temp_tmdb = Arc::clone(tmdb);
+            let movie_id = movie.id;
+            handles.push((
+                movie,
+                tokio::spawn(async move { temp_tmdb.get_release_dates(&movie_id).await.ok() }),
+            ));
+        }
+
+        let mut allowed = vec![];
+        for (movie, handle) in handles {
+            let certification = handle
+                .await
+                .ok()
+                .flatten()
+                .and_then(|release_dates| release_dates.certification(&limit.country));
+
+            if within_limit(certification.as_deref(), &ratings, &limit.max) {
+                allowed.push(movie);
+            }
+        }
+
+        Ok(allowed)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn ratings() -> Vec<Certification> {
+            ["G", "PG", "PG-13", "R", "NC-17"]
+                .iter()
+                .enumerate()
+                .map(|(order, certification)| Certification {
+                    certification: certification.to_string(),
+                    meaning: String::new(),
+                    order: order as i32 + 1,
+                })
+                .collect()
+        }
+
+        #[test]
+        fn limits_by_rating_order() {
+            let ratings = ratings();
+
+            assert!(within_limit(Some("G"), &ratings, "PG"));
+            assert!(within_limit(Some("PG"), &ratings, "PG"));
+            assert!(!within_limit(Some("PG-13"), &ratings, "PG"));
+            assert!(!within_limit(None, &ratings, "PG"));
+            // Ratings from some other country's system can't be compared
+            assert!(!within_limit(Some("12A"), &ratings, "PG-13"));
+        }
+
+        #[test]
+        fn provider_filter() {
+            let providers = GetWatchProvidersResponse {
+                results: WatchProviderRegions {
+                    us: WatchProviderRegion {
+                        flatrate: vec![WatchProvider {
+                            logo_path: String::new(),
+                            provider_id: 8,
+                            provider_name: String::from("Netflix"),
+                        }],
+                    },
+                },
+            };
+
+            assert!(on_providers(Some(&providers), &[8, 337]));
+            assert!(!on_providers(Some(&providers), &[337]));
+            assert!(!on_providers(None, &[8]));
+            assert!(on_providers(None, &[]));
+        }
+    }
+}
+}
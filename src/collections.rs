@@ -0,0 +1,224 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::{collections::HashMap, sync::Arc};
+
+    use crate::tmdb::Tmdb;
+    use crate::*;
+
+    /* Collections keyed by the ID of each movie that belongs to one - one details call per movie, one collection call per franchise */
+    pub async fn collections_for(
+        tmdb: &Arc<Tmdb>,
+        movies: &[Movie],
+        language: &str,
+    ) -> HashMap<i64, Collection> {
+        // Series don't have collections
+        let mut handles = vec![];
+        for movie in movies.iter().filter(|movie| movie.media_type == MediaType::Movie) {
+            let temp_tmdb = Arc::clone(tmdb);
+            let movie_id = movie.id;
+            handles.push((
+                movie_id,
+                tokio::spawn(async move { temp_tmdb.get_movie_details(&movie_id).await.ok() }),
+            ));
+        }
+
+        let mut memberships: Vec<(i64, i64)> = vec![];
+        for (movie_id, handle) in handles {
+            if let Ok(Some(MovieDetails {
+                belongs_to_collection: Some(summary),
+                ..
+            })) = handle.await
+            {
+                memberships.push((movie_id, summary.id));
+            }
+        }
+
+        let mut collection_ids: Vec<i64> = memberships.iter().map(|(_, id)| *id).collect();
+        collection_ids.sort();
+        collection_ids.dedup();
+
+        let mut collection_handles = vec![];
+        for collection_id in collection_ids {
+            let temp_tmdb = Arc::clone(tmdb);
+            let language = language.to_string();
+            collection_handles.push(tokio::spawn(async move {
+                temp_tmdb.get_collection(&collection_id, &language).await.ok()
+            }));
+        }
+
+        let mut collections: HashMap<i64, Collection> = HashMap::new();
+        for handle in collection_handles {
+            if let Ok(Some(collection)) = handle.await {
+                collections.insert(collection.id, collection);
+            }
+        }
+
+        memberships
+            .into_iter()
+            .filter_map(|(movie_id, collection_id)| {
+                collections
+                    .get(&collection_id)
+                    .map(|collection| (movie_id, collection.clone()))
+            })
+            .collect()
+    }
+
+    /* The first released part that hasn't been watched - where someone new to the franchise should start */
+    pub fn next_unwatched(collection: &Collection, watched: &dyn Fn(i64) -> bool) -> Option<Movie> {
+        collection
+            .in_release_order()
+            .into_iter()
+            .filter(|part| !part.release_date.is_empty())
+            .find(|part| !watched(part.id))
+    }
+
+    /* Franchise entries share one card - the earliest part in the results */
+    /* With skip_unseen_sequels, parts after the next unwatched one are dropped too */
+    pub fn arrange(
+        movies: Vec<Movie>,
+        collections: &HashMap<i64, Collection>,
+        watched: &dyn Fn(i64) -> bool,
+        skip_unseen_sequels: bool,
+    ) -> Vec<(Movie, Option<CollectionPosition>)> {
+        // Collections are keyed by movie ID - a series that happens to share the ID isn't part of one
+        let collection_of = |movie: &Movie| match movie.media_type {
+            MediaType::Movie => collections.get(&movie.id),
+            MediaType::Tv => None,
+        };
+
+        let positions: HashMap<(MediaType, i64), CollectionPosition> = movies
+            .iter()
+            .filter_map(|movie| {
+                collection_of(movie)
+                    .and_then(|collection| collection.position(movie.id))
+                    .map(|position| ((movie.media_type, movie.id), position))
+            })
+            .collect();
+
+        let mut earliest: HashMap<i64, usize> = HashMap::new();
+        for position in positions.values() {
+            let part = earliest.entry(position.collection_id).or_insert(position.part);
+            *part = (*part).min(position.part);
+        }
+
+        movies
+            .into_iter()
+            .filter(|movie| match positions.get(&(movie.media_type, movie.id)) {
+                None => true,
+                Some(position) => earliest.get(&position.collection_id) == Some(&position.part),
+            })
+            .filter(|movie| {
+                if !skip_unseen_sequels {
+                    return true;
+                }
+                match (positions.get(&(movie.media_type, movie.id)), collection_of(movie)) {
+                    (Some(position), Some(collection)) => match next_unwatched(collection, watched)
+                        .and_then(|next| collection.position(next.id))
+                    {
+                        Some(next) => position.part <= next.part,
+                        None => true,
+                    },
+                    _ => true,
+                }
+            })
+            .map(|movie| {
+                let position = positions.get(&(movie.media_type, movie.id)).cloned();
+                (movie, position)
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn movie(id: i64, release_date: &str) -> Movie {
+            Movie {
+                release_date: release_date.to_string(),
+                ..test_movie(id)
+            }
+        }
+
+        fn trilogy() -> Collection {
+            Collection {
+                id: 10,
+                name: String::from("Trilogy"),
+                overview: String::new(),
+                poster_path: None,
+                // Out of order on purpose, with a fourth part announced but undated
+                parts: vec![movie(3, "2005-05-19"), movie(1, "1999-03-31"), movie(4, ""), movie(2, "2003-05-15")],
+            }
+        }
+
+        fn collections(ids: &[i64]) -> HashMap<i64, Collection> {
+            ids.iter().map(|id| (*id, trilogy())).collect()
+        }
+
+        #[test]
+        fn positions_in_release_order() {
+            assert_eq!(trilogy().position(2).unwrap().describe(), "Part 2 of 4 in Trilogy");
+            assert_eq!(trilogy().position(4).unwrap().part, 4);
+            assert!(trilogy().position(99).is_none());
+        }
+
+        #[test]
+        fn groups_onto_earliest_part() {
+            let arranged = arrange(
+                vec![movie(3, "2005-05-19"), movie(50, "2010-01-01"), movie(2, "2003-05-15")],
+                &collections(&[2, 3]),
+                &|_| false,
+                false,
+            );
+
+            let ids: Vec<i64> = arranged.iter().map(|(movie, _)| movie.id).collect();
+            assert_eq!(ids, vec![50, 2]);
+            assert_eq!(arranged[1].1.as_ref().unwrap().part, 2);
+            assert!(arranged[0].1.is_none());
+        }
+
+        #[test]
+        fn series_are_never_grouped() {
+            let series = Movie {
+                media_type: MediaType::Tv,
+                ..movie(3, "2005-05-19")
+            };
+
+            let arranged = arrange(
+                vec![series, movie(2, "2003-05-15")],
+                &collections(&[2, 3]),
+                &|_| false,
+                false,
+            );
+
+            assert_eq!(arranged.len(), 2);
+            assert!(arranged[0].1.is_none());
+            assert_eq!(arranged[1].1.as_ref().unwrap().part, 2);
+        }
+
+        #[test]
+        fn skips_unseen_sequels() {
+            let movies = vec![movie(3, "2005-05-19"), movie(50, "2010-01-01")];
+
+            // Nothing watched - part 3 waits for part 1
+            let arranged = arrange(movies.clone(), &collections(&[3]), &|_| false, true);
+            assert_eq!(arranged.len(), 1);
+
+            // Parts 1 and 2 watched - part 3 is next up
+            let arranged = arrange(movies.clone(), &collections(&[3]), &|id| id < 3, true);
+            assert_eq!(arranged.len(), 2);
+
+            // Without the setting sequels are always shown
+            let arranged = arrange(movies, &collections(&[3]), &|_| false, false);
+            assert_eq!(arranged.len(), 2);
+        }
+
+        #[test]
+        fn next_unwatched_skips_undated() {
+            assert_eq!(next_unwatched(&trilogy(), &|_| false).unwrap().id, 1);
+            assert!(next_unwatched(&trilogy(), &|id| id < 4).is_none());
+        }
+    }
+}
+}
@@ -1,5 +1,5 @@
 #![allow(unused_imports, dead_code, unused_variables)]
-use std::{f64::consts::E, sync::Arc};
+use std::{collections::VecDeque, f64::consts::E, sync::Arc};
 
 use leptos::{html::s, server_fn::redirect, svg::view, *};
 use leptos_meta::*;
@@ -41,23 +41,46 @@ pub fn HomePage() -> impl IntoView {
                     if pending() {
                         view! { <div class="loader"></div> }.into_view()
                     } else {
-                        let session_id = session_value();
                         view! {
                             <A
                                 href=match session_value() {
-                                    Some(session_result) => {
-                                        match session_result {
-                                            Ok(session_id) => format!("/providers/{}", session_id),
-                                            Err(_) => String::from("/"),
-                                        }
-                                    }
-                                    None => String::from("/"),
+                                    Some(Ok(_)) => String::from("/providers"),
+                                    _ => String::from("/"),
                                 }
 
                                 class="btn btn-primary"
                             >
                                 "Get Started"
                             </A>
+                            <A href="/account" class="btn btn-outline-secondary">
+                                "Account"
+                            </A>
+                            <A
+                                href=match session_value() {
+                                    Some(Ok(_)) => String::from("/mood"),
+                                    _ => String::from("/"),
+                                }
+
+                                class="btn btn-outline-primary"
+                            >
+                                "Pick a Mood"
+                            </A>
+                            <A
+                                href=match session_value() {
+                                    Some(Ok(_)) => String::from("/surprise"),
+                                    _ => String::from("/"),
+                                }
+
+                                class="btn btn-outline-primary"
+                            >
+                                "Surprise Me"
+                            </A>
+                            <A href="/room" class="btn btn-outline-secondary">
+                                "Movie Night"
+                            </A>
+                            <A href="/browse/trending" class="btn btn-outline-secondary">
+                                "Browse"
+                            </A>
                         }
                             .into_view()
                     }
@@ -76,16 +99,20 @@ pub fn ProviderPage() -> impl IntoView {
     );
     let post_providers = create_server_action::<PostProviders>();
     let pending = post_providers.pending();
-    let params = use_params_map();
-    let session_id = move || {
-        params
-            .with(|params| params.get("session_id").cloned())
-            .expect("Oh noooo")
-    };
 
     let select_data_signal = SelectedData::new(true);
     provide_context(select_data_signal);
 
+    // Returning users start with the services from their taste profile already picked
+    let criteria = create_resource(|| (), |_| async move { fetch_session_criteria().await });
+    create_effect(move |_| {
+        if let Some(Ok(criteria)) = criteria.get() {
+            if let Some(providers) = criteria.watch_providers {
+                select_data_signal.data_signal.set(providers);
+            }
+        }
+    });
+
     let mut global_state = expect_context::<GlobalState>();
     view! {
         <div
@@ -100,19 +127,18 @@ pub fn ProviderPage() -> impl IntoView {
             {move || if !global_state.data_loading.get() && !select_data_signal.data_signal.get().is_empty() {
                 view! {
                     <A
-                        href=format!("/runtime/{}", session_id())
+                        href="/media"
                         class="btn btn-primary"
                         on:click=move |_| {
                             provide_context(global_state.data_loading = pending);
                             post_providers
                                 .dispatch(PostProviders {
-                                    session_id: session_id(),
                                     providers: select_data_signal.data_signal.get(),
                                 });
                         }
                     >
 
-                        "To Runtime"
+                        "Next"
                     </A>
                 }.into_view()
             }else{
@@ -123,18 +149,137 @@ pub fn ProviderPage() -> impl IntoView {
     }
 }
 
+/* Movies, series or both - series swap film runtime for episode length and season count */
+#[component]
+pub fn MediaPage() -> impl IntoView {
+    let media = create_rw_signal(MediaChoice::Movies);
+    let episode_length = create_rw_signal(EpisodeLength::Standard);
+    let seasons = create_rw_signal(None::<SeasonCount>);
+    let post_media = create_server_action::<PostMedia>();
+    let pending = post_media.pending();
+    let mut global_state = expect_context::<GlobalState>();
+
+    let choice_button = move |choice: MediaChoice, label: &'static str| {
+        let current = choice.clone();
+        view! {
+            <button
+                class=move || {
+                    if media.get() == current { "btn btn-secondary" } else { "btn btn-outline-secondary" }
+                }
+                on:click=move |_| media.set(choice.clone())
+            >
+                {label}
+            </button>
+        }
+    };
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="40%"
+            style:top="30%"
+            style:transform="translate(-20%, -25%)"
+        >
+            <h2>"What are we watching?"</h2>
+            <div>
+                {choice_button(MediaChoice::Movies, "Movies")}
+                {choice_button(MediaChoice::Series, "Series")}
+                {choice_button(MediaChoice::Both, "Both")}
+            </div>
+            {move || if media.get().includes_series() {
+                view! {
+                    <h3>"Episode length"</h3>
+                    <div>
+                        {[EpisodeLength::Short, EpisodeLength::Standard, EpisodeLength::Long]
+                            .into_iter()
+                            .map(|length| {
+                                let current = length.clone();
+                                let name = length.name();
+                                view! {
+                                    <button
+                                        class=move || {
+                                            if episode_length.get() == current { "btn btn-secondary" } else { "btn btn-outline-secondary" }
+                                        }
+                                        on:click=move |_| episode_length.set(length.clone())
+                                    >
+                                        {name}
+                                    </button>
+                                }
+                            })
+                            .collect_view()}
+                    </div>
+                    <h3>"How many seasons?"</h3>
+                    <div>
+                        {[None, Some(SeasonCount::Miniseries), Some(SeasonCount::Few), Some(SeasonCount::LongRunning)]
+                            .into_iter()
+                            .map(|count| {
+                                let current = count.clone();
+                                let name = count.as_ref().map(|count| count.name()).unwrap_or(String::from("Any"));
+                                view! {
+                                    <button
+                                        class=move || {
+                                            if seasons.get() == current { "btn btn-secondary" } else { "btn btn-outline-secondary" }
+                                        }
+                                        on:click=move |_| seasons.set(count.clone())
+                                    >
+                                        {name}
+                                    </button>
+                                }
+                            })
+                            .collect_view()}
+                    </div>
+                }.into_view()
+            } else {
+                view! {}.into_view()
+            }}
+            {move || if !global_state.data_loading.get() {
+                view! {
+                    // Film runtime only matters if movies are included
+                    <A
+                        href=move || String::from(if media.get().includes_movies() { "/runtime" } else { "/decade" })
+                        class="btn btn-primary"
+                        on:click=move |_| {
+                            provide_context(global_state.data_loading = pending);
+                            let media = media.get();
+                            let series = media.includes_series();
+                            post_media.dispatch(PostMedia {
+                                media,
+                                episode_length: series.then_some(episode_length.get()),
+                                seasons: seasons.get().filter(|_| series),
+                            });
+                        }
+                    >
+                        "Next"
+                    </A>
+                }.into_view()
+            } else {
+                view! {}.into_view()
+            }}
+        </div>
+    }
+}
+
 #[component]
 pub fn RuntimePage() -> impl IntoView {
     let (runtime, set_runtime) = create_signal(1);
     let post_runtime = create_server_action::<PostRuntime>();
     let pending = post_runtime.pending();
-    let params = use_params_map();
-    let session_id = move || {
-        params
-            .with(|params| params.get("session_id").cloned())
-            .expect("Oh noooo")
-    };
     let mut global_state = expect_context::<GlobalState>();
+
+    // Same as the providers - start from the runtime the taste profile pre-filled
+    let criteria = create_resource(|| (), |_| async move { fetch_session_criteria().await });
+    create_effect(move |_| {
+        if let Some(Ok(criteria)) = criteria.get() {
+            if let Some(saved_runtime) = criteria.runtime {
+                set_runtime(match saved_runtime {
+                    Runtime::Quick => 1,
+                    Runtime::Average => 2,
+                    Runtime::MovieNight => 3,
+                    Runtime::MartinScorsese => 4,
+                });
+            }
+        }
+    });
     view! {
         <div
             style:position="absolute"
@@ -149,7 +294,7 @@ pub fn RuntimePage() -> impl IntoView {
                         min=1
                         max=4
                         step=1
-                        bind:value=runtime
+                        prop:value=runtime
                         style:width="90%"
                         on:input=move |e| {
                             match event_target_value(&e).parse() {
@@ -178,7 +323,7 @@ pub fn RuntimePage() -> impl IntoView {
             {move || if !global_state.data_loading.get() {
                 view! {
                     <A
-                        href=format!("/decade/{}", session_id())
+                        href="/decade"
                         class="btn btn-primary"
                         on:click=move |_| {
                             provide_context(global_state.data_loading = pending);
@@ -191,7 +336,6 @@ pub fn RuntimePage() -> impl IntoView {
                             };
                             post_runtime
                                 .dispatch(PostRuntime {
-                                    session_id: session_id(),
                                     runtime: selected_runtime,
                                 });
                         }
@@ -213,12 +357,6 @@ pub fn DecadePage() -> impl IntoView {
     let (decade, set_decade) = create_signal(1);
     let post_decade = create_server_action::<PostDecade>();
     let pending = post_decade.pending();
-    let params = use_params_map();
-    let session_id = move || {
-        params
-            .with(|params| params.get("session_id").cloned())
-            .expect("Oh noooo")
-    };
     let mut global_state = expect_context::<GlobalState>();
     view! {
         <div
@@ -268,7 +406,7 @@ pub fn DecadePage() -> impl IntoView {
             {move || if !global_state.data_loading.get(){
                 view! {
                     <A
-                        href=format!("/genres/{}", session_id())
+                        href="/language"
                         class="btn btn-primary"
                         on:click=move |_| {
                             provide_context(global_state.data_loading = pending);
@@ -285,7 +423,6 @@ pub fn DecadePage() -> impl IntoView {
                             };
                             post_decade
                                 .dispatch(PostDecade {
-                                    session_id: session_id(),
                                     decade: selected_decade,
                                 });
                         }
@@ -302,17 +439,401 @@ pub fn DecadePage() -> impl IntoView {
     }
 }
 
+/* Display language plus original languages to keep or skip - leaving everything blank keeps English and any origin */
+#[component]
+pub fn LanguagePage() -> impl IntoView {
+    let languages = create_resource(|| (), |_| async move { fetch_languages().await });
+    let display = create_rw_signal(None::<String>);
+    let include = create_rw_signal(Vec::<String>::new());
+    let exclude = create_rw_signal(Vec::<String>::new());
+    let post_languages = create_server_action::<PostLanguages>();
+    let pending = post_languages.pending();
+    let mut global_state = expect_context::<GlobalState>();
+
+    let add_to = move |list: RwSignal<Vec<String>>, other: RwSignal<Vec<String>>, code: String| {
+        if code.is_empty() {
+            return;
+        }
+        // A language can't be both kept and skipped
+        other.update(|other| other.retain(|existing| existing != &code));
+        list.update(|list| {
+            if !list.contains(&code) {
+                list.push(code);
+            }
+        });
+    };
+
+    let chips = move |list: RwSignal<Vec<String>>| {
+        view! {
+            <For
+                each=move || list.get()
+                key=|code| code.clone()
+                children=move |code| {
+                    let removed = code.clone();
+                    view! {
+                        <button
+                            class="btn btn-sm btn-outline-secondary"
+                            on:click=move |_| list.update(|list| list.retain(|existing| existing != &removed))
+                        >
+                            {code} " ×"
+                        </button>
+                    }
+                }
+            />
+        }
+    };
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="40%"
+            style:top="30%"
+            style:transform="translate(-20%, -25%)"
+        >
+            <h2>"Languages"</h2>
+            <Suspense fallback=move || view! { <div class="loader"></div> }>
+                {move || languages.get().map(|result| match result {
+                    Err(err) => view! { <p>{err.to_string()}</p> }.into_view(),
+                    Ok(languages) => {
+                        let options = move || {
+                            languages
+                                .iter()
+                                .map(|language| view! {
+                                    <option value=language.iso_639_1.clone()>{language.english_name.clone()}</option>
+                                })
+                                .collect_view()
+                        };
+                        view! {
+                            <label>"Show titles and descriptions in"</label>
+                            <select
+                                class="form-select"
+                                on:change=move |e| {
+                                    let code = event_target_value(&e);
+                                    display.set(Some(code).filter(|code| !code.is_empty()));
+                                }
+                            >
+                                <option value="">"English (default)"</option>
+                                {options()}
+                            </select>
+                            <label>"Only titles originally in"</label>
+                            <select
+                                class="form-select"
+                                on:change=move |e| add_to(include, exclude, event_target_value(&e))
+                            >
+                                <option value="">"Any language"</option>
+                                {options()}
+                            </select>
+                            <div>{chips(include)}</div>
+                            <label>"Skip titles originally in"</label>
+                            <select
+                                class="form-select"
+                                on:change=move |e| add_to(exclude, include, event_target_value(&e))
+                            >
+                                <option value="">"Nothing"</option>
+                                {options()}
+                            </select>
+                            <div>{chips(exclude)}</div>
+                        }.into_view()
+                    }
+                })}
+            </Suspense>
+            {move || if !global_state.data_loading.get() {
+                view! {
+                    <A
+                        href="/rating"
+                        class="btn btn-primary"
+                        on:click=move |_| {
+                            provide_context(global_state.data_loading = pending);
+                            post_languages.dispatch(PostLanguages {
+                                languages: LanguageFilter {
+                                    display: display.get(),
+                                    include: include.get(),
+                                    exclude: exclude.get(),
+                                },
+                            });
+                        }
+                    >
+                        "Next"
+                    </A>
+                }.into_view()
+            } else {
+                view! {}.into_view()
+            }}
+        </div>
+    }
+}
+
+/* Countries whose rating systems are offered - TMDB covers more, these are the ones people ask for */
+const CERTIFICATION_COUNTRIES: [(&str, &str); 6] = [
+    ("US", "United States"),
+    ("GB", "United Kingdom"),
+    ("CA", "Canada"),
+    ("AU", "Australia"),
+    ("DE", "Germany"),
+    ("FR", "France"),
+];
+
+#[component]
+pub fn CertificationPage() -> impl IntoView {
+    let country = create_rw_signal(String::from("US"));
+    let max = create_rw_signal(None::<String>);
+    let family_mode = create_rw_signal(false);
+    let certifications = create_resource(
+        move || country.get(),
+        |country| async move { fetch_certifications(country).await },
+    );
+    let post_certification = create_server_action::<PostCertification>();
+    let pending = post_certification.pending();
+    let mut global_state = expect_context::<GlobalState>();
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="40%"
+            style:top="30%"
+            style:transform="translate(-20%, -25%)"
+        >
+            <h2>"Content rating"</h2>
+            <label>"Ratings from"</label>
+            <select
+                class="form-select"
+                on:change=move |e| {
+                    // Ratings don't carry over between countries
+                    max.set(None);
+                    country.set(event_target_value(&e));
+                }
+            >
+                {CERTIFICATION_COUNTRIES
+                    .iter()
+                    .map(|(code, name)| view! { <option value=*code>{*name}</option> })
+                    .collect_view()}
+            </select>
+            <label>"Nothing rated above"</label>
+            <Suspense fallback=move || view! { <div class="loader"></div> }>
+                {move || certifications.get().map(|result| match result {
+                    Err(err) => view! { <p>{err.to_string()}</p> }.into_view(),
+                    Ok(certifications) => view! {
+                        <select
+                            class="form-select"
+                            on:change=move |e| {
+                                let value = event_target_value(&e);
+                                max.set(Some(value).filter(|value| !value.is_empty()));
+                            }
+                        >
+                            <option value="">"No limit"</option>
+                            {certifications
+                                .into_iter()
+                                .map(|certification| view! {
+                                    <option value=certification.certification.clone() title=certification.meaning>
+                                        {certification.certification.clone()}
+                                    </option>
+                                })
+                                .collect_view()}
+                        </select>
+                    }.into_view(),
+                })}
+            </Suspense>
+            <div class="form-check">
+                <input
+                    class="form-check-input"
+                    type="checkbox"
+                    id="family-mode"
+                    prop:checked=move || family_mode.get()
+                    on:change=move |e| family_mode.set(event_target_checked(&e))
+                />
+                <label class="form-check-label" for="family-mode">
+                    "Family mode - applies to surprises and rooms you host too, and skips series"
+                </label>
+            </div>
+            {move || if !global_state.data_loading.get() {
+                view! {
+                    <A
+                        href="/people"
+                        class="btn btn-primary"
+                        on:click=move |_| {
+                            provide_context(global_state.data_loading = pending);
+                            post_certification.dispatch(PostCertification {
+                                certification: max.get().map(|max| CertificationLimit {
+                                    country: country.get(),
+                                    max,
+                                }),
+                                family_mode: family_mode.get(),
+                            });
+                        }
+                    >
+                        "Next"
+                    </A>
+                }.into_view()
+            } else {
+                view! {}.into_view()
+            }}
+        </div>
+    }
+}
+
+/* Optional - narrows the wizard to particular actors, directors and the like, or studios */
+#[component]
+pub fn CreditsPage() -> impl IntoView {
+    let person_query = create_rw_signal(String::new());
+    let company_query = create_rw_signal(String::new());
+    // (id, name) so the picks can be shown - only the IDs are posted
+    let cast = create_rw_signal(Vec::<(i64, String)>::new());
+    let crew = create_rw_signal(Vec::<(i64, String)>::new());
+    let companies = create_rw_signal(Vec::<(i64, String)>::new());
+
+    // Too short a query matches half of TMDB
+    let people = create_resource(
+        move || person_query.get(),
+        |query| async move {
+            match query.trim().len() < 2 {
+                true => Ok(vec![]),
+                false => search_people(query).await,
+            }
+        },
+    );
+    let company_results = create_resource(
+        move || company_query.get(),
+        |query| async move {
+            match query.trim().len() < 2 {
+                true => Ok(vec![]),
+                false => search_companies(query).await,
+            }
+        },
+    );
+
+    let post_credits = create_server_action::<PostCredits>();
+    let pending = post_credits.pending();
+    let mut global_state = expect_context::<GlobalState>();
+
+    let add = move |list: RwSignal<Vec<(i64, String)>>, id: i64, name: String| {
+        list.update(|list| {
+            if !list.iter().any(|(existing, _)| *existing == id) {
+                list.push((id, name));
+            }
+        });
+    };
+
+    let chips = move |list: RwSignal<Vec<(i64, String)>>| {
+        view! {
+            <For
+                each=move || list.get()
+                key=|(id, _)| *id
+                children=move |(id, name)| {
+                    view! {
+                        <button
+                            class="btn btn-sm btn-outline-secondary"
+                            on:click=move |_| list.update(|list| list.retain(|(existing, _)| *existing != id))
+                        >
+                            {name} " ×"
+                        </button>
+                    }
+                }
+            />
+        }
+    };
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="40%"
+            style:top="20%"
+            style:transform="translate(-20%, -25%)"
+        >
+            <h2>"Anyone in particular?"</h2>
+            <input
+                class="form-control"
+                type="text"
+                placeholder="Search for an actor, director, writer..."
+                on:change=move |e| person_query.set(event_target_value(&e))
+            />
+            <Suspense fallback=move || view! { <div class="loader"></div> }>
+                {move || people.get().map(|result| match result {
+                    Err(err) => view! { <p>{err.to_string()}</p> }.into_view(),
+                    Ok(people) => people
+                        .into_iter()
+                        .take(5)
+                        .map(|person| {
+                            let department = person.known_for_department.clone().unwrap_or_default();
+                            let (id, name) = (person.id, person.name.clone());
+                            let crew_name = name.clone();
+                            view! {
+                                <div>
+                                    {person.name} " " <small>{department}</small>
+                                    <button class="btn btn-sm btn-outline-primary" on:click=move |_| add(cast, id, name.clone())>
+                                        "In it"
+                                    </button>
+                                    <button class="btn btn-sm btn-outline-primary" on:click=move |_| add(crew, id, crew_name.clone())>
+                                        "Made it"
+                                    </button>
+                                </div>
+                            }
+                        })
+                        .collect_view(),
+                })}
+            </Suspense>
+            <div>{chips(cast)}{chips(crew)}</div>
+            <h2>"Any studio?"</h2>
+            <input
+                class="form-control"
+                type="text"
+                placeholder="Search for a studio"
+                on:change=move |e| company_query.set(event_target_value(&e))
+            />
+            <Suspense fallback=move || view! { <div class="loader"></div> }>
+                {move || company_results.get().map(|result| match result {
+                    Err(err) => view! { <p>{err.to_string()}</p> }.into_view(),
+                    Ok(results) => results
+                        .into_iter()
+                        .take(5)
+                        .map(|company| {
+                            let (id, name) = (company.id, company.name.clone());
+                            view! {
+                                <div>
+                                    {company.name} " " <small>{company.origin_country}</small>
+                                    <button class="btn btn-sm btn-outline-primary" on:click=move |_| add(companies, id, name.clone())>
+                                        "Add"
+                                    </button>
+                                </div>
+                            }
+                        })
+                        .collect_view(),
+                })}
+            </Suspense>
+            <div>{chips(companies)}</div>
+            {move || if !global_state.data_loading.get() {
+                view! {
+                    <A
+                        href="/genres"
+                        class="btn btn-primary"
+                        on:click=move |_| {
+                            provide_context(global_state.data_loading = pending);
+                            let ids = |list: RwSignal<Vec<(i64, String)>>| {
+                                list.get().into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+                            };
+                            post_credits.dispatch(PostCredits {
+                                credits: CreditFilter {
+                                    cast: ids(cast),
+                                    crew: ids(crew),
+                                    companies: ids(companies),
+                                },
+                            });
+                        }
+                    >
+                        "Next"
+                    </A>
+                }.into_view()
+            } else {
+                view! {}.into_view()
+            }}
+        </div>
+    }
+}
+
 #[component]
 pub fn GenrePage() -> impl IntoView {
     let genres = create_resource(|| (), |_| async move { fetch_genres().await });
     let post_genres = create_server_action::<PostGenres>();
     let pending = post_genres.pending();
-    let params = use_params_map();
-    let session_id = move || {
-        params
-            .with(|params| params.get("session_id").cloned())
-            .expect("Oh noooo")
-    };
     let mut global_state = expect_context::<GlobalState>();
     // <GridPage resource=genres />
 
@@ -332,13 +853,12 @@ pub fn GenrePage() -> impl IntoView {
             {move || if !global_state.data_loading.get() && !select_data_signal.data_signal.get().is_empty(){
                 view! {
                     <A
-                        href=format!("/recommend/{}", session_id())
+                        href="/recommend"
                         class="btn btn-primary"
                         on:click=move |_| {
                             provide_context(global_state.data_loading = pending);
                             post_genres
                                 .dispatch(PostGenres {
-                                    session_id: session_id(),
                                     genres: select_data_signal.data_signal.get(),
                                 });
                         }
@@ -358,12 +878,6 @@ pub fn GenrePage() -> impl IntoView {
 
 #[component]
 pub fn RecommendationPage() -> impl IntoView {
-    let params = use_params_map();
-    let session_id = move || {
-        params
-            .with(|params| params.get("session_id").cloned())
-            .expect("Oh noooo")
-    };
     let global_state = expect_context::<GlobalState>();
 
     let (reload_page, set_reload_page) = create_signal(false);
@@ -371,6 +885,9 @@ pub fn RecommendationPage() -> impl IntoView {
     let post_feedback = create_server_action::<PostFeedback>();
     let pending = post_feedback.pending();
 
+    let create_share_link = create_server_action::<CreateShareLink>();
+    let share_link = create_share_link.value();
+
     let select_data_signal = SelectedData::new(false);
     provide_context(select_data_signal);
     view! {
@@ -387,7 +904,7 @@ pub fn RecommendationPage() -> impl IntoView {
             }else{
                     let recommendations = create_resource(
                         || (),
-                        move |_| async move { fetch_recommendations(session_id()).await },
+                        move |_| async move { fetch_recommendations().await },
                     );
                     view! {<GridPage
                         resource=recommendations
@@ -402,22 +919,17 @@ pub fn RecommendationPage() -> impl IntoView {
                                 match recommendations.get() {
                                     Some(data) => {match data {
                                         Ok(reccs) => {
-                                            let mut like = Vec::<i64>::new();
-                                            let mut dislike = Vec::<i64>::new();
-
-                                            for rec in reccs{
-                                                if rec.liked.get() {
-                                                    like.push(rec.movie.id.clone());
-
+                                            let decisions = reccs.iter().filter_map(|rec| {
+                                                match (rec.liked.get(), rec.disliked.get()) {
+                                                    (true, _) => Some((rec.movie.id, rec.movie.media_type, true)),
+                                                    (_, true) => Some((rec.movie.id, rec.movie.media_type, false)),
+                                                    _ => None,
                                                 }
-                                                if rec.disliked.get() {
-                                                    dislike.push(rec.movie.id.clone());
-                                                }
-                                            }
-                                            let feedback=Feedback{like: Some(like), dislike: Some(dislike)};
+                                            });
+                                            let (feedback, show_feedback) = Feedback::split(decisions);
                                             post_feedback.dispatch(PostFeedback{
-                                                session_id: session_id(),
-                                                feedback
+                                                feedback,
+                                                show_feedback,
                                             });
                                             set_reload_page(true);
                                         },
@@ -425,7 +937,19 @@ pub fn RecommendationPage() -> impl IntoView {
                                     }},
                                     None => {}
                                 }
-                            }}>"Refresh Recommendations"</button>}
+                            }}>"Refresh Recommendations"</button>
+                            <button class="btn btn-outline-secondary" on:click=move |_| {
+                                create_share_link.dispatch(CreateShareLink {});
+                            }>"Share"</button>
+                            <A href="/watchlist" class="btn btn-outline-primary">"Watchlist"</A>
+                            <A href="/swipe" class="btn btn-outline-primary">"One at a Time"</A>
+                            {move || match share_link() {
+                                Some(Ok(share_path)) => view! {
+                                    <A href=share_path.clone()>{share_path}</A>
+                                }.into_view(),
+                                _ => view! {}.into_view()
+                            }}
+                        }
                             .into_view()
                     }else{
                         view! {}.into_view()
@@ -436,3 +960,1049 @@ pub fn RecommendationPage() -> impl IntoView {
             </div>
     }
 }
+
+// Fetch the next batch once the queue gets this short, so there's never a wait between cards
+const SWIPE_PREFETCH_THRESHOLD: usize = 3;
+// How far (in px) a touch has to travel sideways to count as a swipe
+const SWIPE_DISTANCE: i32 = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SwipeDecision {
+    Like,
+    Skip,
+    Dislike,
+}
+
+impl SwipeDecision {
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "ArrowRight" => Some(SwipeDecision::Like),
+            "ArrowLeft" => Some(SwipeDecision::Dislike),
+            "ArrowUp" | "ArrowDown" => Some(SwipeDecision::Skip),
+            _ => None,
+        }
+    }
+
+    /* Anything short of a full swipe is treated as a tap and ignored */
+    fn from_swipe(distance: i32) -> Option<Self> {
+        match distance {
+            distance if distance >= SWIPE_DISTANCE => Some(SwipeDecision::Like),
+            distance if distance <= -SWIPE_DISTANCE => Some(SwipeDecision::Dislike),
+            _ => None,
+        }
+    }
+
+    /* Skipping doesn't say anything about the movie, so it isn't sent as feedback */
+    fn liked(self) -> Option<bool> {
+        match self {
+            SwipeDecision::Like => Some(true),
+            SwipeDecision::Dislike => Some(false),
+            SwipeDecision::Skip => None,
+        }
+    }
+}
+
+/* One recommendation at a time - every decision is posted straight away so the next batch already reflects it */
+#[component]
+pub fn SwipePage() -> impl IntoView {
+    let queue = create_rw_signal(VecDeque::<MovieRecommendation>::new());
+    let seen = create_rw_signal(Vec::<(MediaType, i64)>::new());
+    let fetching = create_rw_signal(false);
+    let exhausted = create_rw_signal(false);
+    // Discover page the queue is being filled from
+    let page = create_rw_signal(1_u32);
+    let error_message = create_rw_signal(None::<String>);
+    let touch_start = store_value(None::<i32>);
+
+    let prefetch = move || {
+        if fetching.get_untracked()
+            || exhausted.get_untracked()
+            || queue.with_untracked(|queue| queue.len()) > SWIPE_PREFETCH_THRESHOLD
+        {
+            return;
+        }
+        fetching.set(true);
+
+        let mut known = seen.get_untracked();
+        queue.with_untracked(|queue| known.extend(queue.iter().map(|rec| (rec.movie.media_type, rec.movie.id))));
+
+        spawn_local(async move {
+            let start_page = page.get_untracked();
+            let mut current_page = start_page;
+
+            loop {
+                match fetch_next_recommendations(known.clone(), current_page).await {
+                    Ok(batch) => {
+                        let fresh: Vec<MovieRecommendation> = batch
+                            .into_iter()
+                            .filter(|rec| !known.contains(&(rec.movie.media_type, rec.movie.id)))
+                            .collect();
+
+                        if !fresh.is_empty() {
+                            queue.update(|queue| queue.extend(fresh));
+                        } else if current_page == start_page {
+                            // Everything on this page has been seen - move on to the next one
+                            current_page += 1;
+                            page.set(current_page);
+                            continue;
+                        } else {
+                            // Even a page nobody has looked at yet had nothing - that's the end of the results
+                            exhausted.set(true);
+                        }
+                    }
+                    Err(err) => error_message.set(Some(err.to_string())),
+                }
+                break;
+            }
+            fetching.set(false);
+        });
+    };
+
+    let decide = move |decision: SwipeDecision| {
+        let current = match queue.try_update(|queue| queue.pop_front()).flatten() {
+            Some(current) => current,
+            None => return,
+        };
+        seen.update(|seen| seen.push((current.movie.media_type, current.movie.id)));
+
+        match decision.liked() {
+            Some(liked) => spawn_local(async move {
+                match post_swipe(current.movie.id, current.movie.media_type, liked).await {
+                    Ok(_) => exhausted.set(false),
+                    Err(err) => error_message.set(Some(err.to_string())),
+                }
+                prefetch();
+            }),
+            None => prefetch(),
+        }
+    };
+
+    // Effects only run in the browser, so the first batch isn't fetched during SSR
+    create_effect(move |_| prefetch());
+
+    let keydown = window_event_listener(ev::keydown, move |ev| {
+        if let Some(decision) = SwipeDecision::from_key(&ev.key()) {
+            ev.prevent_default();
+            decide(decision);
+        }
+    });
+    on_cleanup(move || keydown.remove());
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="50%"
+            style:transform="translate(-50%, 5%)"
+            style:width="20rem"
+        >
+            {move || match queue.with(|queue| queue.front().cloned()) {
+                Some(current) => {
+                    view! {
+                        <div
+                            class="card text-bg-dark"
+                            on:touchstart=move |ev: ev::TouchEvent| {
+                                touch_start
+                                    .set_value(ev.changed_touches().get(0).map(|touch| touch.client_x()));
+                            }
+                            on:touchend=move |ev: ev::TouchEvent| {
+                                let start = touch_start.get_value();
+                                let end = ev.changed_touches().get(0).map(|touch| touch.client_x());
+                                if let (Some(start), Some(end)) = (start, end) {
+                                    if let Some(decision) = SwipeDecision::from_swipe(end - start) {
+                                        decide(decision);
+                                    }
+                                }
+                            }
+                        >
+                            <img
+                                src=current.get_logo_path()
+                                class="card-img-top"
+                                style:width="100%"
+                                style:height="auto"
+                            />
+                            <div class="card-header">
+                                <h5 class="card-title">{current.get_display()}</h5>
+                            </div>
+                            {current.get_body()}
+                            <div class="card-footer">
+                                <button
+                                    class="btn btn-outline-danger"
+                                    on:click=move |_| decide(SwipeDecision::Dislike)
+                                >
+                                    "Dislike"
+                                </button>
+                                <button
+                                    class="btn btn-outline-secondary"
+                                    on:click=move |_| decide(SwipeDecision::Skip)
+                                >
+                                    "Skip"
+                                </button>
+                                <button
+                                    class="btn btn-outline-success"
+                                    on:click=move |_| decide(SwipeDecision::Like)
+                                >
+                                    "Like"
+                                </button>
+                            </div>
+                        </div>
+                    }
+                        .into_view()
+                }
+                None if fetching.get() => view! { <div class="loader"></div> }.into_view(),
+                None => {
+                    view! {
+                        <h2>"That's everything for now"</h2>
+                        <A href="/recommend" class="btn btn-primary">"Back to the grid"</A>
+                    }
+                        .into_view()
+                }
+            }}
+            <p class="text-muted">"← dislike · ↑ skip · like →"</p>
+            {move || error_message.get().map(|message| view! { <p class="text-danger">{message}</p> })}
+        </div>
+    }
+}
+
+/* One click, one movie - "Try Another" remembers what's already been shown */
+#[component]
+pub fn SurprisePage() -> impl IntoView {
+    let seen = create_rw_signal(Vec::<(MediaType, i64)>::new());
+    let (attempt, set_attempt) = create_signal(0);
+
+    let select_data_signal = SelectedData::new(false);
+    provide_context(select_data_signal);
+
+    let surprise = create_resource(
+        move || attempt.get(),
+        move |_| async move { surprise_me(seen.get_untracked()).await },
+    );
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="50%"
+            style:transform="translate(-50%, 5%)"
+        >
+            <Suspense fallback=move || view! { <div class="loader"></div> }>
+                {move || match surprise.get() {
+                    None => view! {}.into_view(),
+                    Some(Err(err)) => view! { <h2>{err.to_string()}</h2> }.into_view(),
+                    Some(Ok(recommendation)) => {
+                        view! {
+                            <Grid>
+                                <Card card_data=vec![recommendation]/>
+                            </Grid>
+                        }
+                            .into_view()
+                    }
+                }}
+            </Suspense>
+            <button
+                class="btn btn-primary"
+                on:click=move |_| {
+                    if let Some(Ok(current)) = surprise.get() {
+                        seen.update(|seen| seen.push((current.movie.media_type, current.movie.id)));
+                    }
+                    set_attempt.update(|attempt| *attempt += 1);
+                }
+            >
+                "Try Another"
+            </button>
+            <A href="/" class="btn btn-outline-secondary">"Home"</A>
+        </div>
+    }
+}
+
+/* Read-only view of someone else's recommendations - reached through a share link */
+#[component]
+pub fn SharedRecommendationPage() -> impl IntoView {
+    let params = use_params_map();
+    let share_token = move || {
+        params
+            .with(|params| params.get("share_token").cloned())
+            .unwrap_or_default()
+    };
+
+    let select_data_signal = SelectedData::new(false);
+    provide_context(select_data_signal);
+
+    let recommendations = create_resource(
+        || (),
+        move |_| async move { fetch_shared_recommendations(share_token()).await },
+    );
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="7%"
+            style:right="5%"
+            style:transform="translate(0%, 5%)"
+        >
+            <GridPage resource=recommendations/>
+        </div>
+    }
+}
+
+/* Optional sign in - a signed in session is pre-filled from the user's taste profile on the next visit */
+#[component]
+pub fn AccountPage() -> impl IntoView {
+    let register = create_server_action::<Register>();
+    let login = create_server_action::<Login>();
+    let logout = create_server_action::<Logout>();
+
+    let current_user = create_resource(
+        move || {
+            (
+                register.version().get(),
+                login.version().get(),
+                logout.version().get(),
+            )
+        },
+        |_| async move { fetch_current_user().await },
+    );
+
+    let error_message = move || {
+        [register.value().get(), login.value().get()]
+            .into_iter()
+            .find_map(|value| match value {
+                Some(Err(err)) => Some(err.to_string()),
+                _ => None,
+            })
+    };
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="40%"
+            style:top="20%"
+            style:transform="translate(-20%, 0%)"
+        >
+            <Suspense fallback=move || view! { <div class="loader"></div> }>
+                {move || match current_user.get() {
+                    Some(Ok(Some(username))) => {
+                        view! {
+                            <h2>"Signed in as " {username}</h2>
+                            <A href="/providers" class="btn btn-primary">
+                                "Get Started"
+                            </A>
+                            <button
+                                class="btn btn-outline-secondary"
+                                on:click=move |_| logout.dispatch(Logout {})
+                            >
+                                "Sign Out"
+                            </button>
+                        }
+                            .into_view()
+                    }
+                    _ => {
+                        view! {
+                            <h2>"Sign in"</h2>
+                            <ActionForm action=login>
+                                <input type="text" name="username" placeholder="Username" class="form-control"/>
+                                <input type="password" name="password" placeholder="Password" class="form-control"/>
+                                <input type="submit" value="Sign In" class="btn btn-primary"/>
+                            </ActionForm>
+                            <h2>"Create an account"</h2>
+                            <ActionForm action=register>
+                                <input type="text" name="username" placeholder="Username" class="form-control"/>
+                                <input type="password" name="password" placeholder="Password" class="form-control"/>
+                                <input type="submit" value="Register" class="btn btn-secondary"/>
+                            </ActionForm>
+                            {move || error_message().map(|message| view! { <p class="text-danger">{message}</p> })}
+                        }
+                            .into_view()
+                    }
+                }}
+            </Suspense>
+        </div>
+    }
+}
+
+/* Saved movies with their current streaming availability - un-saving a card removes it on the next visit */
+#[component]
+pub fn WatchlistPage() -> impl IntoView {
+    let select_data_signal = SelectedData::new(false);
+    provide_context(select_data_signal);
+
+    let watchlist = create_resource(|| (), |_| async move { fetch_watchlist().await });
+    let dismiss = create_server_action::<DismissAvailabilityAlerts>();
+    let alerts = create_resource(
+        move || dismiss.version().get(),
+        |_| async move { fetch_availability_alerts().await },
+    );
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="7%"
+            style:right="5%"
+            style:transform="translate(0%, 5%)"
+        >
+            <Suspense fallback=move || view! { <div class="loader"></div> }>
+                {move || match alerts.get() {
+                    Some(Ok(alerts)) if !alerts.is_empty() => {
+                        view! {
+                            <div class="alert alert-info">
+                                <h5>
+                                    "Newly available "
+                                    <span class="badge text-bg-primary">{alerts.len()}</span>
+                                </h5>
+                                <ul class="mb-2">
+                                    {alerts
+                                        .into_iter()
+                                        .map(|alert| view! {
+                                            <li>{format!("{} is now on {}", alert.title, alert.provider_name)}</li>
+                                        })
+                                        .collect_view()}
+                                </ul>
+                                <button
+                                    class="btn btn-sm btn-outline-secondary"
+                                    on:click=move |_| dismiss.dispatch(DismissAvailabilityAlerts {})
+                                >
+                                    "Dismiss"
+                                </button>
+                            </div>
+                        }
+                            .into_view()
+                    }
+                    _ => view! {}.into_view(),
+                }}
+            </Suspense>
+            {move || match watchlist.get() {
+                Some(Ok(saved)) if saved.is_empty() => {
+                    view! { <h2>"Nothing saved yet - hit Save on a recommendation to keep it here"</h2> }
+                        .into_view()
+                }
+                _ => view! { <GridPage resource=watchlist/> }.into_view(),
+            }}
+            <A href="/recommend" class="btn btn-primary">"Back to Recommendations"</A>
+        </div>
+    }
+}
+
+/* One movie, and its franchise if it has one - reached from a card's "part x of y" */
+#[component]
+pub fn MovieDetailPage() -> impl IntoView {
+    let params = use_params_map();
+    let movie_id = move || {
+        params
+            .with(|params| params.get("id").and_then(|id| id.parse::<i64>().ok()))
+            .unwrap_or_default()
+    };
+
+    let detail = create_resource(movie_id, |movie_id| async move { fetch_movie_detail(movie_id).await });
+    let save_collection = create_server_action::<SaveCollection>();
+    let saved = save_collection.value();
+    let skip_sequels = create_server_action::<PostSkipUnseenSequels>();
+    let criteria = create_resource(|| (), |_| async move { fetch_session_criteria().await });
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="20%"
+            style:right="20%"
+            style:transform="translate(0%, 5%)"
+        >
+            <Suspense fallback=move || view! { <div class="loader"></div> }>
+                {move || detail.get().map(|result| match result {
+                    Err(err) => view! { <p>{err.to_string()}</p> }.into_view(),
+                    Ok(detail) => {
+                        let movie = detail.movie.clone();
+                        view! {
+                            <h2>{movie.title.clone()}</h2>
+                            <p><small class="text-body-secondary">{movie.release_date.clone()}</small></p>
+                            <p>{movie.overview.clone()}</p>
+                            {match detail.collection.clone() {
+                                None => view! {}.into_view(),
+                                Some(collection) => {
+                                    let collection_id = collection.id;
+                                    let watched = detail.watched.clone();
+                                    view! {
+                                        <h3>{collection.name.clone()}</h3>
+                                        <ol class="list-group list-group-numbered">
+                                            {collection
+                                                .in_release_order()
+                                                .into_iter()
+                                                .map(|part| {
+                                                    let seen = watched.contains(&part.id);
+                                                    let current = part.id == movie.id;
+                                                    view! {
+                                                        <li class=if current { "list-group-item active" } else { "list-group-item" }>
+                                                            {part.title}
+                                                            " "
+                                                            <small>{part.release_date}</small>
+                                                            {seen.then(|| view! { <span class="badge text-bg-success">"Watched"</span> })}
+                                                        </li>
+                                                    }
+                                                })
+                                                .collect_view()}
+                                        </ol>
+                                        <button
+                                            class="btn btn-primary"
+                                            on:click=move |_| save_collection.dispatch(SaveCollection { collection_id })
+                                        >
+                                            "Watch the whole collection"
+                                        </button>
+                                        {move || match saved.get() {
+                                            Some(Ok(_)) => view! { <span>"Added to your watchlist, in order"</span> }.into_view(),
+                                            Some(Err(err)) => view! { <span>{err.to_string()}</span> }.into_view(),
+                                            None => view! {}.into_view(),
+                                        }}
+                                    }.into_view()
+                                }
+                            }}
+                        }.into_view()
+                    }
+                })}
+            </Suspense>
+            <div class="form-check">
+                <input
+                    class="form-check-input"
+                    type="checkbox"
+                    id="skip-sequels"
+                    prop:checked=move || {
+                        criteria.get().and_then(|criteria| criteria.ok()).map(|criteria| criteria.skip_unseen_sequels).unwrap_or(false)
+                    }
+                    on:change=move |e| skip_sequels.dispatch(PostSkipUnseenSequels { skip: event_target_checked(&e) })
+                />
+                <label class="form-check-label" for="skip-sequels">
+                    "Hold back sequels until I've watched the earlier parts"
+                </label>
+            </div>
+            <A href="/recommend" class="btn btn-outline-secondary">"Back to Recommendations"</A>
+        </div>
+    }
+}
+
+/* No wizard - just what's popular or in cinemas, with a tab for each list */
+#[component]
+pub fn BrowsePage(list: BrowseList) -> impl IntoView {
+    let select_data_signal = SelectedData::new(false);
+    provide_context(select_data_signal);
+
+    let movies = create_resource(|| (), move |_| async move { fetch_browse_list(list).await });
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="7%"
+            style:right="5%"
+            style:transform="translate(0%, 5%)"
+        >
+            <ul class="nav nav-tabs">
+                {BrowseList::all()
+                    .into_iter()
+                    .map(|tab| view! {
+                        <li class="nav-item">
+                            <A href=tab.href() class=if tab == list { "nav-link active" } else { "nav-link" }>
+                                {tab.title()}
+                            </A>
+                        </li>
+                    })
+                    .collect_view()}
+            </ul>
+            {move || match movies.get() {
+                Some(Ok(found)) if found.is_empty() => {
+                    view! { <h2>"Nothing here on your services right now"</h2> }.into_view()
+                }
+                _ => view! { <GridPage resource=movies/> }.into_view(),
+            }}
+            <A href="/" class="btn btn-outline-secondary">"Home"</A>
+        </div>
+    }
+}
+
+/* Alternative entry point - a mood stands in for the runtime and genre steps */
+#[component]
+pub fn MoodPage() -> impl IntoView {
+    let moods = create_resource(|| (), |_| async move { fetch_moods().await });
+    let providers = create_resource(|| (), |_| async move { fetch_simple_watch_providers().await });
+    let decades = create_resource(|| (), |_| async move { fetch_decades().await });
+    let post_mood = create_server_action::<PostMood>();
+    let pending = post_mood.pending();
+    let mut global_state = expect_context::<GlobalState>();
+
+    let selected_mood = create_rw_signal(None::<String>);
+    let selected_providers = create_rw_signal(Vec::<i32>::new());
+    let selected_decade = create_rw_signal(Decade::Recent);
+
+    // Signed in users start with their saved streaming services already picked
+    let criteria = create_resource(|| (), |_| async move { fetch_session_criteria().await });
+    create_effect(move |_| {
+        if let Some(Ok(criteria)) = criteria.get() {
+            if let Some(providers) = criteria.watch_providers {
+                selected_providers.set(providers);
+            }
+        }
+    });
+
+    let dispatch_mood = move || {
+        if let Some(mood_id) = selected_mood.get_untracked() {
+            post_mood.dispatch(PostMood {
+                mood_id,
+                providers: selected_providers.get_untracked(),
+                decade: selected_decade.get_untracked(),
+            });
+        }
+    };
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="7%"
+            style:right="5%"
+            style:transform="translate(0%, 5%)"
+        >
+            <h2>"What are you in the mood for?"</h2>
+            <Suspense fallback=move || view! { <div class="loader"></div> }>
+                <div class="row row-cols-1 row-cols-md-3 g-3">
+                    {move || {
+                        moods
+                            .get()
+                            .and_then(|moods| moods.ok())
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|mood| {
+                                let mood_id = mood.id.clone();
+                                let selected_id = mood.id.clone();
+                                view! {
+                                    <div class="col">
+                                        <div
+                                            class="card h-100"
+                                            class=(
+                                                "text-bg-secondary",
+                                                move || selected_mood.get().as_ref() == Some(&selected_id),
+                                            )
+                                            on:click=move |_| selected_mood.set(Some(mood_id.clone()))
+                                        >
+                                            <div class="card-body">
+                                                <h5 class="card-title">{mood.name}</h5>
+                                                <p class="card-text">{mood.description}</p>
+                                            </div>
+                                        </div>
+                                    </div>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </div>
+                <h3>"Your streaming services"</h3>
+                {move || {
+                    providers
+                        .get()
+                        .and_then(|providers| providers.ok())
+                        .map(|providers| {
+                            let options = providers
+                                .into_iter()
+                                .map(|provider| (provider.provider_id, provider.provider_name))
+                                .collect::<Vec<_>>();
+                            view! { <RoomPicker options=options selected=selected_providers/> }
+                        })
+                }}
+                <h3>"From"</h3>
+                <div>
+                    {move || {
+                        decades
+                            .get()
+                            .and_then(|decades| decades.ok())
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|decade| {
+                                let current = decade.clone();
+                                let display = decade.get_display();
+                                view! {
+                                    <button
+                                        class=move || {
+                                            if selected_decade.get() == current {
+                                                "btn btn-secondary"
+                                            } else {
+                                                "btn btn-outline-secondary"
+                                            }
+                                        }
+                                        on:click=move |_| selected_decade.set(decade.clone())
+                                    >
+                                        {display}
+                                    </button>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </div>
+            </Suspense>
+            {move || if selected_mood.get().is_some() && !selected_providers.get().is_empty() {
+                view! {
+                    <A
+                        href="/recommend"
+                        class="btn btn-primary"
+                        on:click=move |_| {
+                            provide_context(global_state.data_loading = pending);
+                            dispatch_mood();
+                        }
+                    >
+                        "Get Recommendations"
+                    </A>
+                    // The rest of the wizard can still override anything the mood filled in
+                    <A href="/runtime" class="btn btn-outline-secondary" on:click=move |_| dispatch_mood()>
+                        "Fine-tune"
+                    </A>
+                }.into_view()
+            } else {
+                view! {}.into_view()
+            }}
+        </div>
+    }
+}
+
+/* Entry point for movie night - start a room or join one with a code */
+#[component]
+pub fn RoomLobbyPage() -> impl IntoView {
+    let create_room = create_server_action::<CreateRoom>();
+    let join_room = create_server_action::<JoinRoom>();
+
+    let navigate = use_navigate();
+    create_effect(move |_| {
+        let code = match (create_room.value().get(), join_room.value().get()) {
+            (Some(Ok(code)), _) | (_, Some(Ok(code))) => code,
+            _ => return,
+        };
+        navigate(&format!("/room/{}", code), Default::default());
+    });
+
+    let error_message = move || {
+        [create_room.value().get(), join_room.value().get()]
+            .into_iter()
+            .find_map(|value| match value {
+                Some(Err(err)) => Some(err.to_string()),
+                _ => None,
+            })
+    };
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="40%"
+            style:top="20%"
+            style:transform="translate(-20%, 0%)"
+        >
+            <h2>"Movie night"</h2>
+            <ActionForm action=create_room>
+                <input type="text" name="name" placeholder="Your name" class="form-control"/>
+                <input type="submit" value="Start a Room" class="btn btn-primary"/>
+            </ActionForm>
+            <h2>"Join a room"</h2>
+            <ActionForm action=join_room>
+                <input type="text" name="code" placeholder="Room code" class="form-control"/>
+                <input type="text" name="name" placeholder="Your name" class="form-control"/>
+                <input type="submit" value="Join" class="btn btn-secondary"/>
+            </ActionForm>
+            {move || error_message().map(|message| view! { <p class="text-danger">{message}</p> })}
+        </div>
+    }
+}
+
+/* Toggle buttons for picking providers/genres inside a room - the wizard grids are one selection per page */
+#[component]
+fn RoomPicker(options: Vec<(i32, String)>, selected: RwSignal<Vec<i32>>) -> impl IntoView {
+    view! {
+        <div>
+            {options
+                .into_iter()
+                .map(|(id, name)| {
+                    view! {
+                        <button
+                            class=move || {
+                                if selected.get().contains(&id) {
+                                    "btn btn-secondary"
+                                } else {
+                                    "btn btn-outline-secondary"
+                                }
+                            }
+                            on:click=move |_| selected.update(|selected| selected.add_or_remove(id))
+                        >
+                            {name}
+                        </button>
+                    }
+                })
+                .collect_view()}
+        </div>
+    }
+}
+
+/* Latest event pushed for the room over server-sent events - the Refresh button covers browsers where the stream drops */
+fn use_room_events(code: impl Fn() -> String + 'static) -> ReadSignal<Option<RoomEvent>> {
+    let (latest, set_latest) = create_signal(None::<RoomEvent>);
+
+    #[cfg(feature = "hydrate")]
+    {
+        use wasm_bindgen::{closure::Closure, JsCast};
+        use web_sys::{EventSource, MessageEvent};
+
+        let connection = store_value(None::<(EventSource, Closure<dyn FnMut(MessageEvent)>)>);
+        let close = move || {
+            connection.update_value(|connection| {
+                if let Some((source, _)) = connection.take() {
+                    source.close();
+                }
+            })
+        };
+
+        create_effect(move |_| {
+            let code = code();
+            close();
+
+            let source = match EventSource::new(&format!("/api/rooms/{}/events", code)) {
+                Ok(source) => source,
+                Err(err) => {
+                    log::error!("Error opening room events: {:?}", err);
+                    return;
+                }
+            };
+
+            let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |message: MessageEvent| {
+                let event = message
+                    .data()
+                    .as_string()
+                    .and_then(|data| serde_json::from_str::<RoomEvent>(&data).ok());
+                if event.is_some() {
+                    set_latest.set(event);
+                }
+            });
+            source.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+            connection.set_value(Some((source, on_message)));
+        });
+
+        on_cleanup(close);
+    }
+
+    latest
+}
+
+#[component]
+pub fn RoomPage() -> impl IntoView {
+    let params = use_params_map();
+    let code = move || {
+        params
+            .with(|params| params.get("code").cloned())
+            .unwrap_or_default()
+    };
+
+    let post_preferences = create_server_action::<PostRoomPreferences>();
+    let start_voting = create_server_action::<StartRoomVoting>();
+    let vote = create_server_action::<VoteInRoom>();
+    let close_voting = create_server_action::<CloseRoomVoting>();
+    let (refresh, set_refresh) = create_signal(0);
+    let room_event = use_room_events(code);
+
+    let room = create_resource(
+        move || {
+            (
+                code(),
+                refresh.get(),
+                room_event.get(),
+                post_preferences.version().get(),
+                start_voting.version().get(),
+                vote.version().get(),
+                close_voting.version().get(),
+            )
+        },
+        |(code, ..)| async move { fetch_room(code).await },
+    );
+
+    let providers = create_resource(|| (), |_| async move { fetch_simple_watch_providers().await });
+    let genres = create_resource(|| (), |_| async move { fetch_genres().await });
+    let selected_providers = create_rw_signal(Vec::<i32>::new());
+    let selected_genres = create_rw_signal(Vec::<i32>::new());
+
+    let error_message = move || {
+        [
+            post_preferences.value().get(),
+            start_voting.value().get(),
+            vote.value().get(),
+            close_voting.value().get(),
+        ]
+        .into_iter()
+        .find_map(|value| match value {
+            Some(Err(err)) => Some(err.to_string()),
+            _ => None,
+        })
+    };
+
+    view! {
+        <div
+            style:position="absolute"
+            style:left="7%"
+            style:right="5%"
+            style:transform="translate(0%, 5%)"
+        >
+            <Suspense fallback=move || view! { <div class="loader"></div> }>
+                {move || match room.get() {
+                    None => view! {}.into_view(),
+                    Some(Err(err)) => {
+                        view! {
+                            <h2>{err.to_string()}</h2>
+                            <A href="/room">"Back"</A>
+                        }
+                            .into_view()
+                    }
+                    Some(Ok(room)) => {
+                        let is_host = room.is_host;
+                        let my_vote = room.my_vote;
+                        view! {
+                            <h2>"Room " {room.code.clone()}</h2>
+                            <ul class="list-group list-group-horizontal">
+                                {room
+                                    .members
+                                    .into_iter()
+                                    .map(|member| {
+                                        let status = match room.status {
+                                            RoomStatus::Gathering if member.ready => "ready",
+                                            RoomStatus::Gathering => "picking",
+                                            _ if member.voted => "voted",
+                                            _ => "deciding",
+                                        };
+                                        view! {
+                                            <li class="list-group-item list-group-item-dark">
+                                                {member.name} " (" {status} ")"
+                                            </li>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </ul>
+                            {match room.status {
+                                RoomStatus::Gathering => {
+                                    view! {
+                                        <h3>"Your streaming services"</h3>
+                                        {move || {
+                                            providers
+                                                .get()
+                                                .and_then(|providers| providers.ok())
+                                                .map(|providers| {
+                                                    let options = providers
+                                                        .into_iter()
+                                                        .map(|provider| (provider.provider_id, provider.provider_name))
+                                                        .collect();
+                                                    view! { <RoomPicker options=options selected=selected_providers/> }
+                                                })
+                                        }}
+                                        <h3>"Genres you're in the mood for"</h3>
+                                        {move || {
+                                            genres
+                                                .get()
+                                                .and_then(|genres| genres.ok())
+                                                .map(|genres| {
+                                                    let options = genres
+                                                        .into_iter()
+                                                        .map(|genre| (genre.id, genre.name))
+                                                        .collect();
+                                                    view! { <RoomPicker options=options selected=selected_genres/> }
+                                                })
+                                        }}
+                                        <button
+                                            class="btn btn-primary"
+                                            on:click=move |_| {
+                                                post_preferences
+                                                    .dispatch(PostRoomPreferences {
+                                                        code: code(),
+                                                        providers: selected_providers.get(),
+                                                        genres: selected_genres.get(),
+                                                    })
+                                            }
+                                        >
+                                            "Save My Picks"
+                                        </button>
+                                        {is_host
+                                            .then(|| {
+                                                view! {
+                                                    <button
+                                                        class="btn btn-success"
+                                                        on:click=move |_| {
+                                                            start_voting.dispatch(StartRoomVoting { code: code() })
+                                                        }
+                                                    >
+                                                        "Find Movies"
+                                                    </button>
+                                                }
+                                            })}
+                                    }
+                                        .into_view()
+                                }
+                                RoomStatus::Voting => {
+                                    view! {
+                                        <div class="row row-cols-1 row-cols-md-5 g-5">
+                                            {room
+                                                .candidates
+                                                .into_iter()
+                                                .map(|candidate| {
+                                                    let movie_id = candidate.movie.id;
+                                                    view! {
+                                                        <div class="col">
+                                                            <div class="card h-100" style:width="13rem">
+                                                                <div class="card-header">
+                                                                    <h5 class="card-title">{candidate.movie.title}</h5>
+                                                                </div>
+                                                                <div class="card-footer">
+                                                                    <button
+                                                                        class=if my_vote == Some(movie_id) {
+                                                                            "btn btn-success"
+                                                                        } else {
+                                                                            "btn btn-outline-success"
+                                                                        }
+                                                                        on:click=move |_| {
+                                                                            vote.dispatch(VoteInRoom { code: code(), movie_id })
+                                                                        }
+                                                                    >
+                                                                        "Vote (" {candidate.votes} ")"
+                                                                    </button>
+                                                                </div>
+                                                            </div>
+                                                        </div>
+                                                    }
+                                                })
+                                                .collect_view()}
+                                        </div>
+                                        {is_host
+                                            .then(|| {
+                                                view! {
+                                                    <button
+                                                        class="btn btn-primary"
+                                                        on:click=move |_| {
+                                                            close_voting.dispatch(CloseRoomVoting { code: code() })
+                                                        }
+                                                    >
+                                                        "Close Voting"
+                                                    </button>
+                                                }
+                                            })}
+                                    }
+                                        .into_view()
+                                }
+                                RoomStatus::Decided => {
+                                    match room.winner {
+                                        Some(winner) => {
+                                            view! {
+                                                <h2>"Tonight you're watching " {winner.title}</h2>
+                                                <p>{winner.overview}</p>
+                                            }
+                                                .into_view()
+                                        }
+                                        None => view! { <h2>"Nobody voted"</h2> }.into_view(),
+                                    }
+                                }
+                            }}
+                            <button
+                                class="btn btn-outline-secondary"
+                                on:click=move |_| set_refresh.update(|refresh| *refresh += 1)
+                            >
+                                "Refresh"
+                            </button>
+                        }
+                            .into_view()
+                    }
+                }}
+            </Suspense>
+            {move || room_event.get().map(|event| view! { <p class="text-muted">{event.describe()}</p> })}
+            {move || error_message().map(|message| view! { <p class="text-danger">{message}</p> })}
+        </div>
+    }
+}
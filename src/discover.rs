@@ -0,0 +1,346 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use crate::*;
+
+    // Everything is matched against what's on a subscription service in the US
+    pub const WATCH_REGION: &str = "US";
+    const MONETIZATION_TYPES: &str = "flatrate";
+
+    // Rating sorts need a floor on vote count, or one-vote obscurities top the list
+    const RATING_SORT_MIN_VOTES: u32 = 200;
+
+    /* How a list of IDs combines - TMDB reads commas as AND and pipes as OR */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Match {
+        All,
+        Any,
+    }
+
+    impl Match {
+        fn join<T: ToString>(&self, ids: &[T]) -> String {
+            let separator = match self {
+                Match::All => ",",
+                Match::Any => "|",
+            };
+
+            ids.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(separator)
+        }
+    }
+
+    /* A /discover request - build it up, then to_url() gives the path and encoded query for Tmdb */
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct DiscoverQuery {
+        media_type: MediaType,
+        page: u32,
+        languages: LanguageFilter,
+        years: Option<(String, String)>,
+        runtime: Option<(i32, i32)>,
+        genres: Vec<i32>,
+        genre_match: Match,
+        providers: Vec<i32>,
+        with_keywords: Vec<i64>,
+        without_keywords: Vec<i64>,
+        min_rating: Option<f64>,
+        min_votes: Option<u32>,
+        certification: Option<CertificationLimit>,
+        credits: CreditFilter,
+        sort_by: SortBy,
+    }
+
+    impl DiscoverQuery {
+        fn new(media_type: MediaType) -> Self {
+            Self {
+                media_type,
+                page: 1,
+                languages: LanguageFilter::default(),
+                years: None,
+                runtime: None,
+                genres: vec![],
+                genre_match: Match::All,
+                providers: vec![],
+                with_keywords: vec![],
+                without_keywords: vec![],
+                min_rating: None,
+                min_votes: None,
+                certification: None,
+                credits: CreditFilter::default(),
+                sort_by: SortBy::default(),
+            }
+        }
+
+        pub fn movies() -> Self {
+            Self::new(MediaType::Movie)
+        }
+
+        pub fn tv() -> Self {
+            Self::new(MediaType::Tv)
+        }
+
+        pub fn media_type(&self) -> MediaType {
+            self.media_type
+        }
+
+        pub fn page(mut self, page: u32) -> Self {
+            self.page = page;
+            self
+        }
+
+        pub fn decade(mut self, decade: &Decade) -> Self {
+            self.years = Some(decade.year_range());
+            self
+        }
+
+        /* Minutes - for series this is the length of an episode */
+        pub fn runtime(mut self, (min, max): (i32, i32)) -> Self {
+            self.runtime = Some((min, max));
+            self
+        }
+
+        pub fn genres(mut self, genres: Vec<i32>, genre_match: Match) -> Self {
+            self.genres = genres;
+            self.genre_match = genre_match;
+            self
+        }
+
+        /* Any one of the providers will do */
+        pub fn providers(mut self, providers: Vec<i32>) -> Self {
+            self.providers = providers;
+            self
+        }
+
+        /* Liked keywords are OR'd together so any one of them can match, disliked ones are all excluded */
+        pub fn feedback(mut self, feedback: Option<Feedback>) -> Self {
+            let feedback = feedback.unwrap_or_default();
+            self.with_keywords = feedback.like.unwrap_or_default();
+            self.without_keywords = feedback.dislike.unwrap_or_default();
+            self
+        }
+
+        pub fn min_rating(mut self, min_rating: f64) -> Self {
+            self.min_rating = Some(min_rating);
+            self
+        }
+
+        pub fn min_votes(mut self, min_votes: u32) -> Self {
+            self.min_votes = Some(min_votes);
+            self
+        }
+
+        pub fn languages(mut self, languages: &LanguageFilter) -> Self {
+            self.languages = languages.clone();
+            self
+        }
+
+        pub fn certification(mut self, certification: Option<&CertificationLimit>) -> Self {
+            self.certification = certification.cloned();
+            self
+        }
+
+        pub fn credits(mut self, credits: &CreditFilter) -> Self {
+            self.credits = credits.clone();
+            self
+        }
+
+        pub fn sort_by(mut self, sort_by: SortBy) -> Self {
+            self.sort_by = sort_by;
+            self
+        }
+
+        /* Unencoded name/value pairs, in the order they go on the URL */
+        pub fn params(&self) -> Vec<(&'static str, String)> {
+            let mut params = vec![("include_adult", String::from("false"))];
+
+            if self.media_type == MediaType::Movie {
+                params.push(("include_video", String::from("false")));
+            }
+
+            params.push(("language", self.languages.display_language()));
+            params.push(("page", self.page.to_string()));
+
+            if let Some((start, end)) = &self.years {
+                let (gte, lte) = match self.media_type {
+                    MediaType::Movie => ("primary_release_date.gte", "primary_release_date.lte"),
+                    MediaType::Tv => ("first_air_date.gte", "first_air_date.lte"),
+                };
+                params.push((gte, format!("{}-01-01", start)));
+                params.push((lte, format!("{}-12-31", end)));
+            }
+
+            if let Some((min, max)) = self.runtime {
+                params.push(("with_runtime.gte", min.to_string()));
+                params.push(("with_runtime.lte", max.to_string()));
+            }
+
+            params.push(("sort_by", String::from(self.sort_by.param(&self.media_type))));
+
+            if let Some(min_rating) = self.min_rating {
+                params.push(("vote_average.gte", min_rating.to_string()));
+            }
+
+            let min_votes = match (self.min_votes, &self.sort_by) {
+                (Some(min_votes), _) => Some(min_votes),
+                (None, SortBy::Rating) => Some(RATING_SORT_MIN_VOTES),
+                (None, _) => None,
+            };
+            if let Some(min_votes) = min_votes {
+                params.push(("vote_count.gte", min_votes.to_string()));
+            }
+
+            if let Some(certification) = &self.certification {
+                params.push(("certification_country", certification.country.clone()));
+                params.push(("certification.lte", certification.max.clone()));
+            }
+
+            params.push(("watch_region", String::from(WATCH_REGION)));
+            params.push(("with_watch_monetization_types", String::from(MONETIZATION_TYPES)));
+
+            // Empty lists are left off entirely
+            let lists: [(&'static str, String); 8] = [
+                ("with_genres", self.genre_match.join(&self.genres)),
+                ("with_original_language", Match::Any.join(&self.languages.include)),
+                ("with_keywords", Match::Any.join(&self.with_keywords)),
+                ("without_keywords", Match::Any.join(&self.without_keywords)),
+                ("with_cast", Match::Any.join(&self.credits.cast)),
+                ("with_crew", Match::Any.join(&self.credits.crew)),
+                ("with_companies", Match::Any.join(&self.credits.companies)),
+                ("with_watch_providers", Match::Any.join(&self.providers)),
+            ];
+            params.extend(lists.into_iter().filter(|(_, value)| !value.is_empty()));
+
+            params
+        }
+
+        pub fn to_url(&self) -> String {
+            format!("discover/{}?{}", self.media_type.path(), encode(&self.params()))
+        }
+    }
+
+    /* Percent-encodes a query string - reqwest's Url does the actual encoding */
+    pub fn encode(params: &[(&str, String)]) -> String {
+        reqwest::Url::parse_with_params("http://localhost", params)
+            .ok()
+            .and_then(|url| url.query().map(String::from))
+            .unwrap_or_default()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn param<'a>(params: &'a [(&'static str, String)], name: &str) -> Option<&'a str> {
+            params
+                .iter()
+                .find(|(param, _)| *param == name)
+                .map(|(_, value)| value.as_str())
+        }
+
+        #[test]
+        fn movie_defaults() {
+            let params = DiscoverQuery::movies().params();
+
+            assert_eq!(param(&params, "include_video"), Some("false"));
+            assert_eq!(param(&params, "language"), Some(DEFAULT_LANGUAGE));
+            assert_eq!(param(&params, "page"), Some("1"));
+            assert_eq!(param(&params, "sort_by"), Some("popularity.desc"));
+            assert_eq!(param(&params, "watch_region"), Some("US"));
+            // Empty lists are left off rather than sent blank
+            assert_eq!(param(&params, "with_genres"), None);
+            assert_eq!(param(&params, "vote_count.gte"), None);
+        }
+
+        #[test]
+        fn wizard_query() {
+            let params = DiscoverQuery::movies()
+                .decade(&Decade::TwentyTens)
+                .runtime(Runtime::Average.runtime())
+                .genres(vec![28, 12], Match::All)
+                .providers(vec![8, 337])
+                .feedback(Some(Feedback {
+                    like: Some(vec![1, 2]),
+                    dislike: Some(vec![3]),
+                }))
+                .sort_by(SortBy::Rating)
+                .params();
+
+            assert_eq!(param(&params, "primary_release_date.gte"), Some("2010-01-01"));
+            assert_eq!(param(&params, "primary_release_date.lte"), Some("2019-12-31"));
+            assert_eq!(param(&params, "with_runtime.gte"), Some("90"));
+            assert_eq!(param(&params, "with_runtime.lte"), Some("120"));
+            assert_eq!(param(&params, "with_genres"), Some("28,12"));
+            assert_eq!(param(&params, "with_watch_providers"), Some("8|337"));
+            assert_eq!(param(&params, "with_keywords"), Some("1|2"));
+            assert_eq!(param(&params, "without_keywords"), Some("3"));
+            assert_eq!(param(&params, "sort_by"), Some("vote_average.desc"));
+            assert_eq!(param(&params, "vote_count.gte"), Some("200"));
+        }
+
+        #[test]
+        fn tv_params() {
+            let params = DiscoverQuery::tv()
+                .decade(&Decade::Nineties)
+                .genres(vec![10759, 35], Match::Any)
+                .sort_by(SortBy::Newest)
+                .params();
+
+            assert_eq!(param(&params, "include_video"), None);
+            assert_eq!(param(&params, "first_air_date.gte"), Some("1990-01-01"));
+            assert_eq!(param(&params, "with_genres"), Some("10759|35"));
+            assert_eq!(param(&params, "sort_by"), Some("first_air_date.desc"));
+        }
+
+        #[test]
+        fn filters() {
+            let params = DiscoverQuery::movies()
+                .languages(&LanguageFilter {
+                    display: Some(String::from("ko-KR")),
+                    include: vec![String::from("ko"), String::from("ja")],
+                    exclude: vec![String::from("en")],
+                })
+                .certification(Some(&CertificationLimit {
+                    country: String::from("DE"),
+                    max: String::from("12"),
+                }))
+                .credits(&CreditFilter {
+                    cast: vec![1373737],
+                    crew: vec![],
+                    companies: vec![41077, 3],
+                })
+                .min_rating(7.0)
+                .min_votes(300)
+                .sort_by(SortBy::Rating)
+                .params();
+
+            assert_eq!(param(&params, "language"), Some("ko-KR"));
+            assert_eq!(param(&params, "with_original_language"), Some("ko|ja"));
+            assert_eq!(param(&params, "certification_country"), Some("DE"));
+            assert_eq!(param(&params, "certification.lte"), Some("12"));
+            assert_eq!(param(&params, "with_cast"), Some("1373737"));
+            assert_eq!(param(&params, "with_crew"), None);
+            assert_eq!(param(&params, "with_companies"), Some("41077|3"));
+            assert_eq!(param(&params, "vote_average.gte"), Some("7"));
+            // An explicit minimum wins over the rating sort's default
+            assert_eq!(param(&params, "vote_count.gte"), Some("300"));
+        }
+
+        #[test]
+        fn url_is_encoded() {
+            let url = DiscoverQuery::movies()
+                .certification(Some(&CertificationLimit {
+                    country: String::from("US"),
+                    max: String::from("PG-13 & up"),
+                }))
+                .providers(vec![8, 337])
+                .to_url();
+
+            assert!(url.starts_with("discover/movie?include_adult=false&"));
+            assert!(url.contains("certification.lte=PG-13+%26+up"));
+            assert!(url.contains("with_watch_providers=8%7C337"));
+        }
+    }
+}
+}
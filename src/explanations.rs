@@ -0,0 +1,184 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::collections::HashMap;
+
+    use crate::*;
+
+    // Keeps the explanation line short enough to fit on a card
+    const MAX_REASON_KEYWORDS: usize = 3;
+
+    /* A movie's keywords that the session currently likes, strongest first */
+    pub fn matched_keywords<'a>(profile: &KeywordProfile, keywords: &'a [Keyword]) -> Vec<&'a Keyword> {
+        let mut matched: Vec<(&Keyword, f64)> = keywords
+            .iter()
+            .filter_map(|keyword| match profile.weights.get(&keyword.id) {
+                Some(weight) if *weight > 0.0 => Some((keyword, *weight)),
+                _ => None,
+            })
+            .collect();
+
+        matched.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.id.cmp(&b.0.id)));
+        matched.dedup_by_key(|(keyword, _)| keyword.id);
+
+        matched
+            .into_iter()
+            .take(MAX_REASON_KEYWORDS)
+            .map(|(keyword, _)| keyword)
+            .collect()
+    }
+
+    /* Liked movies behind the matched keywords - these need a title lookup before `explain` can name them */
+    pub fn liked_sources(profile: &KeywordProfile, keywords: &[Keyword]) -> Vec<i64> {
+        let mut sources: Vec<i64> = vec![];
+
+        for keyword in matched_keywords(profile, keywords) {
+            if let Some(movie_id) = profile.sources.get(&keyword.id) {
+                if !sources.contains(movie_id) {
+                    sources.push(*movie_id);
+                }
+            }
+        }
+
+        sources
+    }
+
+    pub fn explain(
+        movie: &Movie,
+        keywords: &[Keyword],
+        criteria: &RecommendationCriteria,
+        genre_names: &HashMap<i32, String>,
+        liked_titles: &HashMap<i64, String>,
+    ) -> RecommendationReasons {
+        let selected_genres = criteria.genres.clone().unwrap_or_default();
+
+        let genres = movie
+            .genre_ids
+            .iter()
+            .filter(|id| selected_genres.contains(id))
+            .filter_map(|id| genre_names.get(id).cloned())
+            .collect();
+
+        let keyword_names = matched_keywords(&criteria.keyword_profile, keywords)
+            .into_iter()
+            .map(|keyword| keyword.name.clone())
+            .collect();
+
+        let liked_movies = liked_sources(&criteria.keyword_profile, keywords)
+            .iter()
+            .filter_map(|id| liked_titles.get(id).cloned())
+            .collect();
+
+        // Discover already filters on both of these, but the release year is double checked in case TMDB is loose about it
+        let decade = criteria.decade.as_ref().and_then(|decade| {
+            let (start, end) = decade.year_range();
+            let year = movie.release_date.get(..4).unwrap_or_default();
+            match year >= start.as_str() && year <= end.as_str() {
+                true => Some(decade.info().name),
+                false => None,
+            }
+        });
+
+        let runtime = criteria.runtime.as_ref().map(|runtime| runtime.info().name);
+
+        RecommendationReasons {
+            genres,
+            keywords: keyword_names,
+            decade,
+            runtime,
+            liked_movies,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn keyword(id: i64, name: &str) -> Keyword {
+            Keyword {
+                id,
+                name: name.to_string(),
+            }
+        }
+
+        fn get_movie() -> Movie {
+            Movie {
+                overview: String::from("A group of professional bank robbers"),
+                release_date: String::from("1995-12-15"),
+                title: String::from("Heat"),
+                genre_ids: vec![28, 80, 18],
+                ..test_movie(949)
+            }
+        }
+
+        fn get_criteria() -> RecommendationCriteria {
+            let mut criteria = RecommendationCriteria {
+                genres: Some(vec![28, 80]),
+                watch_providers: Some(vec![8]),
+                runtime: Some(Runtime::MovieNight),
+                decade: Some(Decade::Nineties),
+                ..Default::default()
+            };
+
+            let profile = &mut criteria.keyword_profile;
+            profile.weights.insert(10051, 3.0);
+            profile.weights.insert(4289, 1.0);
+            profile.weights.insert(9748, -2.0);
+            profile.sources.insert(10051, 1422);
+            profile.sources.insert(4289, 1422);
+
+            criteria
+        }
+
+        #[test]
+        fn explains_matches() {
+            let genre_names: HashMap<i32, String> = [(28, "Action"), (80, "Crime"), (18, "Drama")]
+                .into_iter()
+                .map(|(id, name)| (id, name.to_string()))
+                .collect();
+            let liked_titles: HashMap<i64, String> =
+                [(1422, String::from("The Departed"))].into_iter().collect();
+            let keywords = vec![
+                keyword(4289, "los angeles"),
+                keyword(10051, "heist"),
+                keyword(9748, "revenge"),
+                keyword(1, "police"),
+            ];
+
+            let reasons = explain(
+                &get_movie(),
+                &keywords,
+                &get_criteria(),
+                &genre_names,
+                &liked_titles,
+            );
+
+            assert_eq!(reasons.genres, vec!["Action", "Crime"]);
+            assert_eq!(reasons.keywords, vec!["heist", "los angeles"]);
+            assert_eq!(reasons.liked_movies, vec!["The Departed"]);
+            assert_eq!(reasons.decade, Some(String::from("90s")));
+            assert_eq!(reasons.runtime, Some(String::from("Movie Night")));
+            assert_eq!(
+                reasons.summary(),
+                "Because you liked The Departed · heist, los angeles · Action, Crime · 90s · Movie Night"
+            );
+        }
+
+        #[test]
+        fn nothing_matched() {
+            let criteria = RecommendationCriteria::default();
+
+            let reasons = explain(
+                &get_movie(),
+                &[keyword(1, "police")],
+                &criteria,
+                &HashMap::new(),
+                &HashMap::new(),
+            );
+
+            assert!(reasons.is_empty());
+        }
+    }
+}
+}
@@ -0,0 +1,162 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use serde::{Deserialize, Serialize};
+
+    use crate::accounts::DataOwner;
+    use crate::session_store::{load_json, update_json, SessionStore, SessionStoreError};
+    use crate::*;
+
+    const HISTORY_KEY_PREFIX: &str = "history:";
+
+    pub const MIN_RATING: u8 = 1;
+    pub const MAX_RATING: u8 = 5;
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+    pub struct WatchedMovie {
+        pub movie_id: i64,
+        // Movie and series IDs overlap - entries from before series were supported are movies
+        #[serde(default)]
+        pub media_type: MediaType,
+        pub rating: u8,
+    }
+
+    impl WatchedMovie {
+        fn is(&self, movie_id: i64, media_type: MediaType) -> bool {
+            self.movie_id == movie_id && self.media_type == media_type
+        }
+    }
+
+    /* Everything a user has marked as seen - watched movies never come back as recommendations */
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+    #[serde(default)]
+    pub struct WatchHistory {
+        pub watched: Vec<WatchedMovie>,
+    }
+
+    impl WatchHistory {
+        /* Re-watching a movie replaces the old rating */
+        pub fn record(&mut self, movie_id: i64, media_type: MediaType, rating: u8) {
+            self.watched.retain(|watched| !watched.is(movie_id, media_type));
+            self.watched.push(WatchedMovie {
+                movie_id,
+                media_type,
+                rating,
+            });
+        }
+
+        pub fn contains(&self, movie_id: i64, media_type: MediaType) -> bool {
+            self.watched.iter().any(|watched| watched.is(movie_id, media_type))
+        }
+
+        pub fn rating(&self, movie_id: i64, media_type: MediaType) -> Option<u8> {
+            self.watched
+                .iter()
+                .find(|watched| watched.is(movie_id, media_type))
+                .map(|watched| watched.rating)
+        }
+    }
+
+    pub fn valid_rating(rating: u8) -> bool {
+        (MIN_RATING..=MAX_RATING).contains(&rating)
+    }
+
+    /* 3 is neutral - anything above pulls recommendations towards the movie's keywords, anything below pushes them away */
+    pub fn rating_weight(rating: u8) -> i16 {
+        rating as i16 - 3
+    }
+
+    pub async fn load_history(
+        store: &dyn SessionStore,
+        owner: &DataOwner,
+    ) -> Result<WatchHistory, SessionStoreError> {
+        Ok(load_json(store, &owner.key(HISTORY_KEY_PREFIX))
+            .await?
+            .unwrap_or_default())
+    }
+
+    /* Ratings are checked by the caller with valid_rating - only store failures come back as errors */
+    pub async fn record_watched(
+        store: &dyn SessionStore,
+        owner: &DataOwner,
+        movie_id: i64,
+        media_type: MediaType,
+        rating: u8,
+    ) -> Result<WatchHistory, SessionStoreError> {
+        update_json(
+            store,
+            &owner.key(HISTORY_KEY_PREFIX),
+            owner.ttl_seconds(),
+            |history: &mut WatchHistory| history.record(movie_id, media_type, rating),
+        )
+        .await
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::session_store::MemorySessionStore;
+
+        #[tokio::test]
+        async fn record_and_rerate() {
+            let store = MemorySessionStore::new();
+            let owner = DataOwner::session("abc");
+
+            record_watched(&store, &owner, 1, MediaType::Movie, 5).await.unwrap();
+            record_watched(&store, &owner, 2, MediaType::Movie, 1).await.unwrap();
+            record_watched(&store, &owner, 1, MediaType::Movie, 3).await.unwrap();
+
+            let history = load_history(&store, &owner).await.unwrap();
+
+            assert_eq!(history.watched.len(), 2);
+            assert!(history.contains(2, MediaType::Movie));
+            assert_eq!(history.rating(1, MediaType::Movie), Some(3));
+            assert!(!history.contains(3, MediaType::Movie));
+        }
+
+        #[tokio::test]
+        async fn movies_and_series_share_ids() {
+            let store = MemorySessionStore::new();
+            let owner = DataOwner::session("abc");
+
+            record_watched(&store, &owner, 1399, MediaType::Movie, 5).await.unwrap();
+
+            let history = load_history(&store, &owner).await.unwrap();
+
+            assert!(history.contains(1399, MediaType::Movie));
+            assert!(!history.contains(1399, MediaType::Tv));
+
+            record_watched(&store, &owner, 1399, MediaType::Tv, 2).await.unwrap();
+
+            let history = load_history(&store, &owner).await.unwrap();
+
+            assert_eq!(history.rating(1399, MediaType::Movie), Some(5));
+            assert_eq!(history.rating(1399, MediaType::Tv), Some(2));
+        }
+
+        #[test]
+        fn old_entries_are_movies() {
+            let history: WatchHistory =
+                serde_json::from_str("{\"watched\":[{\"movie_id\":1,\"rating\":4}]}").unwrap();
+
+            assert!(history.contains(1, MediaType::Movie));
+        }
+
+        #[test]
+        fn rating_out_of_range() {
+            assert!(!valid_rating(0));
+            assert!(valid_rating(1));
+            assert!(valid_rating(5));
+            assert!(!valid_rating(6));
+        }
+
+        #[test]
+        fn rating_weights() {
+            assert_eq!(rating_weight(1), -2);
+            assert_eq!(rating_weight(3), 0);
+            assert_eq!(rating_weight(5), 2);
+        }
+    }
+}
+}
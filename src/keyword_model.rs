@@ -0,0 +1,281 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::*;
+
+    // Every feedback round multiplies existing weights by this, so old signals fade out
+    const KEYWORD_DECAY: f64 = 0.8;
+    // Weights that have decayed below this are dropped
+    const MIN_KEYWORD_WEIGHT: f64 = 0.05;
+    // Caps on how much a single session can accumulate
+    pub const MAX_PROFILE_KEYWORDS: usize = 50;
+    const MAX_TRACKED_KEYWORDS: usize = 500;
+    // How many keywords on each side make it into the discover query
+    pub const DISCOVER_KEYWORD_LIMIT: usize = 5;
+
+    /* One movie's (or series') keywords along with how strongly it was liked (positive) or disliked (negative) */
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RatedKeywords {
+        pub movie_id: i64,
+        pub media_type: MediaType,
+        pub keyword_ids: Vec<i64>,
+        pub weight: f64,
+    }
+
+    /* Strongest liked and disliked keyword IDs, best first */
+    pub fn strongest(weights: &HashMap<i64, f64>, limit: usize) -> (Vec<i64>, Vec<i64>) {
+        let mut sorted: Vec<(&i64, &f64)> = weights.iter().collect();
+        sorted.sort_by(|a, b| b.1.total_cmp(a.1).then(a.0.cmp(b.0)));
+
+        let like: Vec<i64> = sorted
+            .iter()
+            .filter(|(_, weight)| **weight > 0.0)
+            .take(limit)
+            .map(|(id, _)| **id)
+            .collect();
+
+        let dislike: Vec<i64> = sorted
+            .iter()
+            .rev()
+            .filter(|(_, weight)| **weight < 0.0)
+            .take(limit)
+            .map(|(id, _)| **id)
+            .collect();
+
+        (like, dislike)
+    }
+
+    fn unique(keyword_ids: &[i64]) -> HashSet<i64> {
+        keyword_ids.iter().copied().collect()
+    }
+
+    impl KeywordProfile {
+        /* Starts a profile from the strongest entries of a longer-lived weight map */
+        pub fn seeded(weights: &HashMap<i64, f64>, limit: usize) -> Self {
+            let (like, dislike) = strongest(weights, limit);
+
+            Self {
+                weights: like
+                    .into_iter()
+                    .chain(dislike)
+                    .map(|id| (id, weights[&id]))
+                    .collect(),
+                ..Default::default()
+            }
+        }
+
+        /* Smoothed IDF - keywords that turn up on most rated movies (e.g. "based on novel") count for less */
+        pub fn idf(&self, keyword_id: i64) -> f64 {
+            let document_count = *self.document_counts.get(&keyword_id).unwrap_or(&0) as f64;
+
+            ((1.0 + self.documents as f64) / (1.0 + document_count)).ln() + 1.0
+        }
+
+        /* Decays what's already there, then folds in one round of rated movies */
+        pub fn apply_round(&mut self, rated_movies: &[RatedKeywords]) {
+            for weight in self.weights.values_mut() {
+                *weight *= KEYWORD_DECAY;
+            }
+
+            self.apply_votes(rated_movies);
+        }
+
+        /* Folds in single votes (a swipe, a rating) without decaying - only whole rounds age the profile */
+        pub fn apply_votes(&mut self, rated_movies: &[RatedKeywords]) {
+            // Document frequencies are updated first so this round's keywords are weighted against everything seen so far
+            for rated_movie in rated_movies {
+                self.documents += 1;
+                for id in unique(&rated_movie.keyword_ids) {
+                    *self.document_counts.entry(id).or_insert(0) += 1;
+                }
+            }
+
+            for rated_movie in rated_movies {
+                for id in unique(&rated_movie.keyword_ids) {
+                    let delta = rated_movie.weight * self.idf(id);
+                    *self.weights.entry(id).or_insert(0.0) += delta;
+                    // Sources are looked up as movies, so a liked series can't be one
+                    if rated_movie.weight > 0.0 && rated_movie.media_type == MediaType::Movie {
+                        self.sources.insert(id, rated_movie.movie_id);
+                    }
+                }
+            }
+
+            self.prune();
+        }
+
+        fn prune(&mut self) {
+            self.weights.retain(|_, weight| weight.abs() >= MIN_KEYWORD_WEIGHT);
+
+            if self.weights.len() > MAX_PROFILE_KEYWORDS {
+                let mut sorted: Vec<(i64, f64)> = self.weights.drain().collect();
+                sorted.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()).then(a.0.cmp(&b.0)));
+                sorted.truncate(MAX_PROFILE_KEYWORDS);
+                self.weights = sorted.into_iter().collect();
+            }
+
+            let weights = &self.weights;
+            self.sources.retain(|id, _| weights.contains_key(id));
+
+            if self.document_counts.len() > MAX_TRACKED_KEYWORDS {
+                let mut sorted: Vec<(i64, u32)> = self.document_counts.drain().collect();
+                sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+                sorted.truncate(MAX_TRACKED_KEYWORDS);
+                self.document_counts = sorted.into_iter().collect();
+            }
+        }
+
+        /* The discover query only ever sees the top weighted keywords */
+        pub fn to_feedback(&self, limit: usize) -> Option<Feedback> {
+            let (like, dislike) = strongest(&self.weights, limit);
+
+            match like.is_empty() && dislike.is_empty() {
+                true => None,
+                false => Some(Feedback {
+                    like: Some(like).filter(|like| !like.is_empty()),
+                    dislike: Some(dislike).filter(|dislike| !dislike.is_empty()),
+                }),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn rated(movie_id: i64, keyword_ids: Vec<i64>, weight: f64) -> RatedKeywords {
+            RatedKeywords {
+                movie_id,
+                media_type: MediaType::Movie,
+                keyword_ids,
+                weight,
+            }
+        }
+
+        #[test]
+        fn ubiquitous_keywords_weigh_less() {
+            let mut profile = KeywordProfile::default();
+
+            // 818 ("based on novel") is on every liked movie, the others only on one each
+            profile.apply_round(&[
+                rated(1, vec![818, 100], 2.0),
+                rated(2, vec![818, 101], 2.0),
+                rated(3, vec![818, 102], 2.0),
+            ]);
+
+            assert!(profile.weights[&100] > profile.weights[&818] / 3.0);
+            assert!(profile.idf(818) < profile.idf(100));
+        }
+
+        #[test]
+        fn likes_and_dislikes_cancel() {
+            let mut profile = KeywordProfile::default();
+
+            profile.apply_round(&[rated(1, vec![100, 200], 2.0), rated(2, vec![200, 300], -2.0)]);
+
+            assert!(profile.weights[&100] > 0.0);
+            assert!(!profile.weights.contains_key(&200));
+            assert!(profile.weights[&300] < 0.0);
+
+            // Only liked movies are remembered as sources, and only for keywords that survived
+            assert_eq!(profile.sources.get(&100), Some(&1));
+            assert!(!profile.sources.contains_key(&200));
+            assert!(!profile.sources.contains_key(&300));
+        }
+
+        #[test]
+        fn series_never_sources() {
+            let mut profile = KeywordProfile::default();
+            let mut liked_show = rated(1, vec![100], 2.0);
+            liked_show.media_type = MediaType::Tv;
+
+            profile.apply_round(&[liked_show]);
+
+            assert!(profile.weights[&100] > 0.0);
+            assert!(profile.sources.is_empty());
+        }
+
+        #[test]
+        fn old_rounds_decay() {
+            let mut profile = KeywordProfile::default();
+
+            profile.apply_round(&[rated(1, vec![100], 2.0)]);
+            let first = profile.weights[&100];
+
+            profile.apply_round(&[]);
+
+            assert_eq!(profile.weights[&100], first * KEYWORD_DECAY);
+
+            for _ in 0..50 {
+                profile.apply_round(&[]);
+            }
+
+            assert!(profile.weights.is_empty());
+        }
+
+        #[test]
+        fn votes_do_not_decay() {
+            let mut profile = KeywordProfile::default();
+
+            profile.apply_votes(&[rated(1, vec![100], 2.0)]);
+            let first = profile.weights[&100];
+
+            for id in 2..10 {
+                profile.apply_votes(&[rated(id, vec![id + 100], 2.0)]);
+            }
+
+            assert_eq!(profile.weights[&100], first);
+        }
+
+        #[test]
+        fn profile_is_capped() {
+            let mut profile = KeywordProfile::default();
+
+            let rated_movies: Vec<RatedKeywords> = (0..100)
+                .map(|id| rated(id, vec![id], 1.0 + id as f64))
+                .collect();
+
+            profile.apply_round(&rated_movies);
+
+            assert_eq!(profile.weights.len(), MAX_PROFILE_KEYWORDS);
+            // The strongest entries are the ones kept
+            assert!(profile.weights.contains_key(&99));
+            assert!(!profile.weights.contains_key(&0));
+        }
+
+        #[test]
+        fn feedback_uses_top_weights() {
+            let mut profile = KeywordProfile::default();
+            for (id, weight) in [(1, 5.0), (2, 4.0), (3, 3.0), (4, -1.0), (5, -3.0)] {
+                profile.weights.insert(id, weight);
+            }
+
+            assert_eq!(
+                profile.to_feedback(2),
+                Some(Feedback {
+                    like: Some(vec![1, 2]),
+                    dislike: Some(vec![5, 4]),
+                })
+            );
+
+            assert_eq!(KeywordProfile::default().to_feedback(2), None);
+        }
+
+        #[test]
+        fn seeded_keeps_strongest() {
+            let weights: HashMap<i64, f64> =
+                [(1, 5.0), (2, 4.0), (3, 3.0), (4, -1.0)].into_iter().collect();
+
+            let profile = KeywordProfile::seeded(&weights, 2);
+
+            assert_eq!(profile.weights.len(), 3);
+            assert_eq!(profile.weights[&1], 5.0);
+            assert!(!profile.weights.contains_key(&3));
+            assert_eq!(profile.documents, 0);
+        }
+    }
+}
+}
@@ -1,20 +1,52 @@
 #![allow(unused_imports, dead_code, unused_variables)]
+#[cfg(feature = "ssr")]
+pub mod accounts;
 pub mod app;
+#[cfg(feature = "ssr")]
+pub mod availability;
+#[cfg(feature = "ssr")]
+pub mod browse;
 pub mod components;
+#[cfg(feature = "ssr")]
+pub mod collections;
+#[cfg(feature = "ssr")]
+pub mod discover;
+#[cfg(feature = "ssr")]
+pub mod explanations;
+#[cfg(feature = "ssr")]
+pub mod history;
+#[cfg(feature = "ssr")]
+pub mod keyword_model;
+#[cfg(feature = "ssr")]
+pub mod moods;
 pub use app::*;
 #[cfg(feature = "ssr")]
 pub mod redis_helper;
 #[cfg(feature = "ssr")]
-pub use redis_helper::start_recommendation_session;
+pub mod room_events;
+#[cfg(feature = "ssr")]
+pub mod rooms;
 pub mod server_functions;
 pub use server_functions::*;
 #[cfg(feature = "ssr")]
+pub mod scheduler;
+#[cfg(feature = "ssr")]
+pub mod session_cookie;
+#[cfg(feature = "ssr")]
+pub mod session_store;
+#[cfg(feature = "ssr")]
+pub mod surprise;
+#[cfg(feature = "ssr")]
 pub mod tmdb;
 #[cfg(feature = "ssr")]
+pub mod tmdb_cache;
+#[cfg(feature = "ssr")]
 pub mod tmdb_helper;
+#[cfg(feature = "ssr")]
+pub mod watchlist;
 use leptos::{svg::view, *};
 use serde::{Deserialize, Serialize};
-use std::{thread, time::Duration};
+use std::{collections::HashMap, thread, time::Duration};
 
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
@@ -28,21 +60,99 @@ pub fn hydrate() {
     mount_to_body(App);
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+/* Missing fields fall back to their defaults so older stored sessions still load */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(default)]
 pub struct RecommendationCriteria {
     pub genres: Option<Vec<i32>>,
     pub watch_providers: Option<Vec<i32>>,
     pub runtime: Option<Runtime>,
     pub decade: Option<Decade>,
-    pub feedback: Option<Feedback>,
+    pub keyword_profile: KeywordProfile,
+    pub sort_by: Option<SortBy>,
+    pub media: Option<MediaChoice>,
+    pub episode_length: Option<EpisodeLength>,
+    pub seasons: Option<SeasonCount>,
+    pub languages: LanguageFilter,
+    pub certification: Option<CertificationLimit>,
+    // Applies the limit to everything the session does, not just the wizard
+    pub family_mode: bool,
+    pub credits: CreditFilter,
+    // Leaves out sequels until the earlier parts have been marked watched
+    pub skip_unseen_sequels: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+impl RecommendationCriteria {
+    /* The limit for wizard recommendations - family mode always has one */
+    pub fn certification_limit(&self) -> Option<CertificationLimit> {
+        match (&self.certification, self.family_mode) {
+            (Some(limit), _) => Some(limit.clone()),
+            (None, true) => Some(CertificationLimit::family_default()),
+            (None, false) => None,
+        }
+    }
+
+    /* The limit for everything outside the wizard - surprises and hosted rooms - which only family mode restricts */
+    pub fn family_limit(&self) -> Option<CertificationLimit> {
+        match self.family_mode {
+            true => self.certification_limit(),
+            false => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
 pub struct Feedback {
     pub like: Option<Vec<i64>>,
     pub dislike: Option<Vec<i64>>,
 }
 
+impl Feedback {
+    /* Splits (ID, media type, liked) decisions into the movie and series halves PostFeedback expects */
+    pub fn split(decisions: impl IntoIterator<Item = (i64, MediaType, bool)>) -> (Feedback, Feedback) {
+        let mut movies = Feedback::default();
+        let mut shows = Feedback::default();
+
+        for (id, media_type, liked) in decisions {
+            let feedback = match media_type {
+                MediaType::Movie => &mut movies,
+                MediaType::Tv => &mut shows,
+            };
+            let list = match liked {
+                true => &mut feedback.like,
+                false => &mut feedback.dislike,
+            };
+            list.get_or_insert_with(Vec::new).push(id);
+        }
+
+        (movies, shows)
+    }
+}
+
+/* Weighted keyword preferences built up over feedback rounds - positive weights are liked, negative disliked */
+/* document_counts/documents track how many rated movies each keyword showed up in, for IDF-style weighting */
+/* sources remembers the liked movie that most recently pushed each keyword up, for explanations */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct KeywordProfile {
+    pub weights: HashMap<i64, f64>,
+    pub document_counts: HashMap<i64, u32>,
+    pub documents: u32,
+    pub sources: HashMap<i64, i64>,
+}
+
+/* Long-lived preferences for a signed in user - used to pre-fill the wizard on their next visit */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct TasteProfile {
+    pub watch_providers: Option<Vec<i32>>,
+    pub runtime: Option<Runtime>,
+    pub liked_movies: Vec<i64>,
+    pub disliked_movies: Vec<i64>,
+    pub keyword_weights: HashMap<i64, f64>,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct Keyword {
     pub id: i64,
@@ -52,9 +162,332 @@ pub struct Keyword {
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct KeywordResponse {
     pub id: i64,
+    // tv/{id}/keywords calls the list "results"
+    #[serde(alias = "results")]
     pub keywords: Vec<Keyword>,
 }
 
+/* What a single result is - TMDB keeps separate endpoints (and separate ID spaces) for each */
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Default, Hash)]
+pub enum MediaType {
+    #[default]
+    Movie,
+    Tv,
+}
+
+impl MediaType {
+    /* Path segment TMDB uses for this media type, e.g. movie/{id}/keywords vs tv/{id}/keywords */
+    pub fn path(&self) -> &'static str {
+        match self {
+            MediaType::Movie => "movie",
+            MediaType::Tv => "tv",
+        }
+    }
+}
+
+/* What the wizard asked for - sessions from before series were supported count as Movies */
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+pub enum MediaChoice {
+    #[default]
+    Movies,
+    Series,
+    Both,
+}
+
+impl MediaChoice {
+    pub fn includes_movies(&self) -> bool {
+        matches!(self, MediaChoice::Movies | MediaChoice::Both)
+    }
+
+    pub fn includes_series(&self) -> bool {
+        matches!(self, MediaChoice::Series | MediaChoice::Both)
+    }
+}
+
+/* Series stand in for film runtime with how long each episode runs */
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+pub enum EpisodeLength {
+    Short,
+    #[default]
+    Standard,
+    Long,
+}
+
+impl EpisodeLength {
+    pub fn runtime(&self) -> (i32, i32) {
+        match self {
+            EpisodeLength::Short => (0, 30),
+            EpisodeLength::Standard => (30, 60),
+            EpisodeLength::Long => (60, 500),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            EpisodeLength::Short => String::from("Under 30 minutes"),
+            EpisodeLength::Standard => String::from("30 minutes to an hour"),
+            EpisodeLength::Long => String::from("Over an hour"),
+        }
+    }
+}
+
+/* How much of a commitment a series is - discover can't filter on this, so it's checked against each show's details */
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+pub enum SeasonCount {
+    Miniseries,
+    Few,
+    LongRunning,
+}
+
+impl SeasonCount {
+    pub fn matches(&self, number_of_seasons: u32) -> bool {
+        match self {
+            SeasonCount::Miniseries => number_of_seasons <= 1,
+            SeasonCount::Few => (2..=4).contains(&number_of_seasons),
+            SeasonCount::LongRunning => number_of_seasons >= 5,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            SeasonCount::Miniseries => String::from("One and done"),
+            SeasonCount::Few => String::from("A few seasons"),
+            SeasonCount::LongRunning => String::from("Long running"),
+        }
+    }
+}
+
+/* How discover orders its results - rating sorts also require a minimum vote count so obscure one-vote movies don't top the list */
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+pub enum SortBy {
+    #[default]
+    Popularity,
+    Rating,
+    Newest,
+}
+
+impl SortBy {
+    /* discover/tv sorts on first air date rather than release date */
+    pub fn param(&self, media_type: &MediaType) -> &'static str {
+        match (self, media_type) {
+            (SortBy::Popularity, _) => "popularity.desc",
+            (SortBy::Rating, _) => "vote_average.desc",
+            (SortBy::Newest, MediaType::Movie) => "primary_release_date.desc",
+            (SortBy::Newest, MediaType::Tv) => "first_air_date.desc",
+        }
+    }
+}
+
+/* TMDB's language when nothing else has been picked */
+pub const DEFAULT_LANGUAGE: &str = "en-US";
+
+/* Display language for titles, overviews and genre names, plus which original languages to keep */
+/* Original languages are ISO 639-1 codes - discover can include them but not exclude them, so exclusions are checked per result */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct LanguageFilter {
+    pub display: Option<String>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl LanguageFilter {
+    pub fn display_language(&self) -> String {
+        self.display.clone().unwrap_or(String::from(DEFAULT_LANGUAGE))
+    }
+
+    /* The display language is "xx" or "xx-YY", original languages are bare "xx" codes */
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(display) = &self.display {
+            let valid = match display.split_once('-') {
+                Some((language, region)) => is_language_code(language) && is_region_code(region),
+                None => is_language_code(display),
+            };
+            if !valid {
+                return Err(format!("Invalid display language: {}", display));
+            }
+        }
+
+        match self
+            .include
+            .iter()
+            .chain(self.exclude.iter())
+            .find(|code| !is_language_code(code))
+        {
+            Some(code) => Err(format!("Invalid language code: {}", code)),
+            None => Ok(()),
+        }
+    }
+
+    /* Results with no original language are kept rather than guessed at */
+    pub fn allows(&self, original_language: &str) -> bool {
+        original_language.is_empty()
+            || ((self.include.is_empty() || self.include.iter().any(|code| code == original_language))
+                && !self.exclude.iter().any(|code| code == original_language))
+    }
+}
+
+fn is_language_code(code: &str) -> bool {
+    code.len() == 2 && code.chars().all(|c| c.is_ascii_lowercase())
+}
+
+fn is_region_code(code: &str) -> bool {
+    code.len() == 2 && code.chars().all(|c| c.is_ascii_uppercase())
+}
+
+/* An entry from TMDB's configuration/languages */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Language {
+    pub iso_639_1: String,
+    pub english_name: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+/* Highest content rating allowed, in one country's rating system - discover compares it by that system's order */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct CertificationLimit {
+    pub country: String,
+    pub max: String,
+}
+
+impl CertificationLimit {
+    /* What family mode falls back to when no limit has been picked */
+    pub fn family_default() -> Self {
+        Self {
+            country: String::from("US"),
+            max: String::from("PG"),
+        }
+    }
+}
+
+/* One rating from TMDB's certification list - order ranks it within its country, lowest first */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Certification {
+    pub certification: String,
+    pub meaning: String,
+    pub order: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetCertificationsResponse {
+    pub certifications: HashMap<String, Vec<Certification>>,
+}
+
+/* People and studios to narrow discover to - any one of each kind can match, each kind that's set has to */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct CreditFilter {
+    pub cast: Vec<i64>,
+    pub crew: Vec<i64>,
+    pub companies: Vec<i64>,
+}
+
+impl CreditFilter {
+    pub fn is_empty(&self) -> bool {
+        self.cast.is_empty() && self.crew.is_empty() && self.companies.is_empty()
+    }
+}
+
+/* A /search/person result - known_for_department says whether they're mostly cast or crew */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Person {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub known_for_department: Option<String>,
+    pub profile_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchPeopleResponse {
+    pub results: Vec<Person>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Company {
+    pub id: i64,
+    pub name: String,
+    pub logo_path: Option<String>,
+    #[serde(default)]
+    pub origin_country: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchCompaniesResponse {
+    pub results: Vec<Company>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum TrendingWindow {
+    Day,
+    Week,
+}
+
+impl TrendingWindow {
+    pub fn path(&self) -> &'static str {
+        match self {
+            TrendingWindow::Day => "day",
+            TrendingWindow::Week => "week",
+        }
+    }
+}
+
+/* Lists that can be browsed without going through the wizard */
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum BrowseList {
+    Trending(TrendingWindow),
+    NowPlaying,
+    Upcoming,
+}
+
+impl BrowseList {
+    pub fn all() -> Vec<BrowseList> {
+        vec![
+            BrowseList::Trending(TrendingWindow::Day),
+            BrowseList::Trending(TrendingWindow::Week),
+            BrowseList::NowPlaying,
+            BrowseList::Upcoming,
+        ]
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            BrowseList::Trending(TrendingWindow::Day) => String::from("Trending Today"),
+            BrowseList::Trending(TrendingWindow::Week) => String::from("Trending This Week"),
+            BrowseList::NowPlaying => String::from("In Cinemas"),
+            BrowseList::Upcoming => String::from("Coming Soon"),
+        }
+    }
+
+    pub fn href(&self) -> &'static str {
+        match self {
+            BrowseList::Trending(TrendingWindow::Day) => "/browse/trending",
+            BrowseList::Trending(TrendingWindow::Week) => "/browse/trending-week",
+            BrowseList::NowPlaying => "/browse/now-playing",
+            BrowseList::Upcoming => "/browse/upcoming",
+        }
+    }
+
+    /* Now playing and upcoming are cinema releases, so streaming services don't come into it */
+    pub fn streaming(&self) -> bool {
+        matches!(self, BrowseList::Trending(_))
+    }
+}
+
+/* A curated shortcut into the wizard - picking one pre-fills genres, runtime, keywords and sort order */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Mood {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub genres: Vec<i32>,
+    pub include_keywords: Vec<i64>,
+    pub exclude_keywords: Vec<i64>,
+    pub runtime: Option<Runtime>,
+    pub sort_by: SortBy,
+}
+
 /*
    Runtime options
 */
@@ -214,9 +647,33 @@ impl Decade {
             Decade::Recent => (String::from("2020"), String::from("2024")),
         }
     }
+
+    pub fn all() -> Vec<Decade> {
+        vec![
+            Decade::Classic,
+            Decade::Fifties,
+            Decade::Sixties,
+            Decade::Seventies,
+            Decade::Eighties,
+            Decade::Nineties,
+            Decade::TwoThousands,
+            Decade::TwentyTens,
+            Decade::Recent,
+        ]
+    }
+
+    /* Takes a TMDB release date or bare year - anything that doesn't start with a year in range gives None */
+    pub fn from_year(release_date: &str) -> Option<Decade> {
+        let year = release_date.get(..4)?;
+
+        Decade::all().into_iter().find(|decade| {
+            let (start, end) = decade.year_range();
+            year >= start.as_str() && year <= end.as_str()
+        })
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
 pub struct Movie {
     pub id: i64,
     pub overview: String,
@@ -224,8 +681,154 @@ pub struct Movie {
     pub poster_path: Option<String>,
     pub release_date: String,
     pub title: String,
+    // Only filled in by list endpoints like /discover
+    #[serde(default)]
+    pub genre_ids: Vec<i32>,
     //vote_average: f64,
     //vote_count: i64,
+    // Series are carried as a Movie too, so they share cards, watchlists and rooms
+    #[serde(default)]
+    pub media_type: MediaType,
+    #[serde(default)]
+    pub original_language: String,
+}
+
+/* The one test movie - tests override what they care about with ..test_movie(id) */
+#[cfg(test)]
+pub fn test_movie(id: i64) -> Movie {
+    Movie {
+        id,
+        overview: String::from("A movie"),
+        release_date: String::from("2020-01-01"),
+        title: format!("Movie {}", id),
+        original_language: String::from("en"),
+        ..Default::default()
+    }
+}
+
+/* A discover/tv result - names its fields differently to movies */
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+pub struct TvShow {
+    pub id: i64,
+    pub overview: String,
+    pub poster_path: Option<String>,
+    #[serde(default)]
+    pub first_air_date: String,
+    pub name: String,
+    #[serde(default)]
+    pub genre_ids: Vec<i32>,
+    #[serde(default)]
+    pub original_language: String,
+}
+
+impl TvShow {
+    pub fn into_movie(self) -> Movie {
+        Movie {
+            id: self.id,
+            overview: self.overview,
+            poster_path: self.poster_path,
+            release_date: self.first_air_date,
+            title: self.name,
+            genre_ids: self.genre_ids,
+            media_type: MediaType::Tv,
+            original_language: self.original_language,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+pub struct GetTvResponse {
+    pub results: Vec<TvShow>,
+}
+
+/* movie/{id} with the franchise it belongs to, if any */
+#[derive(Debug, Deserialize)]
+pub struct MovieDetails {
+    #[serde(flatten)]
+    pub movie: Movie,
+    pub belongs_to_collection: Option<CollectionSummary>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct CollectionSummary {
+    pub id: i64,
+    pub name: String,
+}
+
+/* A franchise from collection/{id} - TMDB doesn't promise the parts are in order */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub overview: String,
+    pub poster_path: Option<String>,
+    pub parts: Vec<Movie>,
+}
+
+impl Collection {
+    /* Announced parts with no release date yet go last */
+    pub fn in_release_order(&self) -> Vec<Movie> {
+        let mut parts = self.parts.clone();
+        parts.sort_by_key(|part| (part.release_date.is_empty(), part.release_date.clone()));
+        parts
+    }
+
+    pub fn position(&self, movie_id: i64) -> Option<CollectionPosition> {
+        let parts = self.in_release_order();
+        let index = parts.iter().position(|part| part.id == movie_id)?;
+
+        Some(CollectionPosition {
+            collection_id: self.id,
+            name: self.name.clone(),
+            part: index + 1,
+            total: parts.len(),
+        })
+    }
+}
+
+/* Where a movie sits in its franchise */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct CollectionPosition {
+    pub collection_id: i64,
+    pub name: String,
+    pub part: usize,
+    pub total: usize,
+}
+
+impl CollectionPosition {
+    pub fn describe(&self) -> String {
+        format!("Part {} of {} in {}", self.part, self.total, self.name)
+    }
+}
+
+/* A movie with its whole franchise, for the detail page */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct MovieDetailView {
+    pub movie: Movie,
+    pub collection: Option<Collection>,
+    pub watched: Vec<i64>,
+}
+
+/* "Now on Netflix" for something on the watchlist - raised by the background availability check */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct AvailabilityAlert {
+    pub movie_id: i64,
+    #[serde(default)]
+    pub media_type: MediaType,
+    pub title: String,
+    pub provider_id: i32,
+    pub provider_name: String,
+    // Unix seconds
+    pub detected_at: u64,
+}
+
+/* Only the parts of tv/{id} that the season filter needs */
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+pub struct TvDetails {
+    pub id: i64,
+    #[serde(default)]
+    pub number_of_seasons: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -284,27 +887,182 @@ pub struct GetRecommendationsResponse {
     pub results: Vec<Movie>,
 }
 
+/* movie/{id}/release_dates - one entry per country, each with its own ratings */
+#[derive(Debug, Deserialize)]
+pub struct GetReleaseDatesResponse {
+    pub results: Vec<CountryReleaseDates>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CountryReleaseDates {
+    pub iso_3166_1: String,
+    pub release_dates: Vec<ReleaseDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseDate {
+    #[serde(default)]
+    pub certification: String,
+}
+
+impl GetReleaseDatesResponse {
+    /* Releases often leave the rating blank, so the first one that has it counts */
+    pub fn certification(&self, country: &str) -> Option<String> {
+        self.results
+            .iter()
+            .find(|release| release.iso_3166_1 == country)?
+            .release_dates
+            .iter()
+            .map(|release| release.certification.trim())
+            .find(|certification| !certification.is_empty())
+            .map(String::from)
+    }
+}
+
+/* Why a movie was recommended - each list only holds what actually matched the session */
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct RecommendationReasons {
+    pub genres: Vec<String>,
+    pub keywords: Vec<String>,
+    pub decade: Option<String>,
+    pub runtime: Option<String>,
+    pub liked_movies: Vec<String>,
+}
+
+impl RecommendationReasons {
+    pub fn is_empty(&self) -> bool {
+        self.genres.is_empty()
+            && self.keywords.is_empty()
+            && self.decade.is_none()
+            && self.runtime.is_none()
+            && self.liked_movies.is_empty()
+    }
+
+    /* One line for the card - "Because you liked Heat · heist, los angeles · Action, Crime · 90s · Movie Night" */
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = vec![];
+
+        if !self.liked_movies.is_empty() {
+            parts.push(format!("Because you liked {}", self.liked_movies.join(", ")));
+        }
+        if !self.keywords.is_empty() {
+            parts.push(self.keywords.join(", "));
+        }
+        if !self.genres.is_empty() {
+            parts.push(self.genres.join(", "));
+        }
+        if let Some(decade) = &self.decade {
+            parts.push(decade.clone());
+        }
+        if let Some(runtime) = &self.runtime {
+            parts.push(runtime.clone());
+        }
+
+        parts.join(" · ")
+    }
+}
+
+/* Group rooms move from gathering preferences, to voting on a shared grid, to a decided winner */
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub enum RoomStatus {
+    Gathering,
+    Voting,
+    Decided,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RoomMemberView {
+    pub name: String,
+    pub ready: bool,
+    pub voted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RoomCandidate {
+    pub movie: Movie,
+    pub votes: u32,
+}
+
+/* Pushed to everyone in a room whenever it changes - clients refetch their RoomView on receipt */
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum RoomEvent {
+    MemberJoined { name: String },
+    PreferencesUpdated { name: String },
+    VotingStarted,
+    VoteCast { name: String },
+    Decided { movie_id: Option<i64> },
+}
+
+impl RoomEvent {
+    pub fn describe(&self) -> String {
+        match self {
+            RoomEvent::MemberJoined { name } => format!("{} joined", name),
+            RoomEvent::PreferencesUpdated { name } => format!("{} updated their picks", name),
+            RoomEvent::VotingStarted => String::from("Voting has started"),
+            RoomEvent::VoteCast { name } => format!("{} voted", name),
+            RoomEvent::Decided { .. } => String::from("Voting has closed"),
+        }
+    }
+}
+
+/* A room as seen by one of its members */
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RoomView {
+    pub code: String,
+    pub status: RoomStatus,
+    pub is_host: bool,
+    pub my_vote: Option<i64>,
+    pub members: Vec<RoomMemberView>,
+    pub candidates: Vec<RoomCandidate>,
+    pub winner: Option<Movie>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MovieRecommendation {
     pub movie: Movie,
     pub providers: Vec<WatchProvider>,
+    pub reasons: RecommendationReasons,
+    pub collection: Option<CollectionPosition>,
     pub liked: RwSignal<bool>,
     pub disliked: RwSignal<bool>,
+    pub saved: RwSignal<bool>,
 }
 
 impl MovieRecommendation {
     pub fn new(movie: Movie, providers: Vec<WatchProvider>) -> Self {
         let liked = create_rw_signal(false);
         let disliked = create_rw_signal(false);
+        let saved = create_rw_signal(false);
 
         Self {
             movie,
             providers,
+            reasons: RecommendationReasons::default(),
+            collection: None,
             liked,
             disliked,
+            saved,
         }
     }
 
+    pub fn with_reasons(mut self, reasons: RecommendationReasons) -> Self {
+        self.reasons = reasons;
+        self
+    }
+
+    pub fn with_collection(mut self, collection: Option<CollectionPosition>) -> Self {
+        self.collection = collection;
+        self
+    }
+
+    /* For movies that are already on the watchlist */
+    pub fn saved(movie: Movie, providers: Vec<WatchProvider>) -> Self {
+        let recommendation = Self::new(movie, providers);
+        recommendation.saved.set(true);
+        recommendation
+    }
+
     pub fn set_liked(&mut self) {
         self.liked.set(true);
         self.disliked.set(false);
@@ -363,6 +1121,22 @@ impl CardData for MovieRecommendation {
                 })
                 .collect_view()
             }
+            {match self.reasons.is_empty() {
+                true => view! {}.into_view(),
+                false => view! {
+                    <p class="card-text"><small class="text-body-secondary">{self.reasons.summary()}</small></p>
+                }.into_view(),
+            }}
+            {match &self.collection {
+                None => view! {}.into_view(),
+                Some(collection) => view! {
+                    <p class="card-text">
+                        <a href=format!("/movie/{}", self.movie.id) class="link-secondary">
+                            <small>{collection.describe()}</small>
+                        </a>
+                    </p>
+                }.into_view(),
+            }}
             </div>
         }
     }
@@ -371,6 +1145,10 @@ impl CardData for MovieRecommendation {
         println!("Getting footer...");
         let liked_rw_signal = self.liked;
         let disliked_rw_signal = self.disliked;
+        let saved_rw_signal = self.saved;
+        let movie = self.movie.clone();
+        let movie_id = self.movie.id;
+        let media_type = self.movie.media_type;
         view! {
             <div class="card-footer">
                 <ul class="list-group list-group-flush">
@@ -404,6 +1182,46 @@ impl CardData for MovieRecommendation {
                                 "btn btn-outline-danger"
                             }
                         }>"Dislike"</button>
+                        <button on:click={move |_| {
+                            let movie = movie.clone();
+                            let saved = saved_rw_signal.get();
+                            saved_rw_signal.set(!saved);
+                            spawn_local(async move {
+                                let result = if saved {
+                                    remove_from_watchlist(movie.id, movie.media_type).await
+                                } else {
+                                    add_to_watchlist(movie).await
+                                };
+                                if let Err(err) = result {
+                                    println!("Error updating watchlist: {}", err);
+                                    saved_rw_signal.set(saved);
+                                }
+                            });
+                        }} class={ move ||
+                            if saved_rw_signal.get() {
+                                "btn btn-primary"
+                            }else {
+                                "btn btn-outline-primary"
+                            }
+                        }>{move || if saved_rw_signal.get() { "Saved" } else { "Save" }}</button>
+                    </li>
+                    <li class="list-group-item list-group-item-dark">
+                        <select class="form-select form-select-sm" on:change={move |e| {
+                            if let Ok(rating) = event_target_value(&e).parse::<u8>() {
+                                spawn_local(async move {
+                                    if let Err(err) = mark_watched(movie_id, media_type, rating).await {
+                                        println!("Error marking movie as watched: {}", err);
+                                    }
+                                });
+                            }
+                        }}>
+                            <option value="" selected>"Watched it?"</option>
+                            {(1..=5u8)
+                                .map(|rating| view! {
+                                    <option value=rating>{format!("{} / 5", rating)}</option>
+                                })
+                                .collect_view()}
+                        </select>
                     </li>
                 </ul>
             </div>
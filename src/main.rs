@@ -1,7 +1,5 @@
 #[cfg(feature = "ssr")]
-use actix_session::{storage::CookieSessionStore, *};
-#[cfg(feature = "ssr")]
-use actix_web::{cookie::Key, *};
+use actix_web::*;
 #[cfg(feature = "ssr")]
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -9,6 +7,10 @@ async fn main() -> std::io::Result<()> {
     use leptos::*;
     use leptos_actix::{generate_route_list, LeptosRoutes};
     use moodie_server::app::*;
+    use moodie_server::scheduler::{self, Scheduler};
+    use moodie_server::room_events;
+    use moodie_server::session_store;
+    use moodie_server::tmdb::Tmdb;
 
     let conf = get_configuration(None).await.unwrap();
     let addr = conf.leptos_options.site_addr;
@@ -16,29 +18,63 @@ async fn main() -> std::io::Result<()> {
     let routes = generate_route_list(App);
     println!("listening on http://{}", &addr);
 
-    HttpServer::new(move || {
+    // Built once so every worker shares the same sessions
+    let store = session_store::store_from_env();
+    // Also shared, so an event published by one worker reaches subscribers held by another
+    let room_events = room_events::event_bus_from_env();
+
+    // Periodic work runs alongside the server - stopped once HttpServer has finished shutting down
+    let scheduler = scheduler::server_jobs(store.clone(), Tmdb::shared_instance())
+        .into_iter()
+        .fold(Scheduler::new(store.clone()), |scheduler, job| scheduler.job(job))
+        .start();
+    let scheduled_jobs = scheduler.jobs();
+
+    let server = HttpServer::new(move || {
         let leptos_options = &conf.leptos_options;
         let site_root = &leptos_options.site_root;
+        let store = store.clone();
+        let room_events = room_events.clone();
+        let context_options = leptos_options.clone();
+        let scheduled_jobs = scheduled_jobs.clone();
 
         App::new()
-            .wrap(SessionMiddleware::new(
-                CookieSessionStore::default(),
-                Key::generate(),
-            ))
             // serve JS/WASM/CSS from `pkg`
             .service(Files::new("/pkg", format!("{site_root}/pkg")))
             // serve other assets from the `assets` directory
             .service(Files::new("/assets", site_root))
             // serve the favicon from /favicon.ico
             .service(favicon)
+            .service(room_event_stream)
+            .service(job_status)
             //.service(session)
-            .leptos_routes(leptos_options.to_owned(), routes.to_owned(), App)
+            .leptos_routes_with_context(
+                leptos_options.to_owned(),
+                routes.to_owned(),
+                {
+                    let store = store.clone();
+                    let room_events = room_events.clone();
+                    move || {
+                        provide_context(store.clone());
+                        provide_context(room_events.clone());
+                        provide_context(context_options.clone());
+                    }
+                },
+                App,
+            )
             .app_data(web::Data::new(leptos_options.to_owned()))
+            .app_data(web::Data::new(store))
+            .app_data(web::Data::new(room_events))
+            .app_data(web::Data::new(scheduled_jobs))
         //.wrap(middleware::Compress::default())
     })
     .bind(&addr)?
     .run()
-    .await
+    .await;
+
+    scheduler.shutdown().await;
+
+    server
 }
 
 #[cfg(feature = "ssr")]
@@ -53,6 +89,60 @@ async fn favicon(
     ))?)
 }
 
+/* Server-sent events for one room - only members get to listen in */
+#[cfg(feature = "ssr")]
+#[actix_web::get("/api/rooms/{code}/events")]
+async fn room_event_stream(
+    req: HttpRequest,
+    code: web::Path<String>,
+    store: web::Data<std::sync::Arc<dyn moodie_server::session_store::SessionStore>>,
+    room_events: web::Data<std::sync::Arc<moodie_server::room_events::RoomEventBus>>,
+) -> HttpResponse {
+    use moodie_server::{room_events::sse_stream, rooms, session_cookie::session_id_from_request};
+
+    let session_id = match session_id_from_request(&req) {
+        Some(session_id) => session_id,
+        None => return HttpResponse::Unauthorized().body("No valid session"),
+    };
+
+    match rooms::load_room(store.as_ref().as_ref(), &code).await {
+        Err(err) => HttpResponse::NotFound().body(err.to_string()),
+        Ok(room) if room.member(&session_id).is_none() => {
+            HttpResponse::Forbidden().body(rooms::RoomError::NotMember.to_string())
+        }
+        Ok(room) => HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .insert_header(("Cache-Control", "no-cache"))
+            .streaming(sse_stream(room_events.subscribe(&room.code))),
+    }
+}
+
+/* Last run of every background job - only when ADMIN_TOKEN is set and sent as a bearer token */
+#[cfg(feature = "ssr")]
+#[actix_web::get("/api/admin/jobs")]
+async fn job_status(
+    req: HttpRequest,
+    store: web::Data<std::sync::Arc<dyn moodie_server::session_store::SessionStore>>,
+    jobs: web::Data<Vec<(String, std::time::Duration)>>,
+) -> HttpResponse {
+    use moodie_server::scheduler::{admin_authorized, job_reports};
+
+    let admin_token = std::env::var("ADMIN_TOKEN").ok();
+    let authorization = req
+        .headers()
+        .get(actix_web::http::header::AUTHORIZATION)
+        .and_then(|header| header.to_str().ok());
+
+    if !admin_authorized(admin_token.as_deref(), authorization) {
+        return HttpResponse::Unauthorized().body("Admin token required");
+    }
+
+    match job_reports(store.as_ref().as_ref(), &jobs).await {
+        Ok(reports) => HttpResponse::Ok().json(reports),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
 #[cfg(not(any(feature = "ssr", feature = "csr")))]
 pub fn main() {
     // no client-side main function
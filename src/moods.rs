@@ -0,0 +1,150 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use crate::*;
+
+    // Mood keywords start out as strong as a liked movie's, then decay like any other feedback
+    const MOOD_KEYWORD_WEIGHT: f64 = 2.0;
+
+    /* Genre and keyword IDs are TMDB's */
+    pub fn mood_catalogue() -> Vec<Mood> {
+        vec![
+            Mood {
+                id: String::from("cozy"),
+                name: String::from("Cozy"),
+                description: String::from("Something warm and low stakes for under a blanket"),
+                genres: vec![35, 10751, 10749],
+                include_keywords: vec![207317, 6054],
+                exclude_keywords: vec![14819, 10714],
+                runtime: Some(Runtime::Average),
+                sort_by: SortBy::Popularity,
+            },
+            Mood {
+                id: String::from("mind-bending"),
+                name: String::from("Mind-bending"),
+                description: String::from("You want to still be thinking about it tomorrow"),
+                genres: vec![878, 53, 9648],
+                include_keywords: vec![4379, 310, 12565],
+                exclude_keywords: vec![],
+                runtime: None,
+                sort_by: SortBy::Rating,
+            },
+            Mood {
+                id: String::from("tearjerker"),
+                name: String::from("Tearjerker"),
+                description: String::from("Tissues at the ready"),
+                genres: vec![18, 10749],
+                include_keywords: vec![9672],
+                exclude_keywords: vec![10123],
+                runtime: Some(Runtime::MovieNight),
+                sort_by: SortBy::Rating,
+            },
+            Mood {
+                id: String::from("adrenaline"),
+                name: String::from("Adrenaline"),
+                description: String::from("Chases, heists and no time to breathe"),
+                genres: vec![28, 53],
+                include_keywords: vec![10051, 9748],
+                exclude_keywords: vec![],
+                runtime: Some(Runtime::Average),
+                sort_by: SortBy::Popularity,
+            },
+            Mood {
+                id: String::from("laugh-out-loud"),
+                name: String::from("Laugh Out Loud"),
+                description: String::from("Nothing heavy, just jokes"),
+                genres: vec![35],
+                include_keywords: vec![9253, 11800, 8201],
+                exclude_keywords: vec![10123],
+                runtime: Some(Runtime::Quick),
+                sort_by: SortBy::Popularity,
+            },
+            Mood {
+                id: String::from("spooky"),
+                name: String::from("Spooky"),
+                description: String::from("Lights off, volume up"),
+                genres: vec![27],
+                include_keywords: vec![12339, 3133, 12377],
+                exclude_keywords: vec![],
+                runtime: Some(Runtime::Quick),
+                sort_by: SortBy::Rating,
+            },
+        ]
+    }
+
+    pub fn find_mood(id: &str) -> Option<Mood> {
+        mood_catalogue().into_iter().find(|mood| mood.id == id)
+    }
+
+    /* Only fills in what a mood says something about - providers and decade are left for the user */
+    pub fn apply_mood(criteria: &mut RecommendationCriteria, mood: &Mood) {
+        criteria.genres = Some(mood.genres.clone());
+        criteria.sort_by = Some(mood.sort_by.clone());
+
+        if let Some(runtime) = &mood.runtime {
+            criteria.runtime = Some(runtime.clone());
+        }
+
+        let weights = &mut criteria.keyword_profile.weights;
+        for id in &mood.include_keywords {
+            weights.insert(*id, MOOD_KEYWORD_WEIGHT);
+        }
+        for id in &mood.exclude_keywords {
+            weights.insert(*id, -MOOD_KEYWORD_WEIGHT);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn catalogue_ids_unique() {
+            let catalogue = mood_catalogue();
+            let mut ids: Vec<&String> = catalogue.iter().map(|mood| &mood.id).collect();
+            ids.sort();
+            ids.dedup();
+
+            assert_eq!(ids.len(), catalogue.len());
+            assert!(catalogue.iter().all(|mood| !mood.genres.is_empty()));
+        }
+
+        #[test]
+        fn applies_mood() {
+            let mut criteria = RecommendationCriteria {
+                watch_providers: Some(vec![8]),
+                runtime: Some(Runtime::MartinScorsese),
+                decade: Some(Decade::Nineties),
+                ..Default::default()
+            };
+
+            apply_mood(&mut criteria, &find_mood("cozy").unwrap());
+
+            assert_eq!(criteria.genres, Some(vec![35, 10751, 10749]));
+            assert_eq!(criteria.runtime, Some(Runtime::Average));
+            assert_eq!(criteria.sort_by, Some(SortBy::Popularity));
+            assert_eq!(criteria.keyword_profile.weights[&207317], MOOD_KEYWORD_WEIGHT);
+            assert_eq!(criteria.keyword_profile.weights[&14819], -MOOD_KEYWORD_WEIGHT);
+
+            // Untouched by the mood
+            assert_eq!(criteria.watch_providers, Some(vec![8]));
+            assert_eq!(criteria.decade, Some(Decade::Nineties));
+        }
+
+        #[test]
+        fn mood_without_runtime_keeps_existing() {
+            let mut criteria = RecommendationCriteria {
+                runtime: Some(Runtime::Quick),
+                ..Default::default()
+            };
+
+            apply_mood(&mut criteria, &find_mood("mind-bending").unwrap());
+
+            assert_eq!(criteria.runtime, Some(Runtime::Quick));
+            assert_eq!(criteria.sort_by, Some(SortBy::Rating));
+            assert!(find_mood("grumpy").is_none());
+        }
+    }
+}
+}
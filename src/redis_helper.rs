@@ -3,15 +3,22 @@ use cfg_if::cfg_if;
 cfg_if! {
     if #[cfg(feature = "ssr")] {
     use crate::*;
+    use async_trait::async_trait;
     use lazy_static::lazy_static;
     use redis::{Commands, Connection};
-    use uuid::Uuid;
+    use crate::session_store::{
+        decode_criteria, encode_criteria, new_session_id, SessionStore, SessionStoreError,
+        SESSION_TTL_SECONDS,
+    };
     use crate::RecommendationCriteria;
 
     //const CONNECTION_STRING: &str = "redis://localhost:6379";
 
+    // How many times an optimistic update is retried before giving up
+    const MAX_UPDATE_ATTEMPTS: u8 = 10;
+
     lazy_static! {
-        static ref CONNECTION_STRING: String = match std::env::var("REDIS_CONNECTION_STRING") {
+        pub static ref CONNECTION_STRING: String = match std::env::var("REDIS_CONNECTION_STRING") {
             Ok(con_string) => {
                 println!("Env connection string found...");
                 con_string
@@ -23,117 +30,235 @@ cfg_if! {
         };
     }
 
-    #[cfg(feature = "ssr")]
-    pub async fn criteria_from_cache(
-        session_id: &String,
-    ) -> Result<RecommendationCriteria, redis::RedisError> {
-        match get_connection() {
-            Ok(mut con) => {
-                let redis_result: String = con.get(session_id).expect("Error reading from redis");
+    impl From<redis::RedisError> for SessionStoreError {
+        fn from(err: redis::RedisError) -> Self {
+            SessionStoreError::Backend(err.to_string())
+        }
+    }
 
-                let criteria: RecommendationCriteria =
-                    serde_json::from_str(&redis_result).expect("Error parsing result");
+    /* Session store backed by Redis - sessions are stored as JSON strings keyed by session ID */
+    /* Every write resets the key's TTL, so active sessions never expire */
+    pub struct RedisSessionStore {
+        client: redis::Client,
+    }
 
-                Ok(criteria)
-            }
-            Err(err) => Err(err),
+    impl RedisSessionStore {
+        /* Opening a client does not connect - connections are made per request */
+        pub fn new(connection_string: &str) -> Result<Self, redis::RedisError> {
+            let client = redis::Client::open(connection_string)?;
+            Ok(Self { client })
+        }
+
+        pub fn from_env() -> Result<Self, redis::RedisError> {
+            Self::new(CONNECTION_STRING.as_str())
+        }
+
+        fn get_connection(&self) -> Result<Connection, redis::RedisError> {
+            self.client.get_connection()
         }
     }
 
-    #[cfg(feature = "ssr")]
-    pub async fn criteria_to_cache(
-        session_id: &String,
-        criteria: RecommendationCriteria,
-    ) -> Result<bool, redis::RedisError> {
-        match get_connection() {
-            Ok(mut con) => {
-                let json_string =
-                    serde_json::to_string(&criteria).expect("Unable to parse criteria");
-                Ok(con
-                    .set(session_id, json_string)
-                    .expect("Error writing to redis cache"))
+    #[async_trait]
+    impl SessionStore for RedisSessionStore {
+        async fn create(&self) -> Result<String, SessionStoreError> {
+            let mut con = self.get_connection()?;
+            let session_id = new_session_id();
+
+            let json_string = encode_criteria(&RecommendationCriteria::default())?;
+
+            let _: () = con.set_ex(&session_id, json_string, SESSION_TTL_SECONDS)?;
+
+            Ok(session_id)
+        }
+
+        async fn load(&self, session_id: &str) -> Result<RecommendationCriteria, SessionStoreError> {
+            let mut con = self.get_connection()?;
+
+            let redis_result: Option<String> = con.get(session_id)?;
+
+            match redis_result {
+                Some(json_string) => decode_criteria(&json_string),
+                None => Err(SessionStoreError::NotFound(session_id.to_string())),
             }
-            Err(err) => Err(err),
         }
-    }
 
-    #[cfg(feature= "ssr")]
-    pub async fn clear_session_feedback(session_id: &String) -> Result<(), redis::RedisError> {
-        println!("Clearing feedback data for {}", &session_id);
-        match get_connection() {
-            Ok(con) => {
-                let existing_criteria = criteria_from_cache(&session_id).await;
-                match existing_criteria {
-                    Ok(mut criteria) => {
-                        criteria.feedback = None;
-                        let response = criteria_to_cache(&session_id, criteria).await;
-                        match response {
-                            Ok(_) => Ok(()),
-                            Err(err) => Err(err)
-                        }
-                    },
-                    Err(err) => Err(err)
-                }
-            },
-            Err(err) => Err(err)
+        async fn save(
+            &self,
+            session_id: &str,
+            criteria: &RecommendationCriteria,
+        ) -> Result<(), SessionStoreError> {
+            let mut con = self.get_connection()?;
+
+            let json_string = encode_criteria(criteria)?;
+
+            let _: () = con.set_ex(session_id, json_string, SESSION_TTL_SECONDS)?;
+
+            Ok(())
         }
-    }
 
-    #[cfg(feature = "ssr")]
-    pub async fn start_recommendation_session() -> Result<String, redis::RedisError> {
-        match get_connection() {
-            Ok(mut con) => {
-                let session_id = Uuid::new_v4().to_string();
-
-                let criteria = RecommendationCriteria {
-                    genres: None,
-                    watch_providers: None,
-                    runtime: None,
-                    decade: None,
-                    feedback: None,
+        /* Optimistic locking - WATCH the key, then only commit the MULTI if nobody else wrote it */
+        async fn update(
+            &self,
+            session_id: &str,
+            update: &(dyn for<'c> Fn(&'c mut RecommendationCriteria) + Send + Sync),
+        ) -> Result<RecommendationCriteria, SessionStoreError> {
+            // Connections are per call, so the WATCH is dropped with it on any early return
+            let mut con = self.get_connection()?;
+
+            for _ in 0..MAX_UPDATE_ATTEMPTS {
+                let _: () = redis::cmd("WATCH").arg(session_id).query(&mut con)?;
+
+                let redis_result: Option<String> = con.get(session_id)?;
+
+                let mut criteria = match redis_result {
+                    Some(json_string) => decode_criteria(&json_string)?,
+                    None => return Err(SessionStoreError::NotFound(session_id.to_string())),
                 };
 
-                let json_string =
-                    serde_json::to_string(&criteria).expect("Unable to parse criteria");
+                update(&mut criteria);
 
-                let _: () = con.set(&session_id, json_string).unwrap();
+                let json_string = encode_criteria(&criteria)?;
 
-                Ok(session_id)
+                // EXEC comes back nil if the watched key changed underneath us
+                let committed: Option<()> = redis::pipe()
+                    .atomic()
+                    .set_ex(session_id, json_string, SESSION_TTL_SECONDS)
+                    .ignore()
+                    .query(&mut con)?;
+
+                if committed.is_some() {
+                    return Ok(criteria);
+                }
             }
-            Err(err) => Err(err),
+
+            Err(SessionStoreError::Backend(format!(
+                "Gave up updating {} after {} conflicting writes",
+                session_id, MAX_UPDATE_ATTEMPTS
+            )))
         }
-    }
 
-    #[cfg(feature = "ssr")]
-    fn get_connection() -> Result<Connection, redis::RedisError> {
-        match redis::Client::open(CONNECTION_STRING.as_str()) {
-            Ok(client) => client.get_connection(),
-            Err(err) => Err(err),
+        async fn delete(&self, session_id: &str) -> Result<(), SessionStoreError> {
+            let mut con = self.get_connection()?;
+
+            let _: () = con.del(session_id)?;
+
+            Ok(())
         }
-    }
 
-    #[cfg(feature = "ssr")]
-    pub async fn end_session(session_id: String) {
-        let mut con = get_connection().expect("Error connecting to redis");
+        async fn get_value(&self, key: &str) -> Result<Option<String>, SessionStoreError> {
+            let mut con = self.get_connection()?;
+
+            Ok(con.get(key)?)
+        }
 
-        let _: () = con.del(session_id).unwrap();
+        async fn set_value(
+            &self,
+            key: &str,
+            value: &str,
+            ttl_seconds: Option<u64>,
+        ) -> Result<(), SessionStoreError> {
+            let mut con = self.get_connection()?;
+
+            let _: () = match ttl_seconds {
+                Some(ttl_seconds) => con.set_ex(key, value, ttl_seconds)?,
+                None => con.set(key, value)?,
+            };
+
+            Ok(())
+        }
+
+        async fn delete_value(&self, key: &str) -> Result<(), SessionStoreError> {
+            let mut con = self.get_connection()?;
+
+            let _: () = con.del(key)?;
+
+            Ok(())
+        }
+
+        /* Same WATCH/MULTI loop as `update`, but for arbitrary keys that may not exist yet */
+        async fn update_value(
+            &self,
+            key: &str,
+            ttl_seconds: Option<u64>,
+            update: &(dyn Fn(Option<String>) -> Result<Option<String>, SessionStoreError>
+                  + Send
+                  + Sync),
+        ) -> Result<Option<String>, SessionStoreError> {
+            let mut con = self.get_connection()?;
+
+            for _ in 0..MAX_UPDATE_ATTEMPTS {
+                let _: () = redis::cmd("WATCH").arg(key).query(&mut con)?;
+
+                let current: Option<String> = con.get(key)?;
+
+                let updated = update(current)?;
+
+                let mut pipe = redis::pipe();
+                pipe.atomic();
+
+                match (&updated, ttl_seconds) {
+                    (Some(value), Some(ttl_seconds)) => pipe.set_ex(key, value, ttl_seconds).ignore(),
+                    (Some(value), None) => pipe.set(key, value).ignore(),
+                    (None, _) => pipe.del(key).ignore(),
+                };
+
+                let committed: Option<()> = pipe.query(&mut con)?;
+
+                if committed.is_some() {
+                    return Ok(updated);
+                }
+            }
+
+            Err(SessionStoreError::Backend(format!(
+                "Gave up updating {} after {} conflicting writes",
+                key, MAX_UPDATE_ATTEMPTS
+            )))
+        }
+
+        async fn set_value_if_absent(
+            &self,
+            key: &str,
+            value: &str,
+            ttl_seconds: u64,
+        ) -> Result<bool, SessionStoreError> {
+            let mut con = self.get_connection()?;
+
+            // Nil when the key was already there
+            let set: Option<String> = redis::cmd("SET")
+                .arg(key)
+                .arg(value)
+                .arg("NX")
+                .arg("EX")
+                .arg(ttl_seconds)
+                .query(&mut con)?;
+
+            Ok(set.is_some())
+        }
+
+        /* Redis expires keys on its own */
+        async fn purge_expired(&self) -> Result<usize, SessionStoreError> {
+            Ok(0)
+        }
     }
+
     #[cfg(test)]
     mod local_redis {
-        use std::vec;
+        use std::{sync::Arc, vec};
 
         use super::*;
         use redis::ConnectionLike;
 
         #[tokio::test]
         async fn redis_connection() {
-            let mut con = get_connection().unwrap();
+            let store = RedisSessionStore::from_env().unwrap();
+            let mut con = store.get_connection().unwrap();
             assert!(con.check_connection());
         }
 
         #[tokio::test]
         async fn redis_criteria_roundtrip() {
-            let session_id = start_recommendation_session().await;
+            let store = RedisSessionStore::from_env().unwrap();
+            let session_id = store.create().await;
 
             assert!(session_id.is_ok());
 
@@ -144,27 +269,58 @@ cfg_if! {
                 watch_providers: Some(vec![1]),
                 runtime: Some(Runtime::MovieNight),
                 decade: Some(Decade::Eighties),
-                feedback: None,
+                ..Default::default()
             };
 
-            let to_cache_result = criteria_to_cache(&session_id, criteria_start.clone()).await;
+            let to_cache_result = store.save(&session_id, &criteria_start).await;
 
             assert!(to_cache_result.is_ok());
 
-            let from_cache_result = criteria_from_cache(&session_id).await;
+            let from_cache_result = store.load(&session_id).await;
 
             assert!(from_cache_result.is_ok());
 
             assert_eq!(from_cache_result.unwrap(), criteria_start);
 
-            end_session(session_id).await;
+            store.delete(&session_id).await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn redis_concurrent_updates() {
+            let store = Arc::new(RedisSessionStore::from_env().unwrap());
+            let session_id = store.create().await.unwrap();
+
+            let mut handles = vec![];
+
+            for genre in 0..5 {
+                let store = Arc::clone(&store);
+                let session_id = session_id.clone();
+                handles.push(tokio::spawn(async move {
+                    store
+                        .update(&session_id, &|criteria| {
+                            criteria.genres.get_or_insert_with(Vec::new).push(genre)
+                        })
+                        .await
+                        .unwrap();
+                }));
+            }
+
+            for handle in handles {
+                handle.await.unwrap();
+            }
+
+            let mut genres = store.load(&session_id).await.unwrap().genres.unwrap();
+            genres.sort();
+
+            assert_eq!(genres, vec![0, 1, 2, 3, 4]);
+
+            store.delete(&session_id).await.unwrap();
         }
 
         #[tokio::test]
         async fn redis_start_session() {
-            let empty_criteria_string =
-            "{\"genres\":null,\"watch_providers\":null,\"runtime\":null,\"decade\":null,\"feedback\":null}";
-            let response = start_recommendation_session().await;
+            let store = RedisSessionStore::from_env().unwrap();
+            let response = store.create().await;
 
             assert!(response.is_ok());
 
@@ -172,12 +328,17 @@ cfg_if! {
 
             assert!(!session_id.is_empty());
 
-            let mut con = get_connection().unwrap();
+            let mut con = store.get_connection().unwrap();
             assert!(con.check_connection());
 
-            let empty_criteria: String = con.get(session_id).expect("Error fetching from redis");
+            let empty_criteria: String = con.get(&session_id).expect("Error fetching from redis");
+
+            assert_eq!(
+                decode_criteria(&empty_criteria).unwrap(),
+                RecommendationCriteria::default()
+            );
 
-            assert_eq!(empty_criteria, empty_criteria_string);
+            store.delete(&session_id).await.unwrap();
         }
     }
 }
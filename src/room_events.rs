@@ -0,0 +1,270 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex, Weak},
+        thread,
+        time::Duration,
+    };
+
+    use actix_web::web::Bytes;
+    use futures::Stream;
+    use redis::Commands;
+    use tokio::sync::broadcast::{self, error::RecvError};
+
+    use crate::redis_helper::CONNECTION_STRING;
+    use crate::*;
+
+    const ROOM_EVENTS_CHANNEL_PREFIX: &str = "room_events:";
+
+    // Slow subscribers skip ahead rather than holding up everyone else
+    const ROOM_EVENTS_BUFFER: usize = 32;
+
+    // How long the redis listener waits before reconnecting after losing its connection
+    const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+    /* Fans room events out to every subscriber of a room */
+    /* With redis, events go through pub/sub so subscribers on other server instances hear them too */
+    pub struct RoomEventBus {
+        channels: Mutex<HashMap<String, broadcast::Sender<RoomEvent>>>,
+        redis: Option<redis::Client>,
+    }
+
+    impl RoomEventBus {
+        /* Process-local - fine for a single instance, pairs with MemorySessionStore */
+        pub fn in_memory() -> Arc<Self> {
+            Arc::new(Self {
+                channels: Mutex::new(HashMap::new()),
+                redis: None,
+            })
+        }
+
+        /* Starts one listener thread per process, PSUBSCRIBEd to every room's channel */
+        pub fn redis(connection_string: &str) -> Result<Arc<Self>, redis::RedisError> {
+            let client = redis::Client::open(connection_string)?;
+
+            let bus = Arc::new(Self {
+                channels: Mutex::new(HashMap::new()),
+                redis: Some(client.clone()),
+            });
+
+            let listener_bus = Arc::downgrade(&bus);
+            thread::spawn(move || listen(listener_bus, client));
+
+            Ok(bus)
+        }
+
+        pub fn subscribe(&self, code: &str) -> broadcast::Receiver<RoomEvent> {
+            let mut channels = self.channels.lock().expect("Room event lock poisoned");
+
+            channels
+                .entry(code.to_string())
+                .or_insert_with(|| broadcast::channel(ROOM_EVENTS_BUFFER).0)
+                .subscribe()
+        }
+
+        pub fn publish(&self, code: &str, event: RoomEvent) -> Result<(), String> {
+            match &self.redis {
+                None => {
+                    self.deliver(code, event);
+                    Ok(())
+                }
+                // The listener thread delivers it back to this instance along with every other one
+                Some(client) => {
+                    let payload = serde_json::to_string(&event).map_err(|err| err.to_string())?;
+                    let mut con = client.get_connection().map_err(|err| err.to_string())?;
+                    let _: () = con
+                        .publish(format!("{}{}", ROOM_EVENTS_CHANNEL_PREFIX, code), payload)
+                        .map_err(|err| err.to_string())?;
+                    Ok(())
+                }
+            }
+        }
+
+        fn deliver(&self, code: &str, event: RoomEvent) {
+            let mut channels = self.channels.lock().expect("Room event lock poisoned");
+
+            if let Some(sender) = channels.get(code) {
+                // Err just means nobody is listening any more
+                let _ = sender.send(event);
+            }
+
+            channels.retain(|_, sender| sender.receiver_count() > 0);
+        }
+    }
+
+    /* Runs until the bus is dropped - reconnects if redis goes away */
+    fn listen(bus: Weak<RoomEventBus>, client: redis::Client) {
+        while bus.strong_count() > 0 {
+            if let Err(err) = listen_once(&bus, &client) {
+                println!("Room event listener lost redis: {}", err);
+            }
+            thread::sleep(RECONNECT_DELAY);
+        }
+    }
+
+    fn listen_once(bus: &Weak<RoomEventBus>, client: &redis::Client) -> Result<(), redis::RedisError> {
+        let mut con = client.get_connection()?;
+        let mut pubsub = con.as_pubsub();
+        pubsub.psubscribe(format!("{}*", ROOM_EVENTS_CHANNEL_PREFIX))?;
+        // Lets the loop notice a dropped bus even when no rooms are active
+        pubsub.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+        loop {
+            let message = match pubsub.get_message() {
+                Ok(message) => message,
+                Err(err) if err.is_timeout() => match bus.upgrade() {
+                    Some(_) => continue,
+                    None => return Ok(()),
+                },
+                Err(err) => return Err(err),
+            };
+
+            let bus = match bus.upgrade() {
+                Some(bus) => bus,
+                None => return Ok(()),
+            };
+
+            let channel = message.get_channel_name();
+            let code = channel.trim_start_matches(ROOM_EVENTS_CHANNEL_PREFIX);
+
+            match message
+                .get_payload::<String>()
+                .map(|payload| serde_json::from_str::<RoomEvent>(&payload))
+            {
+                Ok(Ok(event)) => bus.deliver(code, event),
+                _ => println!("Ignoring malformed room event on {}", channel),
+            }
+        }
+    }
+
+    /* Same SESSION_STORE switch as the session store, so memory sessions get memory events */
+    pub fn event_bus_from_env() -> Arc<RoomEventBus> {
+        match std::env::var("SESSION_STORE") {
+            Ok(backend) if backend.trim().eq_ignore_ascii_case("memory") => {
+                println!("Using in-memory room events...");
+                RoomEventBus::in_memory()
+            }
+            _ => {
+                println!("Using redis room events...");
+                RoomEventBus::redis(CONNECTION_STRING.as_str())
+                    .expect("Invalid redis connection string")
+            }
+        }
+    }
+
+    /* Server-sent event framing - one JSON event per message */
+    pub fn sse_message(event: &RoomEvent) -> String {
+        format!(
+            "data: {}\n\n",
+            serde_json::to_string(event).expect("Room events always serialize")
+        )
+    }
+
+    /* Ends when the room's channel goes away - a lagging subscriber just skips what it missed, the next event refetches the room anyway */
+    pub fn sse_stream(
+        receiver: broadcast::Receiver<RoomEvent>,
+    ) -> impl Stream<Item = Result<Bytes, actix_web::Error>> {
+        futures::stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((Ok(Bytes::from(sse_message(&event))), receiver)),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn memory_publish_subscribe() {
+            let bus = RoomEventBus::in_memory();
+
+            let mut receiver = bus.subscribe("ABC234");
+            let mut other_room = bus.subscribe("XYZ789");
+
+            bus.publish(
+                "ABC234",
+                RoomEvent::MemberJoined {
+                    name: String::from("Guest"),
+                },
+            )
+            .unwrap();
+
+            assert_eq!(
+                receiver.recv().await.unwrap(),
+                RoomEvent::MemberJoined {
+                    name: String::from("Guest"),
+                }
+            );
+            assert!(other_room.try_recv().is_err());
+        }
+
+        #[test]
+        fn channels_dropped_with_subscribers() {
+            let bus = RoomEventBus::in_memory();
+
+            drop(bus.subscribe("ABC234"));
+
+            bus.publish("ABC234", RoomEvent::VotingStarted).unwrap();
+
+            assert!(bus.channels.lock().unwrap().is_empty());
+        }
+
+        #[test]
+        fn sse_framing() {
+            assert_eq!(sse_message(&RoomEvent::VotingStarted), "data: \"VotingStarted\"\n\n");
+        }
+
+        #[tokio::test]
+        async fn stream_frames_events() {
+            use futures::StreamExt;
+
+            let bus = RoomEventBus::in_memory();
+            let mut stream = Box::pin(sse_stream(bus.subscribe("ABC234")));
+
+            bus.publish(
+                "ABC234",
+                RoomEvent::VoteCast {
+                    name: String::from("Guest"),
+                },
+            )
+            .unwrap();
+
+            assert_eq!(
+                stream.next().await.unwrap().unwrap(),
+                Bytes::from("data: {\"VoteCast\":{\"name\":\"Guest\"}}\n\n")
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod local_redis {
+        use super::*;
+
+        #[tokio::test]
+        async fn redis_publish_subscribe() {
+            let bus = RoomEventBus::redis(CONNECTION_STRING.as_str()).unwrap();
+            let mut receiver = bus.subscribe("REDIS2");
+
+            // Give the listener thread a moment to subscribe
+            tokio::time::sleep(Duration::from_millis(200)).await;
+
+            bus.publish("REDIS2", RoomEvent::VotingStarted).unwrap();
+
+            let event = tokio::time::timeout(Duration::from_secs(2), receiver.recv())
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(event, RoomEvent::VotingStarted);
+        }
+    }
+}
+}
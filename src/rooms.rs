@@ -0,0 +1,658 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::{collections::HashMap, fmt};
+
+    use serde::{Deserialize, Serialize};
+    use uuid::Uuid;
+
+    use crate::session_store::{load_json, SessionStore, SessionStoreError};
+    use crate::*;
+
+    const ROOM_KEY_PREFIX: &str = "room:";
+
+    // Rooms are for one evening - they don't need to outlive the night
+    pub const ROOM_TTL_SECONDS: u64 = 60 * 60 * 12;
+
+    // No 0/O or 1/I so codes can be read out loud
+    const ROOM_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    const ROOM_CODE_LENGTH: usize = 6;
+
+    // At most this many genres are ANDed together in the joint discover query
+    const MAX_ROOM_GENRES: usize = 3;
+    pub const ROOM_CANDIDATES: usize = 10;
+
+    #[derive(Debug)]
+    pub enum RoomError {
+        NotFound(String),
+        NotMember,
+        NotHost,
+        InvalidName(String),
+        WrongStatus(RoomStatus),
+        NoCommonProviders,
+        MissingPreferences,
+        UnknownCandidate(i64),
+        Store(SessionStoreError),
+    }
+
+    impl fmt::Display for RoomError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                RoomError::NotFound(code) => write!(f, "No room found for code {}", code),
+                RoomError::NotMember => write!(f, "You haven't joined this room"),
+                RoomError::NotHost => write!(f, "Only the host can do that"),
+                RoomError::InvalidName(reason) => write!(f, "Invalid name: {}", reason),
+                RoomError::WrongStatus(status) => write!(f, "The room is {:?}", status),
+                RoomError::NoCommonProviders => {
+                    write!(f, "Nobody in the room shares a streaming service")
+                }
+                RoomError::MissingPreferences => {
+                    write!(f, "Every member needs to pick providers and genres first")
+                }
+                RoomError::UnknownCandidate(movie_id) => {
+                    write!(f, "Movie {} isn't one of the room's options", movie_id)
+                }
+                RoomError::Store(err) => write!(f, "{}", err),
+            }
+        }
+    }
+
+    impl std::error::Error for RoomError {}
+
+    impl From<SessionStoreError> for RoomError {
+        fn from(err: SessionStoreError) -> Self {
+            RoomError::Store(err)
+        }
+    }
+
+    impl From<serde_json::Error> for RoomError {
+        fn from(err: serde_json::Error) -> Self {
+            RoomError::Store(SessionStoreError::Serialization(err))
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+    pub struct RoomMember {
+        pub session_id: String,
+        pub name: String,
+        pub watch_providers: Option<Vec<i32>>,
+        pub genres: Option<Vec<i32>>,
+        pub vote: Option<i64>,
+    }
+
+    impl RoomMember {
+        fn new(session_id: &str, name: String) -> Self {
+            Self {
+                session_id: session_id.to_string(),
+                name,
+                watch_providers: None,
+                genres: None,
+                vote: None,
+            }
+        }
+
+        pub fn is_ready(&self) -> bool {
+            self.watch_providers.is_some() && self.genres.is_some()
+        }
+    }
+
+    /* Members are identified by their session ID - the host is always members[0] */
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct Room {
+        pub code: String,
+        pub status: RoomStatus,
+        pub runtime: Runtime,
+        pub decade: Decade,
+        pub members: Vec<RoomMember>,
+        pub candidates: Vec<Movie>,
+        pub winner: Option<i64>,
+    }
+
+    impl Room {
+        pub fn host(&self) -> &RoomMember {
+            &self.members[0]
+        }
+
+        pub fn is_host(&self, session_id: &str) -> bool {
+            self.host().session_id == session_id
+        }
+
+        pub fn member(&self, session_id: &str) -> Option<&RoomMember> {
+            self.members.iter().find(|member| member.session_id == session_id)
+        }
+
+        fn member_mut(&mut self, session_id: &str) -> Result<&mut RoomMember, RoomError> {
+            self.members
+                .iter_mut()
+                .find(|member| member.session_id == session_id)
+                .ok_or(RoomError::NotMember)
+        }
+
+        fn require_status(&self, status: RoomStatus) -> Result<(), RoomError> {
+            match self.status == status {
+                true => Ok(()),
+                false => Err(RoomError::WrongStatus(self.status.clone())),
+            }
+        }
+
+        /* Only services every member has - nobody should end up with a movie they can't stream */
+        pub fn joint_providers(&self) -> Result<Vec<i32>, RoomError> {
+            let mut members = self.members.iter();
+
+            let mut providers = match members.next().and_then(|member| member.watch_providers.clone()) {
+                Some(providers) => providers,
+                None => return Err(RoomError::MissingPreferences),
+            };
+
+            for member in members {
+                match &member.watch_providers {
+                    Some(member_providers) => providers.retain(|id| member_providers.contains(id)),
+                    None => return Err(RoomError::MissingPreferences),
+                }
+            }
+
+            match providers.is_empty() {
+                true => Err(RoomError::NoCommonProviders),
+                false => Ok(providers),
+            }
+        }
+
+        /* Genres are weighted by how many members picked them - anything at least half the room wants is kept */
+        /* If nothing is that popular the single most picked genre is used so the query never ends up empty */
+        pub fn joint_genres(&self) -> Result<Vec<i32>, RoomError> {
+            let mut counts: HashMap<i32, usize> = HashMap::new();
+
+            for member in &self.members {
+                match &member.genres {
+                    Some(genres) => {
+                        for genre in genres {
+                            *counts.entry(*genre).or_insert(0) += 1;
+                        }
+                    }
+                    None => return Err(RoomError::MissingPreferences),
+                }
+            }
+
+            let mut ranked: Vec<(i32, usize)> = counts.into_iter().collect();
+            ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+            let threshold = self.members.len().div_ceil(2);
+
+            let mut genres: Vec<i32> = ranked
+                .iter()
+                .filter(|(_, count)| *count >= threshold)
+                .take(MAX_ROOM_GENRES)
+                .map(|(genre, _)| *genre)
+                .collect();
+
+            if genres.is_empty() {
+                genres.extend(ranked.first().map(|(genre, _)| *genre));
+            }
+
+            match genres.is_empty() {
+                true => Err(RoomError::MissingPreferences),
+                false => Ok(genres),
+            }
+        }
+
+        pub fn votes(&self) -> HashMap<i64, u32> {
+            let mut votes: HashMap<i64, u32> = HashMap::new();
+            for movie_id in self.members.iter().filter_map(|member| member.vote) {
+                *votes.entry(movie_id).or_insert(0) += 1;
+            }
+            votes
+        }
+
+        /* Most votes wins - ties go to whichever candidate TMDB ranked higher */
+        fn leading_candidate(&self) -> Option<i64> {
+            let votes = self.votes();
+
+            self.candidates
+                .iter()
+                .filter_map(|movie| votes.get(&movie.id).map(|count| (movie.id, *count)))
+                .fold(None, |best: Option<(i64, u32)>, (movie_id, count)| match best {
+                    Some((_, best_count)) if best_count >= count => best,
+                    _ => Some((movie_id, count)),
+                })
+                .map(|(movie_id, _)| movie_id)
+        }
+
+        /* What a member's browser gets to see - other members' session IDs stay on the server */
+        pub fn view_for(&self, session_id: &str) -> RoomView {
+            let votes = self.votes();
+
+            RoomView {
+                code: self.code.clone(),
+                status: self.status.clone(),
+                is_host: self.is_host(session_id),
+                my_vote: self.member(session_id).and_then(|member| member.vote),
+                members: self
+                    .members
+                    .iter()
+                    .map(|member| RoomMemberView {
+                        name: member.name.clone(),
+                        ready: member.is_ready(),
+                        voted: member.vote.is_some(),
+                    })
+                    .collect(),
+                candidates: self
+                    .candidates
+                    .iter()
+                    .map(|movie| RoomCandidate {
+                        movie: movie.clone(),
+                        votes: *votes.get(&movie.id).unwrap_or(&0),
+                    })
+                    .collect(),
+                winner: self
+                    .winner
+                    .and_then(|winner| self.candidates.iter().find(|movie| movie.id == winner))
+                    .cloned(),
+            }
+        }
+    }
+
+    /* The joint query matches any of the room's genres - candidates hitting the more popular ones are put first */
+    pub fn rank_by_genres(mut candidates: Vec<Movie>, genres: &[i32]) -> Vec<Movie> {
+        let score = |movie: &Movie| -> usize {
+            genres
+                .iter()
+                .enumerate()
+                .filter(|(_, genre)| movie.genre_ids.contains(genre))
+                .map(|(rank, _)| genres.len() - rank)
+                .sum()
+        };
+
+        // Stable, so TMDB's order still breaks ties
+        candidates.sort_by_key(|movie| std::cmp::Reverse(score(movie)));
+        candidates
+    }
+
+    fn room_key(code: &str) -> String {
+        format!("{}{}", ROOM_KEY_PREFIX, code)
+    }
+
+    pub fn normalize_room_code(code: &str) -> String {
+        code.trim().to_uppercase()
+    }
+
+    fn new_room_code() -> String {
+        Uuid::new_v4()
+            .as_bytes()
+            .iter()
+            .take(ROOM_CODE_LENGTH)
+            .map(|byte| ROOM_CODE_ALPHABET[*byte as usize % ROOM_CODE_ALPHABET.len()] as char)
+            .collect()
+    }
+
+    fn validate_member_name(name: &str) -> Result<String, RoomError> {
+        let name = name.trim();
+
+        match name.chars().count() {
+            0 => Err(RoomError::InvalidName(String::from("can't be empty"))),
+            count if count > 24 => Err(RoomError::InvalidName(String::from(
+                "must be 24 characters or less",
+            ))),
+            _ => Ok(name.to_string()),
+        }
+    }
+
+    pub async fn load_room(store: &dyn SessionStore, code: &str) -> Result<Room, RoomError> {
+        let code = normalize_room_code(code);
+
+        match load_json(store, &room_key(&code)).await? {
+            Some(room) => Ok(room),
+            None => Err(RoomError::NotFound(code)),
+        }
+    }
+
+    /* Same read-modify-write as session criteria - concurrent joins and votes can't clobber each other */
+    pub async fn update_room<F>(store: &dyn SessionStore, code: &str, update: F) -> Result<Room, RoomError>
+    where
+        F: Fn(&mut Room) -> Result<(), RoomError> + Send + Sync,
+    {
+        let code = normalize_room_code(code);
+
+        // The closure can only hand back a SessionStoreError, so room errors are stashed and re-raised after
+        let room_error = std::sync::Mutex::new(None);
+
+        let stored = store
+            .update_value(&room_key(&code), Some(ROOM_TTL_SECONDS), &|current| {
+                let mut room: Room = match current {
+                    Some(json_string) => serde_json::from_str(&json_string)?,
+                    None => return Err(SessionStoreError::NotFound(code.clone())),
+                };
+
+                match update(&mut room) {
+                    Ok(()) => Ok(Some(serde_json::to_string(&room)?)),
+                    Err(err) => {
+                        *room_error.lock().expect("Room error lock poisoned") = Some(err);
+                        Err(SessionStoreError::Backend(String::from("Room update rejected")))
+                    }
+                }
+            })
+            .await;
+
+        if let Some(err) = room_error.lock().expect("Room error lock poisoned").take() {
+            return Err(err);
+        }
+
+        match stored {
+            Ok(Some(json_string)) => Ok(serde_json::from_str(&json_string)?),
+            Ok(None) | Err(SessionStoreError::NotFound(_)) => Err(RoomError::NotFound(code)),
+            Err(err) => Err(RoomError::Store(err)),
+        }
+    }
+
+    pub async fn create_room(
+        store: &dyn SessionStore,
+        host_session_id: &str,
+        host_name: &str,
+        runtime: Runtime,
+        decade: Decade,
+    ) -> Result<Room, RoomError> {
+        let host = RoomMember::new(host_session_id, validate_member_name(host_name)?);
+
+        // Codes are short, so keep drawing until one isn't already in use
+        loop {
+            let room = Room {
+                code: new_room_code(),
+                status: RoomStatus::Gathering,
+                runtime: runtime.clone(),
+                decade: decade.clone(),
+                members: vec![host.clone()],
+                candidates: vec![],
+                winner: None,
+            };
+
+            let room_json = serde_json::to_string(&room)?;
+
+            let stored = store
+                .update_value(&room_key(&room.code), Some(ROOM_TTL_SECONDS), &|current| match current {
+                    Some(existing) => Ok(Some(existing)),
+                    None => Ok(Some(room_json.clone())),
+                })
+                .await?;
+
+            if stored.as_deref() == Some(room_json.as_str()) {
+                return Ok(room);
+            }
+        }
+    }
+
+    /* Joining twice just renames the member */
+    pub async fn join_room(
+        store: &dyn SessionStore,
+        code: &str,
+        session_id: &str,
+        name: &str,
+    ) -> Result<Room, RoomError> {
+        let name = validate_member_name(name)?;
+
+        update_room(store, code, |room| {
+            if let Ok(member) = room.member_mut(session_id) {
+                member.name = name.clone();
+                return Ok(());
+            }
+
+            room.require_status(RoomStatus::Gathering)?;
+            room.members.push(RoomMember::new(session_id, name.clone()));
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn post_preferences(
+        store: &dyn SessionStore,
+        code: &str,
+        session_id: &str,
+        watch_providers: Vec<i32>,
+        genres: Vec<i32>,
+    ) -> Result<Room, RoomError> {
+        update_room(store, code, |room| {
+            room.require_status(RoomStatus::Gathering)?;
+            let member = room.member_mut(session_id)?;
+            member.watch_providers = Some(watch_providers.clone());
+            member.genres = Some(genres.clone());
+            Ok(())
+        })
+        .await
+    }
+
+    /* The discover call happens outside the room update, so the candidates are checked against the status again here */
+    pub async fn open_voting(
+        store: &dyn SessionStore,
+        code: &str,
+        session_id: &str,
+        candidates: Vec<Movie>,
+    ) -> Result<Room, RoomError> {
+        update_room(store, code, |room| {
+            if !room.is_host(session_id) {
+                return Err(RoomError::NotHost);
+            }
+            room.require_status(RoomStatus::Gathering)?;
+            room.candidates = candidates.iter().take(ROOM_CANDIDATES).cloned().collect();
+            room.status = RoomStatus::Voting;
+            Ok(())
+        })
+        .await
+    }
+
+    /* Voting ends on its own once everyone has voted */
+    pub async fn vote(
+        store: &dyn SessionStore,
+        code: &str,
+        session_id: &str,
+        movie_id: i64,
+    ) -> Result<Room, RoomError> {
+        update_room(store, code, |room| {
+            room.require_status(RoomStatus::Voting)?;
+            if !room.candidates.iter().any(|movie| movie.id == movie_id) {
+                return Err(RoomError::UnknownCandidate(movie_id));
+            }
+            room.member_mut(session_id)?.vote = Some(movie_id);
+
+            if room.members.iter().all(|member| member.vote.is_some()) {
+                room.winner = room.leading_candidate();
+                room.status = RoomStatus::Decided;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /* Lets the host call it without waiting on stragglers */
+    pub async fn close_voting(
+        store: &dyn SessionStore,
+        code: &str,
+        session_id: &str,
+    ) -> Result<Room, RoomError> {
+        update_room(store, code, |room| {
+            if !room.is_host(session_id) {
+                return Err(RoomError::NotHost);
+            }
+            room.require_status(RoomStatus::Voting)?;
+            room.winner = room.leading_candidate();
+            room.status = RoomStatus::Decided;
+            Ok(())
+        })
+        .await
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::session_store::MemorySessionStore;
+
+        async fn get_room(store: &MemorySessionStore) -> Room {
+            create_room(store, "host", "Host", Runtime::MovieNight, Decade::Recent)
+                .await
+                .unwrap()
+        }
+
+        #[test]
+        fn room_codes() {
+            let code = new_room_code();
+
+            assert_eq!(code.len(), ROOM_CODE_LENGTH);
+            assert!(code.bytes().all(|c| ROOM_CODE_ALPHABET.contains(&c)));
+            assert_eq!(normalize_room_code(&format!(" {} ", code.to_lowercase())), code);
+        }
+
+        #[tokio::test]
+        async fn join_and_post_preferences() {
+            let store = MemorySessionStore::new();
+            let room = get_room(&store).await;
+
+            join_room(&store, &room.code.to_lowercase(), "guest", "Guest").await.unwrap();
+            // Joining again keeps a single entry
+            join_room(&store, &room.code, "guest", "Guest 2").await.unwrap();
+
+            post_preferences(&store, &room.code, "host", vec![8, 15, 337], vec![28, 35]).await.unwrap();
+            let room = post_preferences(&store, &room.code, "guest", vec![15, 8], vec![35, 27])
+                .await
+                .unwrap();
+
+            assert_eq!(room.members.len(), 2);
+            assert_eq!(room.members[1].name, "Guest 2");
+            assert_eq!(room.joint_providers().unwrap(), vec![8, 15]);
+            assert_eq!(room.joint_genres().unwrap(), vec![35, 27, 28]);
+
+            assert!(matches!(
+                post_preferences(&store, &room.code, "stranger", vec![8], vec![28]).await,
+                Err(RoomError::NotMember)
+            ));
+        }
+
+        #[tokio::test]
+        async fn missing_room() {
+            let store = MemorySessionStore::new();
+
+            assert!(matches!(
+                join_room(&store, "NOPE42", "guest", "Guest").await,
+                Err(RoomError::NotFound(_))
+            ));
+        }
+
+        #[test]
+        fn joint_preferences() {
+            let mut room = Room {
+                code: String::from("ABC234"),
+                status: RoomStatus::Gathering,
+                runtime: Runtime::Average,
+                decade: Decade::Recent,
+                members: vec![
+                    RoomMember::new("a", String::from("A")),
+                    RoomMember::new("b", String::from("B")),
+                    RoomMember::new("c", String::from("C")),
+                ],
+                candidates: vec![],
+                winner: None,
+            };
+
+            assert!(matches!(room.joint_providers(), Err(RoomError::MissingPreferences)));
+
+            room.members[0].watch_providers = Some(vec![8, 15]);
+            room.members[1].watch_providers = Some(vec![8]);
+            room.members[2].watch_providers = Some(vec![337]);
+
+            assert!(matches!(room.joint_providers(), Err(RoomError::NoCommonProviders)));
+
+            // Nobody agrees, so the most picked (lowest ID on a tie) genre wins
+            room.members[0].genres = Some(vec![28]);
+            room.members[1].genres = Some(vec![35]);
+            room.members[2].genres = Some(vec![27]);
+
+            assert_eq!(room.joint_genres().unwrap(), vec![27]);
+
+            room.members[2].genres = Some(vec![35, 28]);
+
+            assert_eq!(room.joint_genres().unwrap(), vec![28, 35]);
+        }
+
+        #[test]
+        fn candidates_ranked_by_genre_weight() {
+            let genre_movie = |id: i64, genre_ids: Vec<i32>| Movie {
+                genre_ids,
+                ..test_movie(id)
+            };
+
+            let ranked = rank_by_genres(
+                vec![
+                    genre_movie(1, vec![18]),
+                    genre_movie(2, vec![27]),
+                    genre_movie(3, vec![35, 27]),
+                    genre_movie(4, vec![35]),
+                    genre_movie(5, vec![99]),
+                ],
+                &[35, 27, 18],
+            );
+
+            let ids: Vec<i64> = ranked.iter().map(|movie| movie.id).collect();
+            assert_eq!(ids, vec![3, 4, 2, 1, 5]);
+        }
+
+        #[tokio::test]
+        async fn voting() {
+            let store = MemorySessionStore::new();
+            let room = get_room(&store).await;
+            join_room(&store, &room.code, "guest", "Guest").await.unwrap();
+            join_room(&store, &room.code, "third", "Third").await.unwrap();
+
+            let candidates = vec![test_movie(1), test_movie(2), test_movie(3)];
+
+            assert!(matches!(
+                open_voting(&store, &room.code, "guest", candidates.clone()).await,
+                Err(RoomError::NotHost)
+            ));
+
+            open_voting(&store, &room.code, "host", candidates).await.unwrap();
+
+            // Nobody can join once voting has started
+            assert!(matches!(
+                join_room(&store, &room.code, "late", "Late").await,
+                Err(RoomError::WrongStatus(RoomStatus::Voting))
+            ));
+            assert!(matches!(
+                vote(&store, &room.code, "host", 4).await,
+                Err(RoomError::UnknownCandidate(4))
+            ));
+
+            vote(&store, &room.code, "host", 2).await.unwrap();
+            vote(&store, &room.code, "guest", 1).await.unwrap();
+            let room = vote(&store, &room.code, "third", 2).await.unwrap();
+
+            assert_eq!(room.status, RoomStatus::Decided);
+            assert_eq!(room.winner, Some(2));
+
+            let view = room.view_for("guest");
+
+            assert!(!view.is_host);
+            assert_eq!(view.my_vote, Some(1));
+            assert_eq!(view.winner, Some(test_movie(2)));
+            assert_eq!(view.candidates[1].votes, 2);
+        }
+
+        #[tokio::test]
+        async fn host_closes_voting() {
+            let store = MemorySessionStore::new();
+            let room = get_room(&store).await;
+            join_room(&store, &room.code, "guest", "Guest").await.unwrap();
+
+            open_voting(&store, &room.code, "host", vec![test_movie(1), test_movie(2)])
+                .await
+                .unwrap();
+            vote(&store, &room.code, "guest", 2).await.unwrap();
+
+            assert!(matches!(
+                close_voting(&store, &room.code, "guest").await,
+                Err(RoomError::NotHost)
+            ));
+
+            let room = close_voting(&store, &room.code, "host").await.unwrap();
+
+            assert_eq!(room.winner, Some(2));
+        }
+    }
+}
+}
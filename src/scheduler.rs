@@ -0,0 +1,433 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+    use rand::Rng;
+    use serde::{Deserialize, Serialize};
+    use subtle::ConstantTimeEq;
+    use tokio::{sync::watch, task::JoinHandle};
+    use uuid::Uuid;
+
+    use crate::availability::{self, unix_now};
+    use crate::session_store::{load_json, update_json, SessionStore, SessionStoreError};
+    use crate::tmdb::Tmdb;
+    use crate::tmdb_cache;
+
+    const LOCK_KEY_PREFIX: &str = "job_lock:";
+    const STATUS_KEY_PREFIX: &str = "job_status:";
+
+    // How long a running job gets to finish once the server is stopping
+    const SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+
+    const CACHE_WARM_INTERVAL: Duration = Duration::from_secs(60 * 60 * 12);
+    const SESSION_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+    type JobFuture = Pin<Box<dyn Future<Output = Result<String, String>> + Send>>;
+
+    /* A named bit of periodic work - the Ok message ends up in the job's status */
+    pub struct Job {
+        name: String,
+        interval: Duration,
+        jitter: Duration,
+        run: Arc<dyn Fn() -> JobFuture + Send + Sync>,
+    }
+
+    impl Job {
+        pub fn new<F, Fut>(name: &str, interval: Duration, run: F) -> Self
+        where
+            F: Fn() -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<String, String>> + Send + 'static,
+        {
+            Self {
+                name: name.to_string(),
+                interval,
+                jitter: Duration::ZERO,
+                run: Arc::new(move || Box::pin(run())),
+            }
+        }
+
+        /* Up to this much is added to every wait, so instances started together don't all fire at once */
+        pub fn jitter(mut self, jitter: Duration) -> Self {
+            self.jitter = jitter;
+            self
+        }
+
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn next_delay(&self) -> Duration {
+            self.interval + random_jitter(self.jitter)
+        }
+    }
+
+    fn random_jitter(jitter: Duration) -> Duration {
+        match jitter.as_millis() as u64 {
+            0 => Duration::ZERO,
+            max => Duration::from_millis(rand::thread_rng().gen_range(0..=max)),
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+    #[serde(tag = "status", content = "detail")]
+    pub enum JobOutcome {
+        Succeeded(String),
+        Failed(String),
+        TimedOut,
+    }
+
+    /* The last run of a job by any instance - kept in the session store so every instance reports the same thing */
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+    #[serde(default)]
+    pub struct JobStatus {
+        pub instance: String,
+        pub started_at: u64,
+        pub finished_at: u64,
+        pub outcome: Option<JobOutcome>,
+        pub runs: u64,
+    }
+
+    #[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+    pub struct JobReport {
+        pub name: String,
+        pub interval_seconds: u64,
+        pub last_run: Option<JobStatus>,
+    }
+
+    /* The lock lasts one interval (at least a second) - whichever instance takes it runs the job for that interval, everyone else skips */
+    fn lock_ttl(interval: Duration) -> Duration {
+        Duration::from_secs(interval.as_secs().max(1))
+    }
+
+    /* Runs are cut off a tenth of the way before the lock expires, so a slow run never outlives its lock */
+    fn run_timeout(interval: Duration) -> Duration {
+        let ttl = lock_ttl(interval);
+        ttl - ttl / 10
+    }
+
+    pub async fn try_lock(
+        store: &dyn SessionStore,
+        job: &str,
+        instance: &str,
+        interval: Duration,
+    ) -> Result<bool, SessionStoreError> {
+        store
+            .set_value_if_absent(
+                &format!("{}{}", LOCK_KEY_PREFIX, job),
+                instance,
+                lock_ttl(interval).as_secs(),
+            )
+            .await
+    }
+
+    async fn record_run(
+        store: &dyn SessionStore,
+        job: &str,
+        instance: &str,
+        started_at: u64,
+        outcome: JobOutcome,
+    ) -> Result<(), SessionStoreError> {
+        let finished_at = unix_now();
+
+        update_json(
+            store,
+            &format!("{}{}", STATUS_KEY_PREFIX, job),
+            None,
+            |status: &mut JobStatus| {
+                status.instance = instance.to_string();
+                status.started_at = started_at;
+                status.finished_at = finished_at;
+                status.outcome = Some(outcome.clone());
+                status.runs += 1;
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /* One tick of a job - returns the outcome, or None if another instance has this interval */
+    pub async fn run_once(store: &dyn SessionStore, job: &Job, instance: &str) -> Option<JobOutcome> {
+        match try_lock(store, &job.name, instance, job.interval).await {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(err) => {
+                println!("Error taking lock for job {}: {}", job.name, err);
+                return None;
+            }
+        }
+
+        let started_at = unix_now();
+        let outcome = match tokio::time::timeout(run_timeout(job.interval), (job.run)()).await {
+            Ok(Ok(message)) => JobOutcome::Succeeded(message),
+            Ok(Err(err)) => JobOutcome::Failed(err),
+            Err(_) => JobOutcome::TimedOut,
+        };
+
+        println!("Job {} finished: {:?}", job.name, outcome);
+
+        if let Err(err) = record_run(store, &job.name, instance, started_at, outcome.clone()).await {
+            println!("Error recording status for job {}: {}", job.name, err);
+        }
+
+        Some(outcome)
+    }
+
+    pub struct Scheduler {
+        store: Arc<dyn SessionStore>,
+        jobs: Vec<Job>,
+    }
+
+    impl Scheduler {
+        pub fn new(store: Arc<dyn SessionStore>) -> Self {
+            Self { store, jobs: vec![] }
+        }
+
+        pub fn job(mut self, job: Job) -> Self {
+            self.jobs.push(job);
+            self
+        }
+
+        /* Each job gets its own task - the first run waits out one jitter, not a whole interval */
+        pub fn start(self) -> SchedulerHandle {
+            let instance = Uuid::new_v4().to_string();
+            let (shutdown, stopping) = watch::channel(false);
+
+            let jobs: Vec<(String, Duration)> = self
+                .jobs
+                .iter()
+                .map(|job| (job.name.clone(), job.interval))
+                .collect();
+
+            let tasks = self
+                .jobs
+                .into_iter()
+                .map(|job| {
+                    let store = Arc::clone(&self.store);
+                    let instance = instance.clone();
+                    let mut stopping = stopping.clone();
+
+                    tokio::spawn(async move {
+                        let mut delay = random_jitter(job.jitter);
+                        loop {
+                            tokio::select! {
+                                _ = tokio::time::sleep(delay) => {}
+                                _ = stopping.changed() => break,
+                            }
+                            // A run in progress is left to finish - shutdown only interrupts the wait
+                            run_once(store.as_ref(), &job, &instance).await;
+                            if *stopping.borrow() {
+                                break;
+                            }
+                            delay = job.next_delay();
+                        }
+                    })
+                })
+                .collect();
+
+            println!("Started {} background jobs as instance {}", jobs.len(), instance);
+
+            SchedulerHandle { jobs, shutdown, tasks }
+        }
+    }
+
+    pub struct SchedulerHandle {
+        jobs: Vec<(String, Duration)>,
+        shutdown: watch::Sender<bool>,
+        tasks: Vec<JoinHandle<()>>,
+    }
+
+    impl SchedulerHandle {
+        /* Names and intervals, for the admin endpoint */
+        pub fn jobs(&self) -> Vec<(String, Duration)> {
+            self.jobs.clone()
+        }
+
+        /* Stops scheduling new runs and waits (up to SHUTDOWN_GRACE) for running ones to finish */
+        pub async fn shutdown(self) {
+            let _ = self.shutdown.send(true);
+
+            let finished = futures::future::join_all(self.tasks);
+            if tokio::time::timeout(SHUTDOWN_GRACE, finished).await.is_err() {
+                println!("Background jobs still running after {:?}, stopping anyway", SHUTDOWN_GRACE);
+            }
+        }
+    }
+
+    pub async fn job_reports(
+        store: &dyn SessionStore,
+        jobs: &[(String, Duration)],
+    ) -> Result<Vec<JobReport>, SessionStoreError> {
+        let mut reports = vec![];
+        for (name, interval) in jobs {
+            reports.push(JobReport {
+                name: name.clone(),
+                interval_seconds: interval.as_secs(),
+                last_run: load_json(store, &format!("{}{}", STATUS_KEY_PREFIX, name)).await?,
+            });
+        }
+
+        Ok(reports)
+    }
+
+    /* The admin endpoint is off unless ADMIN_TOKEN is set - then it wants "Authorization: Bearer <token>" */
+    pub fn admin_authorized(admin_token: Option<&str>, authorization: Option<&str>) -> bool {
+        match (admin_token.map(str::trim), authorization) {
+            (Some(token), Some(header)) if !token.is_empty() => header
+                .strip_prefix("Bearer ")
+                .map(|given| bool::from(given.trim().as_bytes().ct_eq(token.as_bytes())))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /* Everything the server runs in the background */
+    pub fn server_jobs(store: Arc<dyn SessionStore>, tmdb: Arc<Tmdb>) -> Vec<Job> {
+        let availability_interval = availability::check_interval_from_env();
+
+        vec![
+            Job::new("availability", availability_interval, {
+                let store = Arc::clone(&store);
+                let tmdb = Arc::clone(&tmdb);
+                move || {
+                    let store = Arc::clone(&store);
+                    let tmdb = Arc::clone(&tmdb);
+                    async move {
+                        availability::check_watchlists(store.as_ref(), tmdb.as_ref())
+                            .await
+                            .map(|checked| format!("Checked {} watchlists", checked))
+                            .map_err(|err| err.to_string())
+                    }
+                }
+            })
+            .jitter(availability_interval / 10),
+            Job::new("warm_tmdb_cache", CACHE_WARM_INTERVAL, {
+                let store = Arc::clone(&store);
+                let tmdb = Arc::clone(&tmdb);
+                move || {
+                    let store = Arc::clone(&store);
+                    let tmdb = Arc::clone(&tmdb);
+                    async move { tmdb_cache::warm(store.as_ref(), tmdb.as_ref()).await }
+                }
+            })
+            .jitter(Duration::from_secs(60 * 10)),
+            Job::new("session_cleanup", SESSION_CLEANUP_INTERVAL, {
+                let store = Arc::clone(&store);
+                move || {
+                    let store = Arc::clone(&store);
+                    async move {
+                        store
+                            .purge_expired()
+                            .await
+                            .map(|purged| format!("Purged {} expired entries", purged))
+                            .map_err(|err| err.to_string())
+                    }
+                }
+            })
+            .jitter(Duration::from_secs(60 * 5)),
+        ]
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::session_store::MemorySessionStore;
+
+        fn counting_job(interval: Duration, result: Result<String, String>) -> Job {
+            Job::new("test_job", interval, move || {
+                let result = result.clone();
+                async move { result }
+            })
+        }
+
+        #[tokio::test]
+        async fn single_flight_per_interval() {
+            let store = MemorySessionStore::new();
+            let job = counting_job(Duration::from_secs(60), Ok(String::from("done")));
+
+            assert_eq!(
+                run_once(&store, &job, "instance-a").await,
+                Some(JobOutcome::Succeeded(String::from("done")))
+            );
+            // Another instance (or the same one, early) can't run it again this interval
+            assert_eq!(run_once(&store, &job, "instance-b").await, None);
+
+            let reports = job_reports(&store, &[(String::from("test_job"), job.interval)])
+                .await
+                .unwrap();
+            let last_run = reports[0].last_run.clone().unwrap();
+
+            assert_eq!(last_run.instance, "instance-a");
+            assert_eq!(last_run.runs, 1);
+        }
+
+        #[tokio::test]
+        async fn records_failures_and_timeouts() {
+            let store = MemorySessionStore::new();
+
+            let failing = counting_job(Duration::from_secs(60), Err(String::from("TMDB is down")));
+            assert_eq!(
+                run_once(&store, &failing, "instance-a").await,
+                Some(JobOutcome::Failed(String::from("TMDB is down")))
+            );
+
+            let slow = Job::new("slow_job", Duration::from_millis(10), || async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(String::new())
+            });
+            assert_eq!(run_once(&store, &slow, "instance-a").await, Some(JobOutcome::TimedOut));
+        }
+
+        #[test]
+        fn runs_end_before_their_lock() {
+            for interval in [
+                Duration::from_millis(10),
+                Duration::from_secs(1),
+                Duration::from_secs(90),
+                CACHE_WARM_INTERVAL,
+            ] {
+                assert!(run_timeout(interval) < lock_ttl(interval));
+            }
+        }
+
+        #[tokio::test]
+        async fn never_run_jobs_are_reported() {
+            let store = MemorySessionStore::new();
+
+            let reports = job_reports(&store, &[(String::from("idle"), Duration::from_secs(3600))])
+                .await
+                .unwrap();
+
+            assert_eq!(reports[0].interval_seconds, 3600);
+            assert_eq!(reports[0].last_run, None);
+        }
+
+        #[tokio::test]
+        async fn shutdown_stops_waiting_jobs() {
+            let store: Arc<dyn SessionStore> = Arc::new(MemorySessionStore::new());
+
+            let handle = Scheduler::new(store)
+                .job(counting_job(Duration::from_secs(3600), Ok(String::new())).jitter(Duration::from_secs(3600)))
+                .start();
+
+            tokio::time::timeout(Duration::from_secs(1), handle.shutdown())
+                .await
+                .expect("Scheduler should stop without waiting out the interval");
+        }
+
+        #[test]
+        fn admin_token() {
+            assert!(admin_authorized(Some("secret"), Some("Bearer secret")));
+            assert!(!admin_authorized(Some("secret"), Some("Bearer wrong")));
+            assert!(!admin_authorized(Some("secret"), Some("Bearer secret-and-more")));
+            assert!(!admin_authorized(Some("secret"), Some("secret")));
+            assert!(!admin_authorized(Some("secret"), None));
+            // Unset or blank means the endpoint is off
+            assert!(!admin_authorized(None, Some("Bearer ")));
+            assert!(!admin_authorized(Some(""), Some("Bearer ")));
+        }
+    }
+}
+}
@@ -13,7 +13,23 @@ use leptos_actix::ResponseOptions;
 use lazy_static::lazy_static;
 
 #[cfg(feature = "ssr")]
-use crate::{tmdb::Tmdb, *};
+use crate::{
+    accounts,
+    availability,
+    browse,
+    discover::{DiscoverQuery, Match},
+    history,
+    moods,
+    room_events::RoomEventBus,
+    rooms,
+    session_cookie::use_session_id,
+    surprise,
+    session_store::{update_criteria, use_session_store, SessionStore},
+    tmdb::Tmdb,
+    tmdb_cache,
+    watchlist,
+    *,
+};
 
 #[cfg(feature = "ssr")]
 lazy_static! {
@@ -36,25 +52,49 @@ pub async fn fetch_runtimes() -> Result<Vec<RuntimeInfo>, ServerFnError> {
 
 #[server(FetchDecades, "/api", "GetJson")]
 pub async fn fetch_decades() -> Result<Vec<Decade>, ServerFnError> {
-    let decades = vec![
-        Decade::Classic,
-        Decade::Fifties,
-        Decade::Sixties,
-        Decade::Seventies,
-        Decade::Eighties,
-        Decade::Nineties,
-        Decade::TwoThousands,
-        Decade::TwentyTens,
-        Decade::Recent,
-    ];
+    Ok(Decade::all())
+}
 
-    Ok(decades)
+#[server(FetchMoods, "/api", "GetJson")]
+pub async fn fetch_moods() -> Result<Vec<Mood>, ServerFnError> {
+    Ok(moods::mood_catalogue())
+}
+
+/* Shortcut through the wizard - the mood covers genres and runtime, the user still picks where and when */
+#[server(PostMood, "/api")]
+pub async fn post_mood(
+    mood_id: String,
+    providers: Vec<i32>,
+    decade: Decade,
+) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+
+    let mood = match moods::find_mood(&mood_id) {
+        Some(mood) => mood,
+        None => return Err(ServerFnError::new(format!("Unknown mood: {}", mood_id))),
+    };
+
+    update_criteria(&session_id, |criteria| {
+        moods::apply_mood(criteria, &mood);
+        criteria.watch_providers = Some(providers.clone());
+        criteria.decade = Some(decade.clone());
+    })
+    .await?;
+
+    update_session_user_profile(&session_id, |profile| {
+        profile.watch_providers = Some(providers.clone())
+    })
+    .await;
+
+    println!("Posted mood {} for {}", &mood_id, &session_id);
+
+    Ok(())
 }
 
 #[server(FetchWatchProviders, "/api", "GetJson")]
 pub async fn fetch_simple_watch_providers() -> Result<Vec<WatchProvider>, ServerFnError> {
     let tmdb: Arc<Tmdb> = Arc::clone(&TMDB);
-    let providers = tmdb.get_providers_list();
+    let store = use_session_store()?;
     let supported_providers = vec![
         "Netflix",
         "Hulu",
@@ -68,14 +108,13 @@ pub async fn fetch_simple_watch_providers() -> Result<Vec<WatchProvider>, Server
         "Paramount Plus",
     ];
     println!("Getting watch providers");
-    match providers.await {
+    match tmdb_cache::providers(store.as_ref(), tmdb.as_ref()).await {
         Err(err) => Err(ServerFnError::new(format!(
             "Error fetching watch providers: {}",
             err
         ))),
         Ok(providers) => {
             let mut provider_output: Vec<WatchProvider> = providers
-                .results
                 .into_iter()
                 .filter(|p| supported_providers.contains(&p.provider_name.as_str()))
                 .collect();
@@ -90,223 +129,758 @@ pub async fn fetch_simple_watch_providers() -> Result<Vec<WatchProvider>, Server
 }
 
 #[server(PostDecade, "/api")]
-pub async fn post_decades(session_id: String, decade: Decade) -> Result<(), ServerFnError> {
-    let id = session_id.clone();
+pub async fn post_decades(decade: Decade) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+
+    update_criteria(&session_id, |criteria| criteria.decade = Some(decade.clone())).await?;
+
+    println!("Posted decade for {}", &session_id);
+
+    Ok(())
+}
+
+#[server(PostProviders, "/api")]
+pub async fn post_providers(providers: Vec<i32>) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+
+    update_criteria(&session_id, |criteria| {
+        criteria.watch_providers = Some(providers.clone())
+    })
+    .await?;
+
+    update_session_user_profile(&session_id, |profile| {
+        profile.watch_providers = Some(providers.clone())
+    })
+    .await;
+
+    println!("Posted providers for {}", &session_id);
+
+    Ok(())
+}
+
+#[server(PostGenres, "/api")]
+pub async fn post_genres(genres: Vec<i32>) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+    println!("Posting genres");
 
-    match redis_helper::criteria_from_cache(&session_id).await {
+    update_criteria(&session_id, |criteria| criteria.genres = Some(genres.clone())).await?;
+
+    println!("Posted genres for {}", &session_id);
+
+    Ok(())
+}
+
+#[server(PostRuntime, "/api")]
+pub async fn post_runtime(runtime: Runtime) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+    println!("Received a runtime: {:#?}", runtime);
+
+    update_criteria(&session_id, |criteria| criteria.runtime = Some(runtime.clone())).await?;
+
+    update_session_user_profile(&session_id, |profile| profile.runtime = Some(runtime.clone())).await;
+
+    Ok(())
+}
+
+/* Movies, series or both - episode length and season count only matter once series are in */
+#[server(PostMedia, "/api")]
+pub async fn post_media(
+    media: MediaChoice,
+    episode_length: Option<EpisodeLength>,
+    seasons: Option<SeasonCount>,
+) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+
+    update_criteria(&session_id, |criteria| {
+        criteria.media = Some(media.clone());
+        criteria.episode_length = episode_length.clone();
+        criteria.seasons = seasons.clone();
+    })
+    .await?;
+
+    println!("Posted {:?} for {}", media, &session_id);
+
+    Ok(())
+}
+
+#[server(FetchLanguages, "/api", "GetJson")]
+pub async fn fetch_languages() -> Result<Vec<Language>, ServerFnError> {
+    let tmdb = Arc::clone(&TMDB);
+
+    match tmdb.get_languages().await {
+        Ok(mut languages) => {
+            languages.sort_by(|a, b| a.english_name.cmp(&b.english_name));
+            Ok(languages)
+        }
         Err(err) => Err(ServerFnError::new(format!(
-            "Error reading criteria from cache: {}",
+            "Error fetching languages: {}",
             err
         ))),
-        Ok(mut criteria) => {
-            criteria.decade = Some(decade);
-
-            match redis_helper::criteria_to_cache(&session_id, criteria).await {
-                Ok(redis_response) => {
-                    let response = format!("Posted decade for {}", id);
+    }
+}
 
-                    println!("{}", &response);
+/* Ratings for one country, lowest first, for picking a maximum */
+#[server(FetchCertifications, "/api", "GetJson")]
+pub async fn fetch_certifications(country: String) -> Result<Vec<Certification>, ServerFnError> {
+    let tmdb = Arc::clone(&TMDB);
 
-                    Ok(())
-                }
-                Err(err) => Err(ServerFnError::new(format!(
-                    "Error writing decades to cache: {}",
-                    err
-                ))),
-            }
+    match tmdb.get_certifications().await {
+        Ok(mut response) => {
+            let mut certifications = response.certifications.remove(&country).unwrap_or_default();
+            certifications.sort_by_key(|certification| certification.order);
+            Ok(certifications)
         }
+        Err(err) => Err(ServerFnError::new(format!(
+            "Error fetching certifications: {}",
+            err
+        ))),
     }
 }
 
-#[server(PostProviders, "/api")]
-pub async fn post_providers(session_id: String, providers: Vec<i32>) -> Result<(), ServerFnError> {
-    let id = session_id.clone();
+/* Family mode applies the limit to surprises and hosted rooms as well, and leaves series out */
+#[server(PostCertification, "/api")]
+pub async fn post_certification(
+    certification: Option<CertificationLimit>,
+    family_mode: bool,
+) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+
+    update_criteria(&session_id, |criteria| {
+        criteria.certification = certification.clone();
+        criteria.family_mode = family_mode;
+    })
+    .await?;
+
+    println!(
+        "Posted certification {:?} (family mode {}) for {}",
+        certification, family_mode, &session_id
+    );
+
+    Ok(())
+}
 
-    match redis_helper::criteria_from_cache(&session_id).await {
+#[server(SearchPeople, "/api", "GetJson")]
+pub async fn search_people(query: String) -> Result<Vec<Person>, ServerFnError> {
+    let tmdb = Arc::clone(&TMDB);
+
+    match tmdb.search_people(query.trim()).await {
+        Ok(response) => Ok(response.results),
         Err(err) => Err(ServerFnError::new(format!(
-            "Error reading criteria from cache: {}",
+            "Error searching people: {}",
             err
         ))),
-        Ok(mut criteria) => {
-            criteria.watch_providers = Some(providers);
+    }
+}
 
-            match redis_helper::criteria_to_cache(&session_id, criteria).await {
-                Ok(redis_response) => {
-                    let response = format!("Posted providers for {}", id);
+#[server(SearchCompanies, "/api", "GetJson")]
+pub async fn search_companies(query: String) -> Result<Vec<Company>, ServerFnError> {
+    let tmdb = Arc::clone(&TMDB);
 
-                    println!("{}", &response);
+    match tmdb.search_companies(query.trim()).await {
+        Ok(response) => Ok(response.results),
+        Err(err) => Err(ServerFnError::new(format!(
+            "Error searching companies: {}",
+            err
+        ))),
+    }
+}
 
-                    Ok(())
-                }
-                Err(err) => Err(ServerFnError::new(format!(
-                    "Error writing providers to cache: {}",
-                    err
-                ))),
+#[server(PostCredits, "/api")]
+pub async fn post_credits(credits: CreditFilter) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+
+    update_criteria(&session_id, |criteria| {
+        criteria.credits = credits.clone();
+    })
+    .await?;
+
+    println!("Posted {:?} for {}", credits, &session_id);
+
+    Ok(())
+}
+
+#[server(PostLanguages, "/api")]
+pub async fn post_languages(languages: LanguageFilter) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+
+    // The codes end up in TMDB query strings
+    languages.validate().map_err(ServerFnError::new)?;
+
+    update_criteria(&session_id, |criteria| {
+        criteria.languages = languages.clone();
+    })
+    .await?;
+
+    println!("Posted {:?} for {}", languages, &session_id);
+
+    Ok(())
+}
+
+/* Looks up the keywords behind some likes and dislikes, and records them on the signed in user's profile */
+#[cfg(feature = "ssr")]
+async fn rate_feedback(
+    session_id: &str,
+    feedback: Feedback,
+    show_feedback: Feedback,
+) -> Vec<keyword_model::RatedKeywords> {
+    let tmdb = Arc::clone(&TMDB);
+
+    println!("{:#?}", feedback);
+
+    let liked_movies = feedback.like.unwrap_or_default();
+    let disliked_movies = feedback.dislike.unwrap_or_default();
+
+    // Keywords are looked up before touching the session so the update itself stays short
+    let mut rated_keywords = tmdb_helper::process_feedback(
+        Arc::clone(&tmdb),
+        MediaType::Movie,
+        liked_movies.clone(),
+        disliked_movies.clone(),
+    )
+    .await;
+    rated_keywords.extend(
+        tmdb_helper::process_feedback(
+            tmdb,
+            MediaType::Tv,
+            show_feedback.like.unwrap_or_default(),
+            show_feedback.dislike.unwrap_or_default(),
+        )
+        .await,
+    );
+
+    update_session_user_profile(session_id, |profile| {
+        accounts::record_feedback(profile, &liked_movies, &disliked_movies, &rated_keywords)
+    })
+    .await;
+
+    rated_keywords
+}
+
+/* show_feedback carries likes and dislikes for series - their IDs only mean something to TMDB's tv endpoints */
+#[server(PostFeedback, "/api")]
+pub async fn post_feedback(feedback: Feedback, show_feedback: Feedback) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+
+    let rated_keywords = rate_feedback(&session_id, feedback, show_feedback).await;
+
+    println!("Posting feedback");
+
+    update_criteria(&session_id, |criteria| {
+        criteria.keyword_profile.apply_round(&rated_keywords)
+    })
+    .await?;
+
+    Ok(())
+}
+
+/* One swipe - counts straight away, but only a full feedback round decays what came before */
+#[server(PostSwipe, "/api")]
+pub async fn post_swipe(movie_id: i64, media_type: MediaType, liked: bool) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+
+    let (feedback, show_feedback) = Feedback::split([(movie_id, media_type, liked)]);
+    let rated_keywords = rate_feedback(&session_id, feedback, show_feedback).await;
+
+    update_criteria(&session_id, |criteria| {
+        criteria.keyword_profile.apply_votes(&rated_keywords)
+    })
+    .await?;
+
+    Ok(())
+}
+
+/* Marks a movie as seen - it drops out of future recommendations and its rating steers the session's keywords */
+#[server(MarkWatched, "/api")]
+pub async fn mark_watched(
+    movie_id: i64,
+    media_type: MediaType,
+    rating: u8,
+) -> Result<(), ServerFnError> {
+    // A bad rating is the caller's mistake, not a storage failure
+    if !history::valid_rating(rating) {
+        return Err(ServerFnError::Args(format!(
+            "Rating must be between {} and {}, got {}",
+            history::MIN_RATING,
+            history::MAX_RATING,
+            rating
+        )));
+    }
+
+    let session_id = use_session_id()?;
+    let owner = use_data_owner().await?;
+    let store = use_session_store()?;
+    let tmdb = Arc::clone(&TMDB);
+
+    if let Err(err) = history::record_watched(store.as_ref(), &owner, movie_id, media_type, rating).await {
+        return Err(ServerFnError::new(format!(
+            "Error recording watched movie: {}",
+            err
+        )));
+    }
+
+    let rated_keywords =
+        tmdb_helper::process_rated_feedback(tmdb, media_type, vec![(movie_id, rating)]).await;
+
+    // The profile's liked and disliked lists are movie IDs, so a rated series only counts through its keywords
+    let (liked_movies, disliked_movies) = match (media_type, history::rating_weight(rating)) {
+        (MediaType::Movie, weight) if weight > 0 => (vec![movie_id], vec![]),
+        (MediaType::Movie, weight) if weight < 0 => (vec![], vec![movie_id]),
+        _ => (vec![], vec![]),
+    };
+
+    update_session_user_profile(&session_id, |profile| {
+        accounts::record_feedback(profile, &liked_movies, &disliked_movies, &rated_keywords)
+    })
+    .await;
+
+    // A single rating is a vote like a swipe - it shouldn't age everything rated before it
+    update_criteria(&session_id, |criteria| {
+        criteria.keyword_profile.apply_votes(&rated_keywords)
+    })
+    .await?;
+
+    Ok(())
+}
+
+/* Profile updates are best effort - a signed out session, or a store hiccup, shouldn't fail the wizard step */
+#[cfg(feature = "ssr")]
+async fn update_session_user_profile<F>(session_id: &str, update: F)
+where
+    F: Fn(&mut TasteProfile) + Send + Sync,
+{
+    let store = match use_session_store() {
+        Ok(store) => store,
+        Err(_) => return,
+    };
+
+    match accounts::user_for_session(store.as_ref(), session_id).await {
+        Ok(Some(username)) => {
+            if let Err(err) = accounts::update_profile(store.as_ref(), &username, update).await {
+                println!("Error updating taste profile for {}: {}", username, err);
             }
         }
+        Ok(None) => {}
+        Err(err) => println!("Error looking up user for {}: {}", session_id, err),
     }
 }
 
-#[server(PostGenres, "/api")]
-pub async fn post_genres(session_id: String, genres: Vec<i32>) -> Result<(), ServerFnError> {
-    let id = session_id.clone();
+/* Taste profile of the user signed in to this session, if any */
+#[cfg(feature = "ssr")]
+async fn session_user_profile(session_id: &str) -> Option<TasteProfile> {
+    let store = use_session_store().ok()?;
 
-    println!("Posting genres");
+    let username = accounts::user_for_session(store.as_ref(), session_id)
+        .await
+        .ok()??;
 
-    match redis_helper::criteria_from_cache(&session_id).await {
-        Err(err) => {
-            println!("Error reading from cache");
-            Err(ServerFnError::new(format!(
-                "Error reading criteria from cache: {}",
-                err
-            )))
+    accounts::load_account(store.as_ref(), &username)
+        .await
+        .ok()
+        .map(|account| account.profile)
+}
+
+/* Moves the user onto a new session and cookie - the old session ID is dropped */
+#[cfg(feature = "ssr")]
+async fn sign_in(
+    store: &dyn SessionStore,
+    old_session_id: &str,
+    username: &str,
+) -> Result<String, ServerFnError> {
+    let response = expect_context::<ResponseOptions>();
+
+    match accounts::sign_in_new_session(store, old_session_id, username).await {
+        Err(err) => Err(ServerFnError::new(format!("Error signing in: {}", err))),
+        Ok(session_id) => {
+            set_session_cookie(&response, &session_id);
+            Ok(session_id)
         }
-        Ok(mut criteria) => {
-            criteria.genres = Some(genres);
+    }
+}
 
-            match redis_helper::criteria_to_cache(&session_id, criteria).await {
-                Ok(redis_response) => {
-                    let response = format!("Posted genres for{}", id);
+/* Creates an account and signs in on a fresh session - choices already made in the session seed the profile */
+#[server(Register, "/api")]
+pub async fn register(username: String, password: String) -> Result<String, ServerFnError> {
+    let old_session_id = use_session_id()?;
+    let store = use_session_store()?;
 
-                    println!("{}", &response);
+    let account = match accounts::register(store.as_ref(), &username, &password).await {
+        Err(err) => return Err(ServerFnError::new(format!("Error registering: {}", err))),
+        Ok(account) => account,
+    };
 
-                    Ok(())
-                }
-                Err(err) => {
-                    println!("Error writing genres");
-                    Err(ServerFnError::new(format!(
-                        "Error writing genres to cache: {}",
-                        err
-                    )))
-                }
-            }
-        }
+    let session_id = sign_in(store.as_ref(), &old_session_id, &account.username).await?;
+
+    if let Ok(criteria) = store.load(&session_id).await {
+        update_session_user_profile(&session_id, |profile| {
+            profile.watch_providers = criteria.watch_providers.clone();
+            profile.runtime = criteria.runtime.clone();
+        })
+        .await;
     }
+
+    println!("Registered {}", &account.username);
+
+    Ok(account.username)
 }
 
-#[server(PostRuntime, "/api")]
-pub async fn post_runtime(session_id: String, runtime: Runtime) -> Result<(), ServerFnError> {
-    let id = session_id.clone();
-    println!("Received a runtime: {:#?}", runtime);
+/* Signs in on a fresh session and pre-fills it from the user's taste profile */
+#[server(Login, "/api")]
+pub async fn login(username: String, password: String) -> Result<String, ServerFnError> {
+    let old_session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    let account = match accounts::authenticate(store.as_ref(), &username, &password).await {
+        Err(err) => return Err(ServerFnError::new(format!("Error signing in: {}", err))),
+        Ok(account) => account,
+    };
+
+    let session_id = sign_in(store.as_ref(), &old_session_id, &account.username).await?;
+
+    update_criteria(&session_id, |criteria| {
+        accounts::apply_profile(criteria, &account.profile)
+    })
+    .await?;
 
-    match redis_helper::criteria_from_cache(&session_id).await {
+    println!("Signed in {}", &account.username);
+
+    Ok(account.username)
+}
+
+#[server(Logout, "/api")]
+pub async fn logout() -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    match accounts::unlink_session(store.as_ref(), &session_id).await {
+        Err(err) => Err(ServerFnError::new(format!("Error signing out: {}", err))),
+        Ok(_) => Ok(()),
+    }
+}
+
+#[server(FetchCurrentUser, "/api", "GetJson")]
+pub async fn fetch_current_user() -> Result<Option<String>, ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    match accounts::user_for_session(store.as_ref(), &session_id).await {
         Err(err) => Err(ServerFnError::new(format!(
-            "Error reading criteria from cache: {}",
+            "Error fetching current user: {}",
             err
         ))),
-        Ok(mut criteria) => {
-            criteria.runtime = Some(runtime);
+        Ok(username) => Ok(username),
+    }
+}
 
-            match redis_helper::criteria_to_cache(&session_id, criteria).await {
-                Ok(redis_response) => {
-                    let response = format!("Posted runtime for {}", &id);
+#[server(FetchTasteProfile, "/api", "GetJson")]
+pub async fn fetch_taste_profile() -> Result<Option<TasteProfile>, ServerFnError> {
+    let session_id = use_session_id()?;
 
-                    Ok(())
-                }
-                Err(err) => Err(ServerFnError::new(format!(
-                    "Error writing runtime to cache: {}",
-                    err
-                ))),
-            }
-        }
-    }
+    Ok(session_user_profile(&session_id).await)
 }
 
-#[cfg(feature = "ssr")]
-fn update_feedback(
-    mut criteria: RecommendationCriteria,
-    mut feedback: Feedback,
-) -> RecommendationCriteria {
-    if let Some(mut criteria_feedback) = criteria.feedback.take() {
-        if let Some(mut likes) = criteria_feedback.like.take() {
-            if let Some(feedback_likes) = feedback.like.take() {
-                likes.extend(feedback_likes);
-                criteria_feedback.like = Some(likes);
-            } else {
-                criteria_feedback.like = Some(likes);
-            }
-        } else {
-            criteria_feedback.like = feedback.like;
+#[server(FetchSessionCriteria, "/api", "GetJson")]
+pub async fn fetch_session_criteria() -> Result<RecommendationCriteria, ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    let criteria = store
+        .load(&session_id)
+        .await
+        .map_err(|err| ServerFnError::new(format!("Error fetching session criteria: {}", err)))?;
+
+    Ok(criteria)
+}
+
+#[server(FetchRecommendations, "/api", "GetJson")]
+pub async fn fetch_recommendations() -> Result<Vec<MovieRecommendation>, ServerFnError> {
+    let session_id = use_session_id()?;
+
+    recommendations_for_session(session_id, &[], 1).await
+}
+
+/* Next batch for the swipe view - skips anything the client already has queued or has decided on */
+/* page is the discover page the client has got up to, so it isn't stuck with the first page's results */
+#[server(FetchNextRecommendations, "/api")]
+pub async fn fetch_next_recommendations(
+    seen: Vec<(MediaType, i64)>,
+    page: u32,
+) -> Result<Vec<MovieRecommendation>, ServerFnError> {
+    let session_id = use_session_id()?;
+
+    recommendations_for_session(session_id, &seen, page.max(1)).await
+}
+
+/* One well-rated pick with no wizard - seen holds earlier surprises so "try another" doesn't repeat itself */
+#[server(SurpriseMe, "/api")]
+pub async fn surprise_me(seen: Vec<(MediaType, i64)>) -> Result<MovieRecommendation, ServerFnError> {
+    let session_id = use_session_id()?;
+    let owner = use_data_owner().await?;
+    let store = use_session_store()?;
+    let tmdb = Arc::clone(&TMDB);
+
+    let criteria = store.load(&session_id).await.unwrap_or_default();
+    let profile = session_user_profile(&session_id).await;
+
+    // Saved services first, then whatever this session picked, then every supported service
+    let providers = match profile
+        .as_ref()
+        .and_then(|profile| profile.watch_providers.clone())
+        .or_else(|| criteria.watch_providers.clone())
+    {
+        Some(providers) if !providers.is_empty() => providers,
+        _ => fetch_simple_watch_providers()
+            .await?
+            .into_iter()
+            .map(|provider| provider.provider_id)
+            .collect(),
+    };
+
+    let mut exclude = seen;
+    if let Ok(watch_history) = history::load_history(store.as_ref(), &owner).await {
+        exclude.extend(
+            watch_history
+                .watched
+                .iter()
+                .map(|watched| (watched.media_type, watched.movie_id)),
+        );
+    }
+
+    let liked_ids = surprise::liked_movie_ids(profile.as_ref(), &criteria);
+
+    let surprise = match surprise::find_surprise(
+        Arc::clone(&tmdb),
+        providers,
+        liked_ids,
+        &criteria.languages,
+        criteria.family_limit().as_ref(),
+        &exclude,
+    )
+    .await {
+        Err(err) => {
+            return Err(ServerFnError::new(format!(
+                "Error finding a surprise: {}",
+                err
+            )))
         }
-        if let Some(mut dislikes) = criteria_feedback.dislike.take() {
-            if let Some(feedback_dislikes) = feedback.dislike.take() {
-                dislikes.extend(feedback_dislikes);
-                criteria_feedback.dislike = Some(dislikes);
-            } else {
-                criteria_feedback.dislike = Some(dislikes);
+        Ok(None) => return Err(ServerFnError::new("Nothing turned up this time - try again")),
+        Ok(Some(surprise)) => surprise,
+    };
+
+    let providers = match tmdb
+        .get_watch_providers_by_id(&surprise.movie.id.to_string())
+        .await
+    {
+        Ok(providers) => supported_flatrate_providers(providers),
+        Err(_) => vec![],
+    };
+
+    let reasons = RecommendationReasons {
+        genres: surprise.genre_name.into_iter().collect(),
+        decade: Some(surprise.decade.info().name),
+        ..Default::default()
+    };
+
+    Ok(MovieRecommendation::new(surprise.movie, providers).with_reasons(reasons))
+}
+
+/* Trending, in cinemas or coming soon - trending is narrowed to the session's streaming services, if it has any */
+#[server(FetchBrowseList, "/api")]
+pub async fn fetch_browse_list(list: BrowseList) -> Result<Vec<MovieRecommendation>, ServerFnError> {
+    let tmdb = Arc::clone(&TMDB);
+
+    // Browsing works before the wizard, or without a session at all
+    let criteria = match (use_session_id(), use_session_store()) {
+        (Ok(session_id), Ok(store)) => {
+            let mut criteria = store.load(&session_id).await.unwrap_or_default();
+            if let Some(providers) = session_user_profile(&session_id)
+                .await
+                .and_then(|profile| profile.watch_providers)
+            {
+                criteria.watch_providers = Some(providers);
             }
-        } else {
-            criteria_feedback.dislike = feedback.dislike;
+            criteria
         }
-        criteria.feedback = Some(criteria_feedback);
-    } else {
-        criteria.feedback = Some(feedback);
+        _ => RecommendationCriteria::default(),
+    };
+
+    match browse::browse(tmdb, &list, &criteria).await {
+        Err(err) => Err(ServerFnError::new(format!(
+            "Error fetching {}: {}",
+            list.title(),
+            err
+        ))),
+        Ok(movies) => Ok(movies
+            .into_iter()
+            .map(|(movie, providers)| {
+                let providers = providers.map(supported_flatrate_providers).unwrap_or_default();
+                MovieRecommendation::new(movie, providers)
+            })
+            .collect()),
     }
+}
+
+/* A movie and, if it's part of a franchise, every part of it - watched lists which parts are already seen */
+#[server(FetchMovieDetail, "/api", "GetJson")]
+pub async fn fetch_movie_detail(movie_id: i64) -> Result<MovieDetailView, ServerFnError> {
+    let session_id = use_session_id()?;
+    let owner = use_data_owner().await?;
+    let store = use_session_store()?;
+    let tmdb = Arc::clone(&TMDB);
+
+    let language = store
+        .load(&session_id)
+        .await
+        .map(|criteria| criteria.languages.display_language())
+        .unwrap_or(String::from(DEFAULT_LANGUAGE));
 
-    criteria
+    let details = match tmdb.get_movie_details(&movie_id).await {
+        Err(err) => {
+            return Err(ServerFnError::new(format!(
+                "Error fetching movie details: {}",
+                err
+            )))
+        }
+        Ok(details) => details,
+    };
+
+    // A franchise that won't load still leaves the movie worth showing
+    let collection = match details.belongs_to_collection {
+        Some(summary) => tmdb.get_collection(&summary.id, &language).await.ok(),
+        None => None,
+    };
+
+    let watch_history = history::load_history(store.as_ref(), &owner)
+        .await
+        .unwrap_or_default();
+    let watched = collection
+        .iter()
+        .flat_map(|collection| collection.parts.iter())
+        .map(|part| part.id)
+        .filter(|id| watch_history.contains(*id, MediaType::Movie))
+        .collect();
+
+    Ok(MovieDetailView {
+        movie: details.movie,
+        collection,
+        watched,
+    })
 }
 
-#[server(PostFeedback, "/api")]
-pub async fn post_feedback(session_id: String, feedback: Feedback) -> Result<(), ServerFnError> {
+/* Saves every released part that hasn't been watched yet, in release order */
+#[server(SaveCollection, "/api")]
+pub async fn save_collection(collection_id: i64) -> Result<(), ServerFnError> {
+    let owner = use_data_owner().await?;
+    let store = use_session_store()?;
     let tmdb = Arc::clone(&TMDB);
 
-    println!("{:#?}", feedback);
+    let collection = match tmdb.get_collection(&collection_id, DEFAULT_LANGUAGE).await {
+        Err(err) => {
+            return Err(ServerFnError::new(format!(
+                "Error fetching collection: {}",
+                err
+            )))
+        }
+        Ok(collection) => collection,
+    };
 
-    match redis_helper::criteria_from_cache(&session_id).await {
+    let watch_history = history::load_history(store.as_ref(), &owner)
+        .await
+        .unwrap_or_default();
+    let parts: Vec<Movie> = collection
+        .in_release_order()
+        .into_iter()
+        .filter(|part| !part.release_date.is_empty() && !watch_history.contains(part.id, MediaType::Movie))
+        .collect();
+
+    match watchlist::add_all_to_watchlist(store.as_ref(), &owner, parts).await {
         Err(err) => Err(ServerFnError::new(format!(
-            "Error reading criteria from cache: {}",
+            "Error saving collection to watchlist: {}",
             err
         ))),
-        Ok(criteria) => {
-            let (upvotes, downvotes) = tmdb_helper::process_feedback(
-                tmdb,
-                feedback.like.unwrap(),
-                feedback.dislike.unwrap(),
-            )
-            .await;
-
-            let feedback = Feedback {
-                like: match upvotes.is_empty() {
-                    true => None,
-                    false => Some(upvotes),
-                },
-                dislike: match downvotes.is_empty() {
-                    true => None,
-                    false => Some(downvotes),
-                },
-            };
-
-            let criteria = update_feedback(criteria, feedback);
-
-            println!("Posting feedback");
-
-            match redis_helper::criteria_to_cache(&session_id, criteria).await {
-                Err(err) => Err(ServerFnError::new(format!(
-                    "Error writing feedback to cache: {}",
-                    err
-                ))),
-                Ok(redis_response) => Ok(()),
-            }
-        }
+        Ok(_) => Ok(()),
     }
 }
 
-#[server(FetchSessionCriteria, "/api", "GetJson")]
-pub async fn fetch_session_criteria(
-    session_id: String,
-) -> Result<RecommendationCriteria, ServerFnError> {
-    let criteria = redis_helper::criteria_from_cache(&session_id)
-        .await
-        .expect("Uh oh");
+#[server(PostSkipUnseenSequels, "/api")]
+pub async fn post_skip_unseen_sequels(skip: bool) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
 
-    Ok(criteria)
+    update_criteria(&session_id, |criteria| {
+        criteria.skip_unseen_sequels = skip;
+    })
+    .await?;
+
+    Ok(())
 }
 
-#[server(FetchRecommendations, "/api", "GetJson")]
-pub async fn fetch_recommendations(
+/* Lets anyone holding a share link see the recommendations for a session without being able to change it */
+#[server(FetchSharedRecommendations, "/api", "GetJson")]
+pub async fn fetch_shared_recommendations(
+    share_token: String,
+) -> Result<Vec<MovieRecommendation>, ServerFnError> {
+    let store = use_session_store()?;
+
+    match session_store::resolve_share_token(store.as_ref(), &share_token).await {
+        Err(err) => Err(ServerFnError::new(format!(
+            "Error resolving share link: {}",
+            err
+        ))),
+        Ok(session_id) => recommendations_for_session(session_id, &[], 1).await,
+    }
+}
+
+#[server(CreateShareLink, "/api")]
+pub async fn create_share_link() -> Result<String, ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    match session_store::create_share_token(store.as_ref(), &session_id).await {
+        Err(err) => Err(ServerFnError::new(format!(
+            "Error creating share link: {}",
+            err
+        ))),
+        Ok(share_token) => Ok(format!("/shared/{}", share_token)),
+    }
+}
+
+#[cfg(feature = "ssr")]
+async fn recommendations_for_session(
     session_id: String,
+    exclude: &[(MediaType, i64)],
+    page: u32,
 ) -> Result<Vec<MovieRecommendation>, ServerFnError> {
     let tmdb = Arc::clone(&TMDB);
+
+    let store = use_session_store()?;
+
+    match tmdb_helper::get_recommendations_for_session(tmdb, store.as_ref(), session_id, exclude, page).await {
+        Err(err) => Err(ServerFnError::new(format!(
+            "Error fetching recommendations: {}",
+            err
+        ))),
+        Ok(recs) => {
+            let mut movie_recommendations: Vec<MovieRecommendation> = vec![];
+
+            for rec in recs {
+                let providers = match rec.async_providers.await {
+                    Ok(Some(providers)) => supported_flatrate_providers(providers),
+                    _ => vec![],
+                };
+                movie_recommendations.push(
+                    MovieRecommendation::new(rec.movie, providers)
+                        .with_reasons(rec.reasons)
+                        .with_collection(rec.collection),
+                )
+            }
+
+            Ok(movie_recommendations)
+        }
+    }
+}
+
+/* Filter out unsupported providers (Things like Amazon Prime or Apple TV channels - these can introduce a lot of junk) */
+#[cfg(feature = "ssr")]
+pub(crate) fn supported_flatrate_providers(providers: GetWatchProvidersResponse) -> Vec<WatchProvider> {
     let supported_providers = vec![
         "Netflix",
         "Hulu",
@@ -320,42 +894,352 @@ pub async fn fetch_recommendations(
         "Paramount Plus",
     ];
 
-    match tmdb_helper::get_recommendations_for_session(tmdb, session_id).await {
+    providers
+        .results
+        .us
+        .flatrate
+        .into_iter()
+        .filter(|p| supported_providers.contains(&p.provider_name.as_str()))
+        .collect()
+}
+
+#[cfg(feature = "ssr")]
+async fn use_data_owner() -> Result<accounts::DataOwner, ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    accounts::DataOwner::for_session(store.as_ref(), &session_id)
+        .await
+        .map_err(|err| ServerFnError::new(format!("Error looking up session user: {}", err)))
+}
+
+#[server(AddToWatchlist, "/api")]
+pub async fn add_to_watchlist(movie: Movie) -> Result<(), ServerFnError> {
+    let owner = use_data_owner().await?;
+    let store = use_session_store()?;
+
+    match watchlist::add_to_watchlist(store.as_ref(), &owner, movie).await {
         Err(err) => Err(ServerFnError::new(format!(
-            "Error fetching recommendations: {}",
+            "Error saving to watchlist: {}",
             err
         ))),
-        Ok(recs) => {
-            let mut movie_recommendations: Vec<MovieRecommendation> = vec![];
+        Ok(_) => Ok(()),
+    }
+}
 
-            for rec in recs {
-                let providers: Vec<WatchProvider> = rec
-                    .async_providers
+#[server(RemoveFromWatchlist, "/api")]
+pub async fn remove_from_watchlist(movie_id: i64, media_type: MediaType) -> Result<(), ServerFnError> {
+    let owner = use_data_owner().await?;
+    let store = use_session_store()?;
+
+    match watchlist::remove_from_watchlist(store.as_ref(), &owner, movie_id, media_type).await {
+        Err(err) => Err(ServerFnError::new(format!(
+            "Error removing from watchlist: {}",
+            err
+        ))),
+        Ok(_) => Ok(()),
+    }
+}
+
+/* Saved movies along with where they can be streamed right now */
+#[server(FetchWatchlist, "/api", "GetJson")]
+pub async fn fetch_watchlist() -> Result<Vec<MovieRecommendation>, ServerFnError> {
+    let tmdb = Arc::clone(&TMDB);
+    let owner = use_data_owner().await?;
+    let store = use_session_store()?;
+
+    let saved = match watchlist::load_watchlist(store.as_ref(), &owner).await {
+        Err(err) => {
+            return Err(ServerFnError::new(format!(
+                "Error fetching watchlist: {}",
+                err
+            )))
+        }
+        Ok(saved) => saved,
+    };
+
+    let handles: Vec<_> = saved
+        .movies
+        .into_iter()
+        .map(|movie| {
+            let temp_tmdb = Arc::clone(&tmdb);
+            let media_type = movie.media_type;
+            let movie_id = movie.id.to_string();
+            let handle = tokio::spawn(async move {
+                temp_tmdb
+                    .get_watch_providers_for(&media_type, &movie_id)
                     .await
-                    .expect(format!("Error fetching watch providers for {}", rec.movie.id).as_str())
-                    .results
-                    .us
-                    .flatrate
-                    // Filter out unsupported providers (Things like Amazon Prime or Apple TV channels - these can introduce a lot of junk)
-                    .into_iter()
-                    .filter(|p| supported_providers.contains(&p.provider_name.as_str()))
-                    .collect();
-                movie_recommendations.push(MovieRecommendation::new(rec.movie, providers))
+                    .map_err(|err| err.to_string())
+            });
+            (movie, handle)
+        })
+        .collect();
+
+    let mut movie_recommendations: Vec<MovieRecommendation> = vec![];
+
+    for (movie, handle) in handles {
+        // A movie that has left every service still belongs on the watchlist
+        let providers = match handle.await {
+            Ok(Ok(providers)) => supported_flatrate_providers(providers),
+            _ => vec![],
+        };
+        movie_recommendations.push(MovieRecommendation::saved(movie, providers));
+    }
+
+    Ok(movie_recommendations)
+}
+
+/* Watchlisted titles that have turned up on a new service since they were saved, newest first */
+#[server(FetchAvailabilityAlerts, "/api", "GetJson")]
+pub async fn fetch_availability_alerts() -> Result<Vec<AvailabilityAlert>, ServerFnError> {
+    let owner = use_data_owner().await?;
+    let store = use_session_store()?;
+
+    match availability::load_availability(store.as_ref(), &owner).await {
+        Err(err) => Err(ServerFnError::new(format!(
+            "Error fetching availability alerts: {}",
+            err
+        ))),
+        Ok(history) => {
+            let mut alerts = history.alerts;
+            alerts.sort_by_key(|alert| std::cmp::Reverse(alert.detected_at));
+            Ok(alerts)
+        }
+    }
+}
+
+#[server(DismissAvailabilityAlerts, "/api")]
+pub async fn dismiss_availability_alerts() -> Result<(), ServerFnError> {
+    let owner = use_data_owner().await?;
+    let store = use_session_store()?;
+
+    match availability::dismiss_alerts(store.as_ref(), &owner).await {
+        Err(err) => Err(ServerFnError::new(format!(
+            "Error dismissing availability alerts: {}",
+            err
+        ))),
+        Ok(_) => Ok(()),
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn room_error(action: &str, err: rooms::RoomError) -> ServerFnError {
+    ServerFnError::new(format!("Error {}: {}", action, err))
+}
+
+/* Best effort - the change is already saved, so a missed event only means members see it on their next refresh */
+#[cfg(feature = "ssr")]
+fn publish_room_event(code: &str, event: RoomEvent) {
+    let bus = match use_context::<Arc<RoomEventBus>>() {
+        Some(bus) => bus,
+        None => {
+            println!("No room event bus in context, skipping event for room {}", code);
+            return;
+        }
+    };
+
+    if let Err(err) = bus.publish(code, event) {
+        println!("Error publishing event for room {}: {}", code, err);
+    }
+}
+
+/* The host's own wizard answers for runtime and decade carry over to the room */
+#[server(CreateRoom, "/api")]
+pub async fn create_room(name: String) -> Result<String, ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    let criteria = store.load(&session_id).await.unwrap_or_default();
+
+    match rooms::create_room(
+        store.as_ref(),
+        &session_id,
+        &name,
+        criteria.runtime.unwrap_or(Runtime::Average),
+        criteria.decade.unwrap_or(Decade::Recent),
+    )
+    .await
+    {
+        Err(err) => Err(room_error("creating room", err)),
+        Ok(room) => {
+            println!("Created room {} for {}", &room.code, &session_id);
+            Ok(room.code)
+        }
+    }
+}
+
+#[server(JoinRoom, "/api")]
+pub async fn join_room(code: String, name: String) -> Result<String, ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    match rooms::join_room(store.as_ref(), &code, &session_id, &name).await {
+        Err(err) => Err(room_error("joining room", err)),
+        Ok(room) => {
+            if let Some(member) = room.member(&session_id) {
+                publish_room_event(
+                    &room.code,
+                    RoomEvent::MemberJoined {
+                        name: member.name.clone(),
+                    },
+                );
             }
+            Ok(room.code)
+        }
+    }
+}
 
-            Ok(movie_recommendations)
+#[server(FetchRoom, "/api", "GetJson")]
+pub async fn fetch_room(code: String) -> Result<RoomView, ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    match rooms::load_room(store.as_ref(), &code).await {
+        Err(err) => Err(room_error("fetching room", err)),
+        Ok(room) if room.member(&session_id).is_none() => {
+            Err(room_error("fetching room", rooms::RoomError::NotMember))
+        }
+        Ok(room) => Ok(room.view_for(&session_id)),
+    }
+}
+
+#[server(PostRoomPreferences, "/api")]
+pub async fn post_room_preferences(
+    code: String,
+    providers: Vec<i32>,
+    genres: Vec<i32>,
+) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    match rooms::post_preferences(store.as_ref(), &code, &session_id, providers, genres).await {
+        Err(err) => Err(room_error("saving preferences", err)),
+        Ok(room) => {
+            if let Some(member) = room.member(&session_id) {
+                publish_room_event(
+                    &room.code,
+                    RoomEvent::PreferencesUpdated {
+                        name: member.name.clone(),
+                    },
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+/* Builds the joint discover query from everyone's preferences and opens the grid for voting */
+#[server(StartRoomVoting, "/api")]
+pub async fn start_room_voting(code: String) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+    let tmdb = Arc::clone(&TMDB);
+
+    let room = match rooms::load_room(store.as_ref(), &code).await {
+        Err(err) => return Err(room_error("starting vote", err)),
+        Ok(room) if !room.is_host(&session_id) => {
+            return Err(room_error("starting vote", rooms::RoomError::NotHost))
+        }
+        Ok(room) => room,
+    };
+
+    let (providers, genres) = match (room.joint_providers(), room.joint_genres()) {
+        (Ok(providers), Ok(genres)) => (providers, genres),
+        (Err(err), _) | (_, Err(err)) => return Err(room_error("starting vote", err)),
+    };
+
+    // A host in family mode keeps the whole room within their limit
+    let family_limit = store
+        .load(&session_id)
+        .await
+        .ok()
+        .and_then(|criteria| criteria.family_limit());
+
+    // Any of the room's genres will do - all of them at once rarely matches anything
+    let query = DiscoverQuery::movies()
+        .decade(&room.decade)
+        .runtime(room.runtime.runtime())
+        .genres(genres.clone(), Match::Any)
+        .providers(providers)
+        .certification(family_limit.as_ref());
+
+    let candidates = match tmdb.get_recommendations(&query).await {
+        Err(err) => {
+            return Err(ServerFnError::new(format!(
+                "Error fetching recommendations: {}",
+                err
+            )))
+        }
+        Ok(recommendations) => rooms::rank_by_genres(recommendations.results, &genres),
+    };
+
+    match rooms::open_voting(store.as_ref(), &code, &session_id, candidates).await {
+        Err(err) => Err(room_error("starting vote", err)),
+        Ok(room) => {
+            publish_room_event(&room.code, RoomEvent::VotingStarted);
+            Ok(())
+        }
+    }
+}
+
+#[server(VoteInRoom, "/api")]
+pub async fn vote_in_room(code: String, movie_id: i64) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    match rooms::vote(store.as_ref(), &code, &session_id, movie_id).await {
+        Err(err) => Err(room_error("voting", err)),
+        // The last vote in decides the room, so that one goes out as the decision instead
+        Ok(room) if room.status == RoomStatus::Decided => {
+            publish_room_event(&room.code, RoomEvent::Decided { movie_id: room.winner });
+            Ok(())
+        }
+        Ok(room) => {
+            if let Some(member) = room.member(&session_id) {
+                publish_room_event(
+                    &room.code,
+                    RoomEvent::VoteCast {
+                        name: member.name.clone(),
+                    },
+                );
+            }
+            Ok(())
         }
     }
 }
 
+#[server(CloseRoomVoting, "/api")]
+pub async fn close_room_voting(code: String) -> Result<(), ServerFnError> {
+    let session_id = use_session_id()?;
+    let store = use_session_store()?;
+
+    match rooms::close_voting(store.as_ref(), &code, &session_id).await {
+        Err(err) => Err(room_error("closing vote", err)),
+        Ok(room) => {
+            publish_room_event(&room.code, RoomEvent::Decided { movie_id: room.winner });
+            Ok(())
+        }
+    }
+}
+
+/* Names are in the session's display language when there is one */
 #[server(FetchGenres, "/api", "GetJson")]
 pub async fn fetch_genres() -> Result<Vec<Genre>, ServerFnError> {
     let tmdb = Arc::clone(&TMDB);
 
-    let genre_list = tmdb.get_genre_list().await;
+    let store = use_session_store()?;
+
+    let language = match use_session_id() {
+        Ok(session_id) => store
+            .load(&session_id)
+            .await
+            .map(|criteria| criteria.languages.display_language())
+            .unwrap_or(String::from(DEFAULT_LANGUAGE)),
+        Err(_) => String::from(DEFAULT_LANGUAGE),
+    };
 
-    match tmdb.get_genre_list().await {
-        Ok(list) => Ok(list.genres),
+    match tmdb_cache::genres(store.as_ref(), tmdb.as_ref(), &language).await {
+        Ok(genres) => Ok(genres),
         Err(err) => Err(ServerFnError::new(format!(
             "Error fetching genres: {}",
             err
@@ -363,83 +1247,85 @@ pub async fn fetch_genres() -> Result<Vec<Genre>, ServerFnError> {
     }
 }
 
+#[cfg(feature = "ssr")]
+fn set_session_cookie(response: &ResponseOptions, session_id: &str) {
+    use actix_web::{http::header, http::header::HeaderValue};
+    use leptos::leptos_config::Env;
+
+    let secure = matches!(
+        use_context::<LeptosOptions>().map(|options| options.env),
+        Some(Env::PROD)
+    );
+
+    let cookie = session_cookie::build_session_cookie(session_id, secure);
+
+    response.append_header(
+        header::SET_COOKIE,
+        HeaderValue::from_str(&cookie.to_string()).expect("to create header value"),
+    );
+}
+
 #[cfg(feature = "ssr")]
 async fn create_session_cookie(response: ResponseOptions) -> Result<String, ServerFnError> {
-    use actix_web::{cookie::Cookie, http::header, http::header::HeaderValue};
-    match redis_helper::start_recommendation_session().await {
+    let store = use_session_store()?;
+
+    match store.create().await {
         Err(err) => Err(ServerFnError::new(format!(
             "Error creating session ID: {}",
             err
         ))),
         Ok(session_id) => {
             println!("Session: {}", &session_id);
-            response.append_header(
-                header::SET_COOKIE,
-                HeaderValue::from_str(&format!(
-                    "SESSION_ID={session_id};\
-                         Path=/"
-                ))
-                .expect("to create header value"),
-            );
+            set_session_cookie(&response, &session_id);
             Ok(session_id)
         }
     }
 }
 
 #[server(StartSession, "/api")]
-pub async fn start_session() -> Result<String, ServerFnError> {
-    use actix_web::{cookie::Cookie, http::header, http::header::HeaderValue};
+pub async fn start_session() -> Result<(), ServerFnError> {
     use leptos_actix::redirect;
     use leptos_actix::ResponseOptions;
     println!("Got request to start session");
     // pull ResponseOptions from context
     let response = expect_context::<leptos_actix::ResponseOptions>();
 
-    let existing_cookie = get_session().await;
+    let existing_cookie = use_session_id();
 
     match existing_cookie {
         Ok(existing_session_id) => {
-            match redis_helper::clear_session_feedback(&existing_session_id).await {
-                Ok(_) => Ok(existing_session_id),
+            println!("Clearing feedback data for {}", &existing_session_id);
+            // Returning users start from their taste profile rather than a blank slate
+            let profile = session_user_profile(&existing_session_id).await;
+            match update_criteria(&existing_session_id, |criteria| {
+                criteria.keyword_profile = KeywordProfile::default();
+                if let Some(profile) = &profile {
+                    accounts::apply_profile(criteria, profile);
+                }
+            })
+            .await
+            {
+                Ok(_) => {
+                    // Re-issue the cookie so its Max-Age lines up with the refreshed store TTL
+                    set_session_cookie(&response, &existing_session_id);
+                    Ok(())
+                }
                 Err(_) => {
                     println!("Error clearing session_data. Creating new cookie");
                     match create_session_cookie(response).await {
                         Err(err) => Err(err),
-                        Ok(session_id) => Ok(session_id),
+                        Ok(_) => Ok(()),
                     }
                 }
             }
         }
         Err(_) => match create_session_cookie(response).await {
             Err(err) => Err(err),
-            Ok(session_id) => Ok(session_id),
+            Ok(_) => Ok(()),
         },
     }
 }
 
-#[server(GetSession, "/api")]
-pub async fn get_session() -> Result<String, ServerFnError> {
-    use actix_web::HttpRequest;
-    use actix_web::{cookie::Cookie, http::header, http::header::HeaderValue};
-    use leptos_actix::ResponseOptions;
-    println!("Got a request for session data");
-    // pull ResponseOptions from context
-    let response = expect_context::<HttpRequest>();
-
-    match response.cookie("SESSION_ID") {
-        Some(cookie) => {
-            println!("Found a cookie: {}", &cookie);
-            Ok(cookie.to_string().replace("SESSION_ID=", ""))
-        }
-        None => {
-            println!("No cookie found :/");
-            Err(ServerFnError::ServerError(
-                "No cookie named SESSION_ID exists".to_string(),
-            ))
-        }
-    }
-}
-
 #[server(TestOutput, "/api")]
 pub async fn test_output() -> Result<(), ServerFnError> {
     println!("Test resource has been requested");
@@ -0,0 +1,152 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use actix_web::{
+        cookie::{time::Duration, Cookie, SameSite},
+        HttpRequest,
+    };
+    use leptos::*;
+    use uuid::Uuid;
+
+    use crate::session_store::SESSION_TTL_SECONDS;
+
+    pub const SESSION_ID_COOKIE: &str = "SESSION_ID";
+
+    /* HttpOnly keeps the ID away from scripts, Secure is only set in PROD since DEV runs over plain http */
+    pub fn build_session_cookie(session_id: &str, secure: bool) -> Cookie<'static> {
+        Cookie::build(SESSION_ID_COOKIE, session_id.to_string())
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .secure(secure)
+            .max_age(Duration::seconds(SESSION_TTL_SECONDS as i64))
+            .finish()
+    }
+
+    /* Only hands back IDs that are well-formed UUIDs - anything else never reaches the session store */
+    pub fn session_id_from_request(req: &HttpRequest) -> Option<String> {
+        let cookie = req.cookie(SESSION_ID_COOKIE)?;
+
+        match Uuid::parse_str(cookie.value()) {
+            Ok(session_id) => Some(session_id.hyphenated().to_string()),
+            Err(_) => None,
+        }
+    }
+
+    /* Session ID for the current server function call - the cookie is the only place it is read from */
+    pub fn use_session_id() -> Result<String, ServerFnError> {
+        let request = use_context::<HttpRequest>()
+            .ok_or_else(|| ServerFnError::new("No request in context"))?;
+
+        session_id_from_request(&request)
+            .ok_or_else(|| ServerFnError::new("No valid cookie named SESSION_ID exists"))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use actix_web::{
+            http::{header, StatusCode},
+            test, web, App, HttpResponse,
+        };
+
+        async fn start_handler(secure: web::Data<bool>) -> HttpResponse {
+            HttpResponse::Ok()
+                .cookie(build_session_cookie(
+                    "67e55044-10b1-426f-9247-bb680e5fe0c8",
+                    **secure,
+                ))
+                .finish()
+        }
+
+        async fn session_handler(req: HttpRequest) -> HttpResponse {
+            match session_id_from_request(&req) {
+                Some(session_id) => HttpResponse::Ok().body(session_id),
+                None => HttpResponse::Unauthorized().finish(),
+            }
+        }
+
+        async fn get_set_cookie(secure: bool) -> String {
+            let app = test::init_service(
+                App::new()
+                    .app_data(web::Data::new(secure))
+                    .route("/start", web::post().to(start_handler)),
+            )
+            .await;
+
+            let req = test::TestRequest::post().uri("/start").to_request();
+            let resp = test::call_service(&app, req).await;
+
+            assert_eq!(resp.status(), StatusCode::OK);
+
+            resp.headers()
+                .get(header::SET_COOKIE)
+                .expect("No Set-Cookie header")
+                .to_str()
+                .unwrap()
+                .to_string()
+        }
+
+        async fn get_session_response(cookie_value: Option<&str>) -> (StatusCode, String) {
+            let app = test::init_service(
+                App::new().route("/session", web::get().to(session_handler)),
+            )
+            .await;
+
+            let mut req = test::TestRequest::get().uri("/session");
+            if let Some(cookie_value) = cookie_value {
+                req = req.cookie(Cookie::new(SESSION_ID_COOKIE, cookie_value.to_string()));
+            }
+
+            let resp = test::call_service(&app, req.to_request()).await;
+            let status = resp.status();
+            let body = test::read_body(resp).await;
+
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        }
+
+        #[actix_web::test]
+        async fn cookie_attributes() {
+            let set_cookie = get_set_cookie(false).await;
+
+            assert!(set_cookie.starts_with("SESSION_ID=67e55044-10b1-426f-9247-bb680e5fe0c8"));
+            assert!(set_cookie.contains("HttpOnly"));
+            assert!(set_cookie.contains("SameSite=Lax"));
+            assert!(set_cookie.contains("Path=/"));
+            assert!(set_cookie.contains(&format!("Max-Age={}", SESSION_TTL_SECONDS)));
+            assert!(!set_cookie.contains("Secure"));
+        }
+
+        #[actix_web::test]
+        async fn cookie_secure_in_prod() {
+            let set_cookie = get_set_cookie(true).await;
+
+            assert!(set_cookie.contains("Secure"));
+        }
+
+        #[actix_web::test]
+        async fn session_from_valid_cookie() {
+            let (status, body) =
+                get_session_response(Some("67e55044-10b1-426f-9247-bb680e5fe0c8")).await;
+
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body, "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        }
+
+        #[actix_web::test]
+        async fn session_from_malformed_cookie() {
+            let (status, _) = get_session_response(Some("user:123*")).await;
+
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+
+        #[actix_web::test]
+        async fn session_without_cookie() {
+            let (status, _) = get_session_response(None).await;
+
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+    }
+}
+}
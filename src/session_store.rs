@@ -0,0 +1,795 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::{
+        collections::HashMap,
+        fmt,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use async_trait::async_trait;
+    use leptos::*;
+    use serde::{de::DeserializeOwned, Deserialize, Serialize};
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+
+    use crate::redis_helper::RedisSessionStore;
+    use crate::RecommendationCriteria;
+
+    /* Errors that can come out of any session store backend */
+    #[derive(Debug)]
+    pub enum SessionStoreError {
+        NotFound(String),
+        Serialization(serde_json::Error),
+        UnsupportedVersion(u32),
+        Backend(String),
+    }
+
+    impl fmt::Display for SessionStoreError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                SessionStoreError::NotFound(session_id) => {
+                    write!(f, "No session found for {}", session_id)
+                }
+                SessionStoreError::Serialization(err) => {
+                    write!(f, "Error (de)serializing session: {}", err)
+                }
+                SessionStoreError::UnsupportedVersion(version) => write!(
+                    f,
+                    "Session schema version {} is newer than this server supports ({})",
+                    version, CRITERIA_SCHEMA_VERSION
+                ),
+                SessionStoreError::Backend(err) => write!(f, "Session store error: {}", err),
+            }
+        }
+    }
+
+    impl std::error::Error for SessionStoreError {}
+
+    impl From<serde_json::Error> for SessionStoreError {
+        fn from(err: serde_json::Error) -> Self {
+            SessionStoreError::Serialization(err)
+        }
+    }
+
+    /* Persistence for recommendation sessions - keyed by session ID */
+    #[async_trait]
+    pub trait SessionStore: Send + Sync {
+        /* Creates an empty session and returns its ID */
+        async fn create(&self) -> Result<String, SessionStoreError>;
+
+        async fn load(&self, session_id: &str) -> Result<RecommendationCriteria, SessionStoreError>;
+
+        async fn save(
+            &self,
+            session_id: &str,
+            criteria: &RecommendationCriteria,
+        ) -> Result<(), SessionStoreError>;
+
+        /* Atomically applies `update` to the stored criteria and returns the result */
+        /* `update` may be called more than once if a backend has to retry on a conflicting write */
+        async fn update(
+            &self,
+            session_id: &str,
+            update: &(dyn for<'c> Fn(&'c mut RecommendationCriteria) + Send + Sync),
+        ) -> Result<RecommendationCriteria, SessionStoreError>;
+
+        async fn delete(&self, session_id: &str) -> Result<(), SessionStoreError>;
+
+        /* Plain keyed values for data that lives alongside sessions (share links, etc.) */
+        async fn get_value(&self, key: &str) -> Result<Option<String>, SessionStoreError>;
+
+        async fn set_value(
+            &self,
+            key: &str,
+            value: &str,
+            ttl_seconds: Option<u64>,
+        ) -> Result<(), SessionStoreError>;
+
+        async fn delete_value(&self, key: &str) -> Result<(), SessionStoreError>;
+
+        /* Atomic read-modify-write of a keyed value - returning Ok(None) from `update` deletes the key */
+        /* Same as `update`, the closure may run more than once */
+        async fn update_value(
+            &self,
+            key: &str,
+            ttl_seconds: Option<u64>,
+            update: &(dyn Fn(Option<String>) -> Result<Option<String>, SessionStoreError>
+                  + Send
+                  + Sync),
+        ) -> Result<Option<String>, SessionStoreError>;
+
+        /* Sets the key only if nothing live is there - true if this call set it (SET NX in redis) */
+        async fn set_value_if_absent(
+            &self,
+            key: &str,
+            value: &str,
+            ttl_seconds: u64,
+        ) -> Result<bool, SessionStoreError>;
+
+        /* Drops expired entries and returns how many went - backends that expire keys themselves have nothing to do */
+        async fn purge_expired(&self) -> Result<usize, SessionStoreError>;
+    }
+
+    /* Sessions expire a week after they were last written - the SESSION_ID cookie uses the same Max-Age */
+    pub const SESSION_TTL_SECONDS: u64 = 60 * 60 * 24 * 7;
+
+    pub fn new_session_id() -> String {
+        Uuid::new_v4().to_string()
+    }
+
+    /*
+        Stored sessions are wrapped in a versioned envelope: {"version": N, "criteria": {...}}
+        Bump CRITERIA_SCHEMA_VERSION and add a step to CRITERIA_MIGRATIONS whenever a change to
+        RecommendationCriteria can't be handled by serde defaults alone (renames, type changes, etc.)
+    */
+    pub const CRITERIA_SCHEMA_VERSION: u32 = 2;
+
+    /* CRITERIA_MIGRATIONS[n] upgrades criteria JSON from version n to version n + 1 */
+    const CRITERIA_MIGRATIONS: &[fn(Value) -> Value] = &[migrate_v0_to_v1, migrate_v1_to_v2];
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct StoredCriteria {
+        version: u32,
+        criteria: Value,
+    }
+
+    /* v0 was the bare criteria JSON with no envelope - the shape itself didn't change */
+    fn migrate_v0_to_v1(criteria: Value) -> Value {
+        criteria
+    }
+
+    /* v2 replaced the ever-growing feedback keyword lists with a weighted keyword profile */
+    /* Old likes/dislikes carry over as +1/-1 weights */
+    fn migrate_v1_to_v2(mut criteria: Value) -> Value {
+        if let Some(fields) = criteria.as_object_mut() {
+            let feedback = fields.remove("feedback").unwrap_or(Value::Null);
+            let mut weights = serde_json::Map::new();
+
+            for (field, weight) in [("like", 1.0), ("dislike", -1.0)] {
+                if let Some(ids) = feedback.get(field).and_then(Value::as_array) {
+                    for id in ids.iter().filter_map(Value::as_i64) {
+                        weights.insert(id.to_string(), json!(weight));
+                    }
+                }
+            }
+
+            fields.insert(String::from("keyword_profile"), json!({ "weights": weights }));
+        }
+
+        criteria
+    }
+
+    fn migrate_criteria(version: u32, mut criteria: Value) -> Result<Value, SessionStoreError> {
+        if version > CRITERIA_SCHEMA_VERSION {
+            return Err(SessionStoreError::UnsupportedVersion(version));
+        }
+
+        for migration in &CRITERIA_MIGRATIONS[version as usize..] {
+            criteria = migration(criteria);
+        }
+
+        Ok(criteria)
+    }
+
+    pub fn encode_criteria(criteria: &RecommendationCriteria) -> Result<String, SessionStoreError> {
+        let stored = StoredCriteria {
+            version: CRITERIA_SCHEMA_VERSION,
+            criteria: serde_json::to_value(criteria)?,
+        };
+
+        Ok(serde_json::to_string(&stored)?)
+    }
+
+    pub fn decode_criteria(json: &str) -> Result<RecommendationCriteria, SessionStoreError> {
+        let value: Value = serde_json::from_str(json)?;
+
+        // Anything without the envelope predates versioning
+        let stored = match serde_json::from_value::<StoredCriteria>(value.clone()) {
+            Ok(stored) => stored,
+            Err(_) => StoredCriteria {
+                version: 0,
+                criteria: value,
+            },
+        };
+
+        let criteria = migrate_criteria(stored.version, stored.criteria)?;
+
+        Ok(serde_json::from_value(criteria)?)
+    }
+
+    /* Process-local store - sessions are lost on restart and not shared between instances */
+    #[derive(Default)]
+    pub struct MemorySessionStore {
+        sessions: Mutex<HashMap<String, MemoryEntry>>,
+    }
+
+    struct MemoryEntry {
+        json_string: String,
+        expires_at: Option<Instant>,
+    }
+
+    impl MemoryEntry {
+        /* Every session write pushes expiry out by the TTL, same as SET EX in redis */
+        fn new(json_string: String) -> Self {
+            Self::with_ttl(json_string, Some(SESSION_TTL_SECONDS))
+        }
+
+        fn with_ttl(json_string: String, ttl_seconds: Option<u64>) -> Self {
+            Self {
+                json_string,
+                expires_at: ttl_seconds.map(|ttl| Instant::now() + Duration::from_secs(ttl)),
+            }
+        }
+
+        fn is_live(&self) -> bool {
+            match self.expires_at {
+                Some(expires_at) => expires_at > Instant::now(),
+                None => true,
+            }
+        }
+    }
+
+    impl MemorySessionStore {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        fn live_entry<'a>(
+            sessions: &'a HashMap<String, MemoryEntry>,
+            session_id: &str,
+        ) -> Result<&'a MemoryEntry, SessionStoreError> {
+            match sessions.get(session_id) {
+                Some(entry) if entry.is_live() => Ok(entry),
+                _ => Err(SessionStoreError::NotFound(session_id.to_string())),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SessionStore for MemorySessionStore {
+        async fn create(&self) -> Result<String, SessionStoreError> {
+            let session_id = new_session_id();
+            let json_string = encode_criteria(&RecommendationCriteria::default())?;
+
+            self.sessions
+                .lock()
+                .expect("Session store lock poisoned")
+                .insert(session_id.clone(), MemoryEntry::new(json_string));
+
+            Ok(session_id)
+        }
+
+        async fn load(&self, session_id: &str) -> Result<RecommendationCriteria, SessionStoreError> {
+            let sessions = self.sessions.lock().expect("Session store lock poisoned");
+
+            decode_criteria(&Self::live_entry(&sessions, session_id)?.json_string)
+        }
+
+        async fn save(
+            &self,
+            session_id: &str,
+            criteria: &RecommendationCriteria,
+        ) -> Result<(), SessionStoreError> {
+            let json_string = encode_criteria(criteria)?;
+
+            self.sessions
+                .lock()
+                .expect("Session store lock poisoned")
+                .insert(session_id.to_string(), MemoryEntry::new(json_string));
+
+            Ok(())
+        }
+
+        async fn update(
+            &self,
+            session_id: &str,
+            update: &(dyn for<'c> Fn(&'c mut RecommendationCriteria) + Send + Sync),
+        ) -> Result<RecommendationCriteria, SessionStoreError> {
+            // Lock is held for the whole read-modify-write so concurrent updates can't interleave
+            let mut sessions = self.sessions.lock().expect("Session store lock poisoned");
+
+            let mut criteria = decode_criteria(&Self::live_entry(&sessions, session_id)?.json_string)?;
+
+            update(&mut criteria);
+
+            sessions.insert(
+                session_id.to_string(),
+                MemoryEntry::new(encode_criteria(&criteria)?),
+            );
+
+            Ok(criteria)
+        }
+
+        async fn delete(&self, session_id: &str) -> Result<(), SessionStoreError> {
+            self.sessions
+                .lock()
+                .expect("Session store lock poisoned")
+                .remove(session_id);
+
+            Ok(())
+        }
+
+        async fn get_value(&self, key: &str) -> Result<Option<String>, SessionStoreError> {
+            let sessions = self.sessions.lock().expect("Session store lock poisoned");
+
+            Ok(sessions
+                .get(key)
+                .filter(|entry| entry.is_live())
+                .map(|entry| entry.json_string.clone()))
+        }
+
+        async fn set_value(
+            &self,
+            key: &str,
+            value: &str,
+            ttl_seconds: Option<u64>,
+        ) -> Result<(), SessionStoreError> {
+            self.sessions
+                .lock()
+                .expect("Session store lock poisoned")
+                .insert(key.to_string(), MemoryEntry::with_ttl(value.to_string(), ttl_seconds));
+
+            Ok(())
+        }
+
+        async fn delete_value(&self, key: &str) -> Result<(), SessionStoreError> {
+            self.sessions
+                .lock()
+                .expect("Session store lock poisoned")
+                .remove(key);
+
+            Ok(())
+        }
+
+        async fn update_value(
+            &self,
+            key: &str,
+            ttl_seconds: Option<u64>,
+            update: &(dyn Fn(Option<String>) -> Result<Option<String>, SessionStoreError>
+                  + Send
+                  + Sync),
+        ) -> Result<Option<String>, SessionStoreError> {
+            let mut sessions = self.sessions.lock().expect("Session store lock poisoned");
+
+            let current = sessions
+                .get(key)
+                .filter(|entry| entry.is_live())
+                .map(|entry| entry.json_string.clone());
+
+            let updated = update(current)?;
+
+            match &updated {
+                Some(value) => {
+                    sessions.insert(key.to_string(), MemoryEntry::with_ttl(value.clone(), ttl_seconds));
+                }
+                None => {
+                    sessions.remove(key);
+                }
+            }
+
+            Ok(updated)
+        }
+
+        async fn set_value_if_absent(
+            &self,
+            key: &str,
+            value: &str,
+            ttl_seconds: u64,
+        ) -> Result<bool, SessionStoreError> {
+            let mut sessions = self.sessions.lock().expect("Session store lock poisoned");
+
+            if sessions.get(key).is_some_and(|entry| entry.is_live()) {
+                return Ok(false);
+            }
+
+            sessions.insert(key.to_string(), MemoryEntry::with_ttl(value.to_string(), Some(ttl_seconds)));
+
+            Ok(true)
+        }
+
+        async fn purge_expired(&self) -> Result<usize, SessionStoreError> {
+            let mut sessions = self.sessions.lock().expect("Session store lock poisoned");
+
+            let before = sessions.len();
+            sessions.retain(|_, entry| entry.is_live());
+
+            Ok(before - sessions.len())
+        }
+    }
+
+    /* Picks a backend from the SESSION_STORE env var - "memory" or "redis" (default) */
+    pub fn store_from_env() -> Arc<dyn SessionStore> {
+        match std::env::var("SESSION_STORE") {
+            Ok(backend) if backend.trim().eq_ignore_ascii_case("memory") => {
+                println!("Using in-memory session store...");
+                Arc::new(MemorySessionStore::new())
+            }
+            _ => {
+                println!("Using redis session store...");
+                Arc::new(RedisSessionStore::from_env().expect("Invalid redis connection string"))
+            }
+        }
+    }
+
+    /* Typed JSON helpers on top of the keyed value methods */
+    pub async fn load_json<T: DeserializeOwned>(
+        store: &dyn SessionStore,
+        key: &str,
+    ) -> Result<Option<T>, SessionStoreError> {
+        match store.get_value(key).await? {
+            Some(json_string) => Ok(Some(serde_json::from_str(&json_string)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn save_json<T: Serialize + Sync>(
+        store: &dyn SessionStore,
+        key: &str,
+        value: &T,
+        ttl_seconds: Option<u64>,
+    ) -> Result<(), SessionStoreError> {
+        store
+            .set_value(key, &serde_json::to_string(value)?, ttl_seconds)
+            .await
+    }
+
+    /* Missing values start out as T::default() */
+    pub async fn update_json<T, F>(
+        store: &dyn SessionStore,
+        key: &str,
+        ttl_seconds: Option<u64>,
+        update: F,
+    ) -> Result<T, SessionStoreError>
+    where
+        T: Serialize + DeserializeOwned + Default,
+        F: Fn(&mut T) + Send + Sync,
+    {
+        let updated = store
+            .update_value(key, ttl_seconds, &|current| {
+                let mut value: T = match current {
+                    Some(json_string) => serde_json::from_str(&json_string)?,
+                    None => T::default(),
+                };
+                update(&mut value);
+                Ok(Some(serde_json::to_string(&value)?))
+            })
+            .await?;
+
+        match updated {
+            Some(json_string) => Ok(serde_json::from_str(&json_string)?),
+            None => Ok(T::default()),
+        }
+    }
+
+    const SHARE_KEY_PREFIX: &str = "share:";
+
+    /* Share tokens are separate from session IDs so a shared link can only ever read a session */
+    pub async fn create_share_token(
+        store: &dyn SessionStore,
+        session_id: &str,
+    ) -> Result<String, SessionStoreError> {
+        // Make sure we aren't handing out a link to a session that doesn't exist
+        store.load(session_id).await?;
+
+        let token = Uuid::new_v4().simple().to_string();
+
+        store
+            .set_value(
+                &format!("{}{}", SHARE_KEY_PREFIX, token),
+                session_id,
+                Some(SESSION_TTL_SECONDS),
+            )
+            .await?;
+
+        Ok(token)
+    }
+
+    pub async fn resolve_share_token(
+        store: &dyn SessionStore,
+        token: &str,
+    ) -> Result<String, SessionStoreError> {
+        match store
+            .get_value(&format!("{}{}", SHARE_KEY_PREFIX, token))
+            .await?
+        {
+            Some(session_id) => Ok(session_id),
+            None => Err(SessionStoreError::NotFound(token.to_string())),
+        }
+    }
+
+    /* Pulls the session store provided to the Leptos context in main.rs */
+    pub fn use_session_store() -> Result<Arc<dyn SessionStore>, ServerFnError> {
+        use_context::<Arc<dyn SessionStore>>()
+            .ok_or_else(|| ServerFnError::new("No session store in context"))
+    }
+
+    /* Atomic read-modify-write of a session's criteria for use in server functions */
+    pub async fn update_criteria<F>(
+        session_id: &str,
+        update: F,
+    ) -> Result<RecommendationCriteria, ServerFnError>
+    where
+        F: Fn(&mut RecommendationCriteria) + Send + Sync,
+    {
+        let store = use_session_store()?;
+
+        store
+            .update(session_id, &update)
+            .await
+            .map_err(|err| ServerFnError::new(format!("Error updating session criteria: {}", err)))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::*;
+
+        fn get_criteria() -> RecommendationCriteria {
+            RecommendationCriteria {
+                genres: Some(vec![28, 12]),
+                watch_providers: Some(vec![8]),
+                runtime: Some(Runtime::MovieNight),
+                decade: Some(Decade::Eighties),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn criteria_encoding_roundtrip() {
+            let json_string = encode_criteria(&get_criteria()).unwrap();
+
+            assert!(json_string.starts_with(&format!("{{\"version\":{}", CRITERIA_SCHEMA_VERSION)));
+
+            assert_eq!(decode_criteria(&json_string).unwrap(), get_criteria());
+        }
+
+        #[test]
+        fn decode_unversioned_criteria() {
+            let legacy_json = "{\"genres\":[28,12],\"watch_providers\":[8],\"runtime\":\"MovieNight\",\"decade\":\"Eighties\",\"feedback\":null}";
+
+            assert_eq!(decode_criteria(legacy_json).unwrap(), get_criteria());
+        }
+
+        #[test]
+        fn decode_fills_missing_fields() {
+            let partial_json = "{\"version\":2,\"criteria\":{\"genres\":[28,12]}}";
+
+            let criteria = decode_criteria(partial_json).unwrap();
+
+            assert_eq!(criteria.genres, Some(vec![28, 12]));
+            assert_eq!(criteria.watch_providers, None);
+            assert_eq!(criteria.keyword_profile, KeywordProfile::default());
+        }
+
+        #[test]
+        fn decode_v1_feedback_as_weights() {
+            let v1_json = "{\"version\":1,\"criteria\":{\"genres\":[28,12],\"feedback\":{\"like\":[1,2],\"dislike\":[3]}}}";
+
+            let criteria = decode_criteria(v1_json).unwrap();
+
+            assert_eq!(criteria.genres, Some(vec![28, 12]));
+            assert_eq!(criteria.keyword_profile.weights.len(), 3);
+            assert_eq!(criteria.keyword_profile.weights[&1], 1.0);
+            assert_eq!(criteria.keyword_profile.weights[&3], -1.0);
+            assert_eq!(criteria.keyword_profile.documents, 0);
+        }
+
+        #[test]
+        fn decode_ignores_unknown_fields() {
+            let future_json = "{\"version\":1,\"criteria\":{\"genres\":[28,12],\"watch_providers\":[8],\"runtime\":\"MovieNight\",\"decade\":\"Eighties\",\"region\":\"GB\"}}";
+
+            assert_eq!(decode_criteria(future_json).unwrap(), get_criteria());
+        }
+
+        #[test]
+        fn decode_rejects_newer_version() {
+            let newer_json = format!(
+                "{{\"version\":{},\"criteria\":{{}}}}",
+                CRITERIA_SCHEMA_VERSION + 1
+            );
+
+            assert!(matches!(
+                decode_criteria(&newer_json),
+                Err(SessionStoreError::UnsupportedVersion(_))
+            ));
+        }
+
+        #[tokio::test]
+        async fn memory_create_session() {
+            let store = MemorySessionStore::new();
+
+            let session_id = store.create().await.unwrap();
+
+            assert!(!session_id.is_empty());
+
+            let criteria = store.load(&session_id).await.unwrap();
+
+            assert_eq!(criteria, RecommendationCriteria::default());
+        }
+
+        #[tokio::test]
+        async fn memory_criteria_roundtrip() {
+            let store = MemorySessionStore::new();
+            let session_id = store.create().await.unwrap();
+
+            store.save(&session_id, &get_criteria()).await.unwrap();
+
+            assert_eq!(store.load(&session_id).await.unwrap(), get_criteria());
+        }
+
+        #[tokio::test]
+        async fn memory_update() {
+            let store = MemorySessionStore::new();
+            let session_id = store.create().await.unwrap();
+
+            store.save(&session_id, &get_criteria()).await.unwrap();
+
+            let updated = store
+                .update(&session_id, &|criteria| criteria.decade = Some(Decade::Recent))
+                .await
+                .unwrap();
+
+            assert_eq!(updated.decade, Some(Decade::Recent));
+            assert_eq!(updated.genres, Some(vec![28, 12]));
+            assert_eq!(store.load(&session_id).await.unwrap(), updated);
+        }
+
+        #[tokio::test]
+        async fn memory_concurrent_updates() {
+            let store = Arc::new(MemorySessionStore::new());
+            let session_id = store.create().await.unwrap();
+
+            let mut handles = vec![];
+
+            for genre in 0..50 {
+                let store = Arc::clone(&store);
+                let session_id = session_id.clone();
+                handles.push(tokio::spawn(async move {
+                    store
+                        .update(&session_id, &|criteria| {
+                            criteria.genres.get_or_insert_with(Vec::new).push(genre)
+                        })
+                        .await
+                        .unwrap();
+                }));
+            }
+
+            for handle in handles {
+                handle.await.unwrap();
+            }
+
+            let mut genres = store.load(&session_id).await.unwrap().genres.unwrap();
+            genres.sort();
+
+            assert_eq!(genres, (0..50).collect::<Vec<i32>>());
+        }
+
+        #[tokio::test]
+        async fn memory_update_missing_session() {
+            let store = MemorySessionStore::new();
+
+            let response = store
+                .update("not-a-session", &|criteria| criteria.genres = None)
+                .await;
+
+            assert!(matches!(response, Err(SessionStoreError::NotFound(_))));
+        }
+
+        #[tokio::test]
+        async fn memory_values() {
+            let store = MemorySessionStore::new();
+
+            assert_eq!(store.get_value("share:abc").await.unwrap(), None);
+
+            store.set_value("share:abc", "123", Some(60)).await.unwrap();
+
+            assert_eq!(
+                store.get_value("share:abc").await.unwrap(),
+                Some("123".to_string())
+            );
+
+            store.set_value("share:expired", "456", Some(0)).await.unwrap();
+
+            assert_eq!(store.get_value("share:expired").await.unwrap(), None);
+
+            store.delete_value("share:abc").await.unwrap();
+
+            assert_eq!(store.get_value("share:abc").await.unwrap(), None);
+        }
+
+        #[tokio::test]
+        async fn memory_update_json() {
+            let store = MemorySessionStore::new();
+
+            let counts: Vec<i64> = update_json(&store, "counts", None, |counts: &mut Vec<i64>| {
+                counts.push(1)
+            })
+            .await
+            .unwrap();
+
+            assert_eq!(counts, vec![1]);
+
+            update_json(&store, "counts", None, |counts: &mut Vec<i64>| counts.push(2))
+                .await
+                .unwrap();
+
+            let stored: Option<Vec<i64>> = load_json(&store, "counts").await.unwrap();
+
+            assert_eq!(stored, Some(vec![1, 2]));
+        }
+
+        #[tokio::test]
+        async fn memory_update_value_delete() {
+            let store = MemorySessionStore::new();
+
+            store.set_value("key", "value", None).await.unwrap();
+
+            let updated = store.update_value("key", None, &|_| Ok(None)).await.unwrap();
+
+            assert_eq!(updated, None);
+            assert_eq!(store.get_value("key").await.unwrap(), None);
+        }
+
+        #[tokio::test]
+        async fn memory_set_if_absent() {
+            let store = MemorySessionStore::new();
+
+            assert!(store.set_value_if_absent("lock:job", "a", 60).await.unwrap());
+            assert!(!store.set_value_if_absent("lock:job", "b", 60).await.unwrap());
+            assert_eq!(store.get_value("lock:job").await.unwrap(), Some("a".to_string()));
+
+            // An expired holder doesn't count
+            store.set_value("lock:stale", "a", Some(0)).await.unwrap();
+            assert!(store.set_value_if_absent("lock:stale", "b", 60).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn memory_purge_expired() {
+            let store = MemorySessionStore::new();
+
+            store.set_value("live", "1", Some(60)).await.unwrap();
+            store.set_value("forever", "2", None).await.unwrap();
+            store.set_value("expired", "3", Some(0)).await.unwrap();
+
+            assert_eq!(store.purge_expired().await.unwrap(), 1);
+            assert_eq!(store.purge_expired().await.unwrap(), 0);
+            assert_eq!(store.get_value("live").await.unwrap(), Some("1".to_string()));
+        }
+
+        #[tokio::test]
+        async fn share_token_roundtrip() {
+            let store = MemorySessionStore::new();
+            let session_id = store.create().await.unwrap();
+
+            let token = create_share_token(&store, &session_id).await.unwrap();
+
+            assert_ne!(token, session_id);
+            assert_eq!(resolve_share_token(&store, &token).await.unwrap(), session_id);
+            assert!(resolve_share_token(&store, "not-a-token").await.is_err());
+        }
+
+        #[tokio::test]
+        async fn share_token_missing_session() {
+            let store = MemorySessionStore::new();
+
+            assert!(create_share_token(&store, "not-a-session").await.is_err());
+        }
+
+        #[tokio::test]
+        async fn memory_delete() {
+            let store = MemorySessionStore::new();
+            let session_id = store.create().await.unwrap();
+
+            store.delete(&session_id).await.unwrap();
+
+            assert!(matches!(
+                store.load(&session_id).await,
+                Err(SessionStoreError::NotFound(_))
+            ));
+        }
+    }
+}
+}
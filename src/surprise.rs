@@ -0,0 +1,298 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use std::{collections::HashMap, sync::Arc};
+
+    use rand::Rng;
+
+    use crate::discover::{DiscoverQuery, Match};
+    use crate::tmdb::Tmdb;
+    use crate::*;
+
+    // Each liked movie in a genre/decade counts this much more than a genre/decade with no likes
+    const SURPRISE_LIKE_WEIGHT: u32 = 3;
+    // Only this many liked movies are looked up - enough to get a feel for taste without a pile of requests
+    const SURPRISE_LIKED_SAMPLE: usize = 10;
+    // Well-rated means at least this average over at least this many votes
+    const SURPRISE_MIN_RATING: f64 = 7.0;
+    const SURPRISE_MIN_VOTES: u32 = 300;
+    // Random pages are drawn from the first few of the best-rated results
+    const SURPRISE_MAX_PAGE: u32 = 5;
+    const SURPRISE_PAGE_SAMPLES: usize = 3;
+    // Narrow genre/decade combinations can come up empty, so a few are tried before giving up
+    const SURPRISE_ATTEMPTS: usize = 3;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Surprise {
+        pub movie: Movie,
+        pub genre_name: Option<String>,
+        pub decade: Decade,
+    }
+
+    /* Liked movies the session knows about, most recent first */
+    pub fn liked_movie_ids(profile: Option<&TasteProfile>, criteria: &RecommendationCriteria) -> Vec<i64> {
+        let mut liked: Vec<i64> = vec![];
+
+        let profile_likes = profile
+            .map(|profile| profile.liked_movies.iter().rev().copied().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let mut session_likes: Vec<i64> = criteria.keyword_profile.sources.values().copied().collect();
+        session_likes.sort();
+
+        for id in profile_likes.into_iter().chain(session_likes) {
+            if !liked.contains(&id) {
+                liked.push(id);
+            }
+        }
+
+        liked.truncate(SURPRISE_LIKED_SAMPLE);
+        liked
+    }
+
+    /* One entry per option - the base weight of 1 keeps everything possible, likes make it more likely */
+    pub fn genre_weights(genre_ids: &[i32], liked_movies: &[Movie]) -> Vec<(i32, u32)> {
+        genre_ids
+            .iter()
+            .map(|id| {
+                let likes = liked_movies
+                    .iter()
+                    .filter(|movie| movie.genre_ids.contains(id))
+                    .count() as u32;
+                (*id, 1 + likes * SURPRISE_LIKE_WEIGHT)
+            })
+            .collect()
+    }
+
+    pub fn decade_weights(liked_movies: &[Movie]) -> Vec<(Decade, u32)> {
+        Decade::all()
+            .into_iter()
+            .map(|decade| {
+                let likes = liked_movies
+                    .iter()
+                    .filter(|movie| Decade::from_year(&movie.release_date).as_ref() == Some(&decade))
+                    .count() as u32;
+                (decade, 1 + likes * SURPRISE_LIKE_WEIGHT)
+            })
+            .collect()
+    }
+
+    /* roll is anywhere in 0..total weight */
+    pub fn weighted_pick<T: Clone>(options: &[(T, u32)], roll: u32) -> Option<T> {
+        let mut remaining = roll;
+
+        for (option, weight) in options {
+            if remaining < *weight {
+                return Some(option.clone());
+            }
+            remaining -= weight;
+        }
+
+        None
+    }
+
+    fn random_pick<T: Clone>(options: &[(T, u32)]) -> Option<T> {
+        let total: u32 = options.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+
+        weighted_pick(options, rand::thread_rng().gen_range(0..total))
+    }
+
+    /* Excluded languages are only filtered out after fetching, so with any set every page is read */
+    fn random_pages(languages: &LanguageFilter) -> Vec<u32> {
+        let samples = match languages.exclude.is_empty() {
+            true => SURPRISE_PAGE_SAMPLES,
+            false => SURPRISE_MAX_PAGE as usize,
+        };
+        let mut rng = rand::thread_rng();
+        let mut pages: Vec<u32> = vec![];
+
+        while pages.len() < samples {
+            let page = rng.gen_range(1..=SURPRISE_MAX_PAGE);
+            if !pages.contains(&page) {
+                pages.push(page);
+            }
+        }
+
+        pages
+    }
+
+    async fn fetch_liked_movies(tmdb: &Arc<Tmdb>, liked_ids: Vec<i64>) -> Vec<Movie> {
+        let mut handles = vec![];
+        for id in liked_ids {
+            let temp_tmdb = Arc::clone(tmdb);
+            handles.push(tokio::spawn(async move {
+                temp_tmdb.get_movie_by_id(&id).await.ok()
+            }));
+        }
+
+        let mut liked_movies = vec![];
+        for handle in handles {
+            if let Ok(Some(movie)) = handle.await {
+                liked_movies.push(movie);
+            }
+        }
+
+        liked_movies
+    }
+
+    /* Candidates from a few random pages, minus anything already seen or watched */
+    async fn fetch_candidates(
+        tmdb: &Arc<Tmdb>,
+        query: &DiscoverQuery,
+        languages: &LanguageFilter,
+        exclude: &[(MediaType, i64)],
+    ) -> Vec<Movie> {
+        let mut handles = vec![];
+        for page in random_pages(languages) {
+            let temp_tmdb = Arc::clone(tmdb);
+            let query = query.clone().page(page);
+            handles.push(tokio::spawn(async move {
+                match temp_tmdb.get_recommendations(&query).await {
+                    Ok(response) => response.results,
+                    Err(_) => vec![],
+                }
+            }));
+        }
+
+        let mut candidates: Vec<Movie> = vec![];
+        for handle in handles {
+            for movie in handle.await.unwrap_or_default() {
+                if !exclude.contains(&(movie.media_type, movie.id))
+                    && languages.allows(&movie.original_language)
+                    && !candidates.iter().any(|candidate| candidate.id == movie.id) {
+                    candidates.push(movie);
+                }
+            }
+        }
+
+        candidates
+    }
+
+    pub async fn find_surprise(
+        tmdb: Arc<Tmdb>,
+        providers: Vec<i32>,
+        liked_ids: Vec<i64>,
+        languages: &LanguageFilter,
+        certification: Option<&CertificationLimit>,
+        exclude: &[(MediaType, i64)],
+    ) -> Result<Option<Surprise>, Box<dyn std::error::Error>> {
+        let genres = tmdb.get_genre_list(&languages.display_language()).await?.genres;
+        let genre_names: HashMap<i32, String> = genres
+            .iter()
+            .map(|genre| (genre.id, genre.name.clone()))
+            .collect();
+        let genre_ids: Vec<i32> = genres.iter().map(|genre| genre.id).collect();
+
+        let liked_movies = fetch_liked_movies(&tmdb, liked_ids).await;
+        let genre_options = genre_weights(&genre_ids, &liked_movies);
+        let decade_options = decade_weights(&liked_movies);
+
+        for _ in 0..SURPRISE_ATTEMPTS {
+            let (genre, decade) = match (random_pick(&genre_options), random_pick(&decade_options)) {
+                (Some(genre), Some(decade)) => (genre, decade),
+                _ => return Ok(None),
+            };
+
+            let query = DiscoverQuery::movies()
+                .decade(&decade)
+                .genres(vec![genre], Match::All)
+                .providers(providers.clone())
+                .languages(languages)
+                .certification(certification)
+                .min_rating(SURPRISE_MIN_RATING)
+                .min_votes(SURPRISE_MIN_VOTES)
+                .sort_by(SortBy::Rating);
+
+            let candidates = fetch_candidates(&tmdb, &query, languages, exclude).await;
+            if candidates.is_empty() {
+                continue;
+            }
+
+            let index = rand::thread_rng().gen_range(0..candidates.len());
+
+            return Ok(Some(Surprise {
+                movie: candidates[index].clone(),
+                genre_name: genre_names.get(&genre).cloned(),
+                decade,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn movie(id: i64, release_date: &str, genre_ids: Vec<i32>) -> Movie {
+            Movie {
+                release_date: release_date.to_string(),
+                genre_ids,
+                ..test_movie(id)
+            }
+        }
+
+        #[test]
+        fn likes_weight_genres_and_decades() {
+            let liked_movies = vec![
+                movie(1, "1995-12-15", vec![80, 18]),
+                movie(2, "1994-09-23", vec![80]),
+            ];
+
+            assert_eq!(
+                genre_weights(&[28, 80, 18], &liked_movies),
+                vec![(28, 1), (80, 7), (18, 4)]
+            );
+
+            let decades = decade_weights(&liked_movies);
+            assert_eq!(decades.len(), Decade::all().len());
+            assert!(decades.contains(&(Decade::Nineties, 7)));
+            assert!(decades.contains(&(Decade::Eighties, 1)));
+        }
+
+        #[test]
+        fn picks_by_weight() {
+            let options = vec![("a", 1), ("b", 3), ("c", 0), ("d", 2)];
+
+            assert_eq!(weighted_pick(&options, 0), Some("a"));
+            assert_eq!(weighted_pick(&options, 1), Some("b"));
+            assert_eq!(weighted_pick(&options, 3), Some("b"));
+            assert_eq!(weighted_pick(&options, 4), Some("d"));
+            assert_eq!(weighted_pick(&options, 6), None);
+        }
+
+        #[test]
+        fn liked_ids_recent_first() {
+            let profile = TasteProfile {
+                liked_movies: vec![1, 2, 3],
+                ..Default::default()
+            };
+            let mut criteria = RecommendationCriteria::default();
+            criteria.keyword_profile.sources.insert(100, 3);
+            criteria.keyword_profile.sources.insert(200, 4);
+
+            assert_eq!(liked_movie_ids(Some(&profile), &criteria), vec![3, 2, 1, 4]);
+            assert_eq!(liked_movie_ids(None, &criteria), vec![3, 4]);
+        }
+
+        #[test]
+        fn random_pages_distinct() {
+            let mut pages = random_pages(&LanguageFilter::default());
+            pages.sort();
+            pages.dedup();
+
+            assert_eq!(pages.len(), SURPRISE_PAGE_SAMPLES);
+            assert!(pages.iter().all(|page| (1..=SURPRISE_MAX_PAGE).contains(page)));
+
+            let excluding = LanguageFilter {
+                exclude: vec![String::from("en")],
+                ..Default::default()
+            };
+            assert_eq!(random_pages(&excluding).len(), SURPRISE_MAX_PAGE as usize);
+        }
+    }
+}
+}
@@ -10,11 +10,15 @@ cfg_if! {
     };
     use serde::{Deserialize, Serialize};
 
+    use crate::discover::{encode, DiscoverQuery};
     use crate::*;
     pub struct AsyncRecommendation {
         pub movie: Movie,
         //pub providers: Vec<WatchProvider>,
-        pub async_providers: tokio::task::JoinHandle<GetWatchProvidersResponse>,
+        // None when the lookup failed - the recommendation is still shown, just without providers
+        pub async_providers: tokio::task::JoinHandle<Option<GetWatchProvidersResponse>>,
+        pub reasons: RecommendationReasons,
+        pub collection: Option<CollectionPosition>,
     }
     /* Struct for interacting with TMDB API */
     #[derive(Clone)]
@@ -86,11 +90,45 @@ cfg_if! {
             Ok(movie_results)
         }
 
+        pub async fn search_people(
+            &self,
+            query: &str,
+        ) -> Result<SearchPeopleResponse, Box<dyn std::error::Error>> {
+            let url = format!("search/person?include_adult=false&{}", encode_param("query", query));
+
+            let search_response = self.make_tmdb_request(&url).await?;
+
+            let people = search_response.json::<SearchPeopleResponse>().await?;
+
+            Ok(people)
+        }
+
+        pub async fn search_companies(
+            &self,
+            query: &str,
+        ) -> Result<SearchCompaniesResponse, Box<dyn std::error::Error>> {
+            let url = format!("search/company?{}", encode_param("query", query));
+
+            let search_response = self.make_tmdb_request(&url).await?;
+
+            let companies = search_response.json::<SearchCompaniesResponse>().await?;
+
+            Ok(companies)
+        }
+
         pub async fn get_keywords_for_id(
             &self,
             movie_id: &i64,
         ) -> Result<KeywordResponse, Box<dyn std::error::Error>> {
-            let url = format!("movie/{}/keywords", movie_id);
+            self.get_keywords_for(&MediaType::Movie, movie_id).await
+        }
+
+        pub async fn get_keywords_for(
+            &self,
+            media_type: &MediaType,
+            id: &i64,
+        ) -> Result<KeywordResponse, Box<dyn std::error::Error>> {
+            let url = format!("{}/{}/keywords", media_type.path(), id);
 
             let keyword_response = self.make_tmdb_request(&url).await?;
 
@@ -99,6 +137,58 @@ cfg_if! {
             Ok(keyword_results)
         }
 
+        pub async fn get_movie_by_id(
+            &self,
+            movie_id: &i64,
+        ) -> Result<Movie, Box<dyn std::error::Error>> {
+            let url = format!("movie/{}", movie_id);
+
+            let movie_response = self.make_tmdb_request(&url).await?;
+
+            let movie = movie_response.json::<Movie>().await?;
+
+            Ok(movie)
+        }
+
+        /* Unlike get_movie_by_id this includes the collection the movie belongs to */
+        pub async fn get_movie_details(
+            &self,
+            movie_id: &i64,
+        ) -> Result<MovieDetails, Box<dyn std::error::Error>> {
+            let url = format!("movie/{}", movie_id);
+
+            let details_response = self.make_tmdb_request(&url).await?;
+
+            let details = details_response.json::<MovieDetails>().await?;
+
+            Ok(details)
+        }
+
+        pub async fn get_collection(
+            &self,
+            collection_id: &i64,
+            language: &str,
+        ) -> Result<Collection, Box<dyn std::error::Error>> {
+            let url = format!("collection/{}?{}", collection_id, encode_param("language", language));
+
+            let collection_response = self.make_tmdb_request(&url).await?;
+
+            let collection = collection_response.json::<Collection>().await?;
+
+            Ok(collection)
+        }
+
+        /* Only needed for the season count filter, which discover can't do */
+        pub async fn get_tv_by_id(&self, tv_id: &i64) -> Result<TvDetails, Box<dyn std::error::Error>> {
+            let url = format!("tv/{}", tv_id);
+
+            let tv_response = self.make_tmdb_request(&url).await?;
+
+            let details = tv_response.json::<TvDetails>().await?;
+
+            Ok(details)
+        }
+
         /* Gets watch providers by movie ID */
         /* Watch providers are given by country, and by type: */
         /* For this application we are mostly interested in "flatrate" */
@@ -106,7 +196,15 @@ cfg_if! {
             &self,
             movie_id: &String,
         ) -> Result<GetWatchProvidersResponse, Box<dyn std::error::Error>> {
-            let url = format!("movie/{}/watch/providers", movie_id);
+            self.get_watch_providers_for(&MediaType::Movie, movie_id).await
+        }
+
+        pub async fn get_watch_providers_for(
+            &self,
+            media_type: &MediaType,
+            id: &String,
+        ) -> Result<GetWatchProvidersResponse, Box<dyn std::error::Error>> {
+            let url = format!("{}/{}/watch/providers", media_type.path(), id);
 
             let provider_response = self.make_tmdb_request(&url).await?;
 
@@ -117,10 +215,72 @@ cfg_if! {
             Ok(providers)
         }
 
+        pub async fn get_trending(
+            &self,
+            window: &TrendingWindow,
+            language: &str,
+            page: u32,
+        ) -> Result<GetRecommendationsResponse, Box<dyn std::error::Error>> {
+            let params = [("language", language.to_string()), ("page", page.to_string())];
+            let url = format!("trending/movie/{}?{}", window.path(), encode(&params));
+
+            let trending_response = self.make_tmdb_request(&url).await?;
+
+            let movies = trending_response.json::<GetRecommendationsResponse>().await?;
+
+            Ok(movies)
+        }
+
+        /* In cinemas in the region right now */
+        pub async fn get_now_playing(
+            &self,
+            region: &str,
+            language: &str,
+            page: u32,
+        ) -> Result<GetRecommendationsResponse, Box<dyn std::error::Error>> {
+            let url = format!("movie/now_playing?{}", encode(&list_params(region, language, page)));
+
+            let now_playing_response = self.make_tmdb_request(&url).await?;
+
+            let movies = now_playing_response.json::<GetRecommendationsResponse>().await?;
+
+            Ok(movies)
+        }
+
+        pub async fn get_upcoming(
+            &self,
+            region: &str,
+            language: &str,
+            page: u32,
+        ) -> Result<GetRecommendationsResponse, Box<dyn std::error::Error>> {
+            let url = format!("movie/upcoming?{}", encode(&list_params(region, language, page)));
+
+            let upcoming_response = self.make_tmdb_request(&url).await?;
+
+            let movies = upcoming_response.json::<GetRecommendationsResponse>().await?;
+
+            Ok(movies)
+        }
+
+        pub async fn get_release_dates(
+            &self,
+            movie_id: &i64,
+        ) -> Result<GetReleaseDatesResponse, Box<dyn std::error::Error>> {
+            let url = format!("movie/{}/release_dates", movie_id);
+
+            let release_response = self.make_tmdb_request(&url).await?;
+
+            let release_dates = release_response.json::<GetReleaseDatesResponse>().await?;
+
+            Ok(release_dates)
+        }
+
+        /* Genre names come back in the given language, the IDs are the same in every language */
         pub async fn get_genre_list(
             &self,
+            language: &str,
         ) -> Result<GetGenresResponse, Box<dyn std::error::Error>> {
-            let url = "genre/movie/list?language=en".to_string();
+            let url = format!("genre/movie/list?{}", encode_param("language", language));
 
             let genre_response = self.make_tmdb_request(&url).await?;
 
@@ -129,6 +289,32 @@ cfg_if! {
             Ok(genres)
         }
 
+        /* Every language TMDB knows about, for picking display and original languages */
+        pub async fn get_languages(&self) -> Result<Vec<Language>, Box<dyn std::error::Error>> {
+            let url = "configuration/languages".to_string();
+
+            let language_response = self.make_tmdb_request(&url).await?;
+
+            let languages = language_response.json::<Vec<Language>>().await?;
+
+            Ok(languages)
+        }
+
+        /* Movie content ratings for every country TMDB covers */
+        pub async fn get_certifications(
+            &self,
+        ) -> Result<GetCertificationsResponse, Box<dyn std::error::Error>> {
+            let url = "certification/movie/list".to_string();
+
+            let certification_response = self.make_tmdb_request(&url).await?;
+
+            let certifications = certification_response
+                .json::<GetCertificationsResponse>()
+                .await?;
+
+            Ok(certifications)
+        }
+
         pub async fn get_providers_list(
             &self,
         ) -> Result<GetProvidersResponse, Box<dyn std::error::Error>> {
@@ -144,79 +330,50 @@ cfg_if! {
             Ok(providers)
         }
 
+        /* Movies or series depending on the query - series are converted so both come back as Movies */
         pub async fn get_recommendations(
             &self,
-            genres: Vec<i32>,
-            watch_providers: Vec<i32>,
-            runtime: Runtime,
-            decade: Decade,
-            mut feedback: Option<Feedback>,
+            query: &DiscoverQuery,
         ) -> Result<GetRecommendationsResponse, Box<dyn std::error::Error>> {
-            let genre_ids: String = genres
-                .iter()
-                .map(|g| g.to_string())
-                .collect::<Vec<_>>()
-                .join(",");
-
-            let provider_ids: String = watch_providers
-                .iter()
-                .map(|p| p.to_string())
-                .collect::<Vec<_>>()
-                .join("|");
-
-            let start_date = decade.year_range().0;
-
-            let end_date = decade.year_range().1;
-
-            let mut url = format!(
-                "discover/movie?include_adult=false&include_video=false&language=en-US&page=1&{}&{}&{}&{}&sort_by=popularity.desc&watch_region=US&{}&with_watch_monetization_types=flatrate&{}",
-                format!("primary_release_date.gte={}-01-01", start_date),
-                format!("primary_release_date.lte={}-12-31", end_date),
-                format!("with_runtime.gte={}", runtime.runtime().0),
-                format!("with_runtime.lte={}",runtime.runtime().1),
-                format!("with_genres={}", genre_ids),
-                format!("with_watch_providers={}",provider_ids)
-            );
-
-            if let Some(mut feedback) = feedback.take() {
-                if let Some(likes) = feedback.like.take() {
-                    url.push_str(&format!(
-                        "&with_keywords={}",
-                        likes
-                            .iter()
-                            .map(|k| k.to_string())
-                            .collect::<Vec<_>>()
-                            .join("|")
-                    ));
-                }
-                if let Some(dislikes) = feedback.dislike.take(){
-                    url.push_str(&format!(
-                        "&without_keywords={}",
-                        dislikes
-                            .iter()
-                            .map(|k| k.to_string())
-                            .collect::<Vec<_>>()
-                            .join("|")
-                    ));
-                }
-            }
+            let url = query.to_url();
 
             println!("{}", &url);
 
             let recommendation_response = self.make_tmdb_request(&url).await?;
 
-            let recommendations = recommendation_response
-                .json::<GetRecommendationsResponse>()
-                .await
-                .expect("Error parsing JSON");
+            match query.media_type() {
+                MediaType::Movie => Ok(recommendation_response
+                    .json::<GetRecommendationsResponse>()
+                    .await?),
+                MediaType::Tv => {
+                    let shows = recommendation_response.json::<GetTvResponse>().await?;
 
-            Ok(recommendations)
+                    Ok(GetRecommendationsResponse {
+                        results: shows.results.into_iter().map(TvShow::into_movie).collect(),
+                    })
+                }
+            }
         }
     }
+
+    /* Shared by the now playing and upcoming lists */
+    fn list_params(region: &str, language: &str, page: u32) -> Vec<(&'static str, String)> {
+        vec![
+            ("language", language.to_string()),
+            ("page", page.to_string()),
+            ("region", region.to_string()),
+        ]
+    }
+
+    fn encode_param(name: &str, value: &str) -> String {
+        encode(&[(name, value.to_string())])
+    }
+
     #[allow(dead_code)]
     #[cfg(test)]
     mod tests {
         use super::*;
+        use crate::discover::Match;
         use httpmock::prelude::*;
         use lazy_static::lazy_static;
 
@@ -369,7 +526,7 @@ cfg_if! {
 
             genre_mock.assert();
 
-            let response = tmdb.get_genre_list().await;
+            let response = tmdb.get_genre_list("en").await;
 
             assert!(response.is_err());
 
@@ -399,7 +556,7 @@ cfg_if! {
                 name: "Action".to_string(),
             };
 
-            let response = tmdb.get_genre_list().await;
+            let response = tmdb.get_genre_list("en").await;
 
             genre_mock.assert();
 
@@ -525,10 +682,20 @@ cfg_if! {
             poster_path: Some("/fSRb7vyIP8rQpL0I47P3qUsEKX3.jpg".to_string()),
             release_date: "2016-02-09".to_string(),
             title: "Deadpool".to_string(),
+            genre_ids: vec![28, 12, 35],
+            media_type: MediaType::Movie,
+            original_language: String::from("en"),
         };
 
             let response = tmdb
-                .get_recommendations(genres, watch_providers, runtime, decade, Some(feedback))
+                .get_recommendations(
+                    &DiscoverQuery::movies()
+                        .decade(&decade)
+                        .runtime(runtime.runtime())
+                        .genres(genres, Match::All)
+                        .providers(watch_providers)
+                        .feedback(Some(feedback)),
+                )
                 .await;
 
             rec_mock.assert();
@@ -541,6 +708,339 @@ cfg_if! {
 
             assert!(response.results.iter().any(|m| m == &movie));
         }
+
+        #[tokio::test]
+        async fn test_get_tv_recommendations() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb {
+                base_url: MOCK_TMDB_VALID.base_url(),
+                api_key: api_key.clone(),
+            };
+
+            let tv_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET)
+                    .path("/discover/tv")
+                    .query_param("language", "ko-KR")
+                    .query_param("with_original_language", "ko|ja")
+                    .query_param("first_air_date.gte", "2010-01-01")
+                    .query_param("first_air_date.lte", "2019-12-31")
+                    .query_param("with_runtime.gte", "30")
+                    .query_param("with_runtime.lte", "60")
+                    .query_param("sort_by", "first_air_date.desc")
+                    .query_param("with_genres", "10759|35")
+                    .query_param("with_watch_providers", "8")
+                    .header("Authorization", format!("Bearer {}", &api_key));
+                then.status(200).body(
+                    r#"{"page":1,"results":[{"id":1396,"name":"Breaking Bad","overview":"A chemistry teacher","poster_path":null,"first_air_date":"2008-01-20","genre_ids":[18,80]}]}"#,
+                );
+            });
+
+            let response = tmdb
+                .get_recommendations(
+                    &DiscoverQuery::tv()
+                        .decade(&Decade::TwentyTens)
+                        .runtime(EpisodeLength::Standard.runtime())
+                        .genres(vec![10759, 35], Match::Any)
+                        .providers(vec![8])
+                        .sort_by(SortBy::Newest)
+                        .languages(&LanguageFilter {
+                            display: Some(String::from("ko-KR")),
+                            include: vec![String::from("ko"), String::from("ja")],
+                            exclude: vec![],
+                        }),
+                )
+                .await
+                .unwrap();
+
+            tv_mock.assert();
+
+            let show = &response.results[0];
+            assert_eq!(show.title, "Breaking Bad");
+            assert_eq!(show.release_date, "2008-01-20");
+            assert_eq!(show.media_type, MediaType::Tv);
+        }
+
+        #[tokio::test]
+        async fn test_tv_keywords() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb {
+                base_url: MOCK_TMDB_VALID.base_url(),
+                api_key: api_key.clone(),
+            };
+
+            let keyword_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET).path("/tv/1396/keywords");
+                then.status(200)
+                    .body(r#"{"id":1396,"results":[{"id":1,"name":"drug dealer"}]}"#);
+            });
+
+            let response = tmdb.get_keywords_for(&MediaType::Tv, &1396).await.unwrap();
+
+            keyword_mock.assert();
+
+            assert_eq!(response.keywords.len(), 1);
+        }
+
+        #[tokio::test]
+        async fn test_discover_well_rated() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb {
+                base_url: MOCK_TMDB_VALID.base_url(),
+                api_key: api_key.clone(),
+            };
+
+            let rec_response = get_json_from_file("recommendations_response");
+
+            let well_rated_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET)
+                    .path("/discover/movie")
+                    .query_param("page", "3")
+                    .query_param("primary_release_date.gte", "1990-01-01")
+                    .query_param("primary_release_date.lte", "1999-12-31")
+                    .query_param("sort_by", "vote_average.desc")
+                    .query_param("vote_average.gte", "7")
+                    .query_param("with_genres", "80")
+                    .query_param("with_watch_providers", "8|337")
+                    .query_param("certification_country", "GB")
+                    .query_param("certification.lte", "12A")
+                    .header("Authorization", format!("Bearer {}", &api_key));
+                then.status(200).body(rec_response);
+            });
+
+            let limit = CertificationLimit {
+                country: String::from("GB"),
+                max: String::from("12A"),
+            };
+
+            let response = tmdb
+                .get_recommendations(
+                    &DiscoverQuery::movies()
+                        .page(3)
+                        .decade(&Decade::Nineties)
+                        .genres(vec![80], Match::All)
+                        .providers(vec![8, 337])
+                        .certification(Some(&limit))
+                        .min_rating(7.0)
+                        .min_votes(300)
+                        .sort_by(SortBy::Rating),
+                )
+                .await;
+
+            well_rated_mock.assert();
+
+            assert!(!response.unwrap().results.is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_search_people() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb {
+                base_url: MOCK_TMDB_VALID.base_url(),
+                api_key: api_key.clone(),
+            };
+
+            let search_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET)
+                    .path("/search/person")
+                    .query_param("query", "Florence Pugh & co")
+                    .header("Authorization", format!("Bearer {}", &api_key));
+                then.status(200).body(
+                    r#"{"page":1,"results":[{"id":1373737,"name":"Florence Pugh","known_for_department":"Acting","profile_path":null}]}"#,
+                );
+            });
+
+            let response = tmdb.search_people("Florence Pugh & co").await.unwrap();
+
+            search_mock.assert();
+
+            assert_eq!(response.results[0].id, 1373737);
+            assert_eq!(response.results[0].known_for_department, Some(String::from("Acting")));
+        }
+
+        #[tokio::test]
+        async fn test_recommendations_with_credits() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb {
+                base_url: MOCK_TMDB_VALID.base_url(),
+                api_key: api_key.clone(),
+            };
+
+            let rec_response = get_json_from_file("recommendations_response");
+
+            let rec_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET)
+                    .path("/discover/movie")
+                    .query_param("with_cast", "1373737")
+                    .query_param("with_crew", "137427")
+                    .query_param("with_companies", "41077|3")
+                    .header("Authorization", format!("Bearer {}", &api_key));
+                then.status(200).body(rec_response);
+            });
+
+            let credits = CreditFilter {
+                cast: vec![1373737],
+                crew: vec![137427],
+                companies: vec![41077, 3],
+            };
+
+            let response = tmdb
+                .get_recommendations(
+                    &DiscoverQuery::movies()
+                        .genres(vec![18], Match::All)
+                        .providers(vec![8])
+                        .credits(&credits),
+                )
+                .await;
+
+            rec_mock.assert();
+
+            assert!(response.is_ok());
+        }
+
+        #[tokio::test]
+        async fn test_trending() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb {
+                base_url: MOCK_TMDB_VALID.base_url(),
+                api_key: api_key.clone(),
+            };
+
+            let rec_response = get_json_from_file("recommendations_response");
+
+            let trending_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET)
+                    .path("/trending/movie/week")
+                    .query_param("language", "en-US")
+                    .query_param("page", "2")
+                    .header("Authorization", format!("Bearer {}", &api_key));
+                then.status(200).body(rec_response);
+            });
+
+            let response = tmdb.get_trending(&TrendingWindow::Week, "en-US", 2).await.unwrap();
+
+            trending_mock.assert();
+
+            assert!(!response.results.is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_upcoming_params_are_encoded() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb {
+                base_url: MOCK_TMDB_VALID.base_url(),
+                api_key: api_key.clone(),
+            };
+
+            let rec_response = get_json_from_file("recommendations_response");
+
+            // The whole value arrives as the language, rather than overriding the region
+            let upcoming_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET)
+                    .path("/movie/upcoming")
+                    .query_param("language", "en&region=GB")
+                    .query_param("region", "US")
+                    .header("Authorization", format!("Bearer {}", &api_key));
+                then.status(200).body(rec_response);
+            });
+
+            let response = tmdb.get_upcoming("US", "en&region=GB", 1).await;
+
+            upcoming_mock.assert();
+
+            assert!(response.is_ok());
+        }
+
+        #[tokio::test]
+        async fn test_release_certification() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb {
+                base_url: MOCK_TMDB_VALID.base_url(),
+                api_key: api_key.clone(),
+            };
+
+            let release_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET)
+                    .path("/movie/293660/release_dates")
+                    .header("Authorization", format!("Bearer {}", &api_key));
+                then.status(200).body(
+                    r#"{"id":293660,"results":[{"iso_3166_1":"GB","release_dates":[{"certification":"15","type":3}]},{"iso_3166_1":"US","release_dates":[{"certification":"","type":1},{"certification":"R","type":3}]}]}"#,
+                );
+            });
+
+            let response = tmdb.get_release_dates(&293660).await.unwrap();
+
+            release_mock.assert();
+
+            assert_eq!(response.certification("US"), Some(String::from("R")));
+            assert_eq!(response.certification("GB"), Some(String::from("15")));
+            assert_eq!(response.certification("DE"), None);
+        }
+
+        #[tokio::test]
+        async fn test_collection() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb {
+                base_url: MOCK_TMDB_VALID.base_url(),
+                api_key: api_key.clone(),
+            };
+
+            let details_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET)
+                    .path("/movie/693134")
+                    .header("Authorization", format!("Bearer {}", &api_key));
+                then.status(200).body(
+                    r#"{"id":693134,"title":"Dune: Part Two","overview":"Paul Atreides unites with the Fremen","poster_path":null,"release_date":"2024-02-27","genres":[{"id":878,"name":"Science Fiction"}],"belongs_to_collection":{"id":726871,"name":"Dune Collection","poster_path":null}}"#,
+                );
+            });
+
+            let collection_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET)
+                    .path("/collection/726871")
+                    .query_param("language", "en-US")
+                    .header("Authorization", format!("Bearer {}", &api_key));
+                then.status(200).body(
+                    r#"{"id":726871,"name":"Dune Collection","overview":"","poster_path":null,"parts":[{"id":693134,"title":"Dune: Part Two","overview":"","poster_path":null,"release_date":"2024-02-27","genre_ids":[878]},{"id":438631,"title":"Dune","overview":"","poster_path":null,"release_date":"2021-09-15","genre_ids":[878]}]}"#,
+                );
+            });
+
+            let details = tmdb.get_movie_details(&693134).await.unwrap();
+
+            details_mock.assert();
+
+            let summary = details.belongs_to_collection.unwrap();
+            assert_eq!(details.movie.title, "Dune: Part Two");
+
+            let collection = tmdb.get_collection(&summary.id, "en-US").await.unwrap();
+
+            collection_mock.assert();
+
+            assert_eq!(collection.position(693134).unwrap().describe(), "Part 2 of 2 in Dune Collection");
+        }
+
+        #[tokio::test]
+        async fn test_certifications() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb {
+                base_url: MOCK_TMDB_VALID.base_url(),
+                api_key: api_key.clone(),
+            };
+
+            let certification_mock = MOCK_TMDB_VALID.mock(|when, then| {
+                when.method(GET)
+                    .path("/certification/movie/list")
+                    .header("Authorization", format!("Bearer {}", &api_key));
+                then.status(200).body(
+                    r#"{"certifications":{"US":[{"certification":"PG","meaning":"Some material may not be suitable for children.","order":2},{"certification":"G","meaning":"All ages admitted.","order":1}]}}"#,
+                );
+            });
+
+            let response = tmdb.get_certifications().await.unwrap();
+
+            certification_mock.assert();
+
+            assert_eq!(response.certifications["US"].len(), 2);
+            assert_eq!(response.certifications["US"][1].certification, "G");
+        }
     }
     }
 }
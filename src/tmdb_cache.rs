@@ -0,0 +1,76 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use crate::session_store::{load_json, save_json, SessionStore};
+    use crate::tmdb::Tmdb;
+    use crate::*;
+
+    const GENRES_KEY_PREFIX: &str = "tmdb_cache:genres:";
+    const PROVIDERS_KEY: &str = "tmdb_cache:providers";
+
+    // The warming job refreshes these well before they expire - the TTL only matters if it stops running
+    const CACHE_TTL_SECONDS: u64 = 60 * 60 * 24;
+
+    /* Lists the wizard asks for on every visit, kept in the session store so every instance shares them */
+    /* A cache miss (or a broken cache) just goes to TMDB */
+    pub async fn genres(store: &dyn SessionStore, tmdb: &Tmdb, language: &str) -> Result<Vec<Genre>, String> {
+        let key = format!("{}{}", GENRES_KEY_PREFIX, language);
+
+        match load_json::<Vec<Genre>>(store, &key).await {
+            Ok(Some(genres)) => return Ok(genres),
+            Ok(None) => {}
+            Err(err) => println!("Error reading cached genres: {}", err),
+        }
+
+        fetch_genres(store, tmdb, language).await
+    }
+
+    pub async fn providers(store: &dyn SessionStore, tmdb: &Tmdb) -> Result<Vec<WatchProvider>, String> {
+        match load_json::<Vec<WatchProvider>>(store, PROVIDERS_KEY).await {
+            Ok(Some(providers)) => return Ok(providers),
+            Ok(None) => {}
+            Err(err) => println!("Error reading cached providers: {}", err),
+        }
+
+        fetch_providers(store, tmdb).await
+    }
+
+    /* Refreshes the default language's genres and the provider list */
+    pub async fn warm(store: &dyn SessionStore, tmdb: &Tmdb) -> Result<String, String> {
+        let genres = fetch_genres(store, tmdb, DEFAULT_LANGUAGE).await?;
+        let providers = fetch_providers(store, tmdb).await?;
+
+        Ok(format!("Cached {} genres and {} providers", genres.len(), providers.len()))
+    }
+
+    async fn fetch_genres(store: &dyn SessionStore, tmdb: &Tmdb, language: &str) -> Result<Vec<Genre>, String> {
+        let genres = tmdb
+            .get_genre_list(language)
+            .await
+            .map_err(|err| err.to_string())?
+            .genres;
+
+        let key = format!("{}{}", GENRES_KEY_PREFIX, language);
+        if let Err(err) = save_json(store, &key, &genres, Some(CACHE_TTL_SECONDS)).await {
+            println!("Error caching genres: {}", err);
+        }
+
+        Ok(genres)
+    }
+
+    async fn fetch_providers(store: &dyn SessionStore, tmdb: &Tmdb) -> Result<Vec<WatchProvider>, String> {
+        let providers = tmdb
+            .get_providers_list()
+            .await
+            .map_err(|err| err.to_string())?
+            .results;
+
+        if let Err(err) = save_json(store, PROVIDERS_KEY, &providers, Some(CACHE_TTL_SECONDS)).await {
+            println!("Error caching providers: {}", err);
+        }
+
+        Ok(providers)
+    }
+}
+}
@@ -4,6 +4,11 @@ use cfg_if::cfg_if;
 
 const NUM_RESULTS: u8 = 5;
 
+// Recommendations shown per batch
+const RECOMMENDATION_LIMIT: usize = 10;
+// Most pages read past the first when excluded languages are filtering results out
+const LANGUAGE_EXCLUDE_EXTRA_PAGES: u32 = 4;
+
 cfg_if! {
     if #[cfg(feature = "ssr")] {
     use std::{collections::HashMap, sync::Arc};
@@ -11,50 +16,310 @@ cfg_if! {
     use httpmock::{prelude::*, Mock};
     use lazy_static::lazy_static;
 
+    use crate::accounts::DataOwner;
+    use crate::collections;
+    use crate::discover::{DiscoverQuery, Match};
+    use crate::explanations;
+    use crate::history::{self, MAX_RATING, MIN_RATING};
+    use crate::keyword_model::{RatedKeywords, DISCOVER_KEYWORD_LIMIT};
+    use crate::session_store::SessionStore;
     use crate::tmdb::{AsyncRecommendation, Tmdb};
     use crate::*;
 
+    /* Discover and the list endpoints can't exclude original languages - with exclusions set, pages are read until enough are left */
+    pub async fn fetch_allowed<F, Fut>(
+        languages: &LanguageFilter,
+        first_page: u32,
+        wanted: usize,
+        fetch_page: F,
+    ) -> Result<Vec<Movie>, Box<dyn std::error::Error>>
+    where
+        F: Fn(u32) -> Fut,
+        Fut: std::future::Future<Output = Result<Vec<Movie>, Box<dyn std::error::Error>>>,
+    {
+        let last_page = match languages.exclude.is_empty() {
+            true => first_page,
+            false => first_page + LANGUAGE_EXCLUDE_EXTRA_PAGES,
+        };
+
+        let mut allowed = vec![];
+        for page in first_page..=last_page {
+            let movies = fetch_page(page).await?;
+            // An empty page means we're past the last one
+            if movies.is_empty() {
+                break;
+            }
+
+            allowed.extend(
+                movies
+                    .into_iter()
+                    .filter(|movie| languages.allows(&movie.original_language)),
+            );
+            if allowed.len() >= wanted {
+                break;
+            }
+        }
+
+        Ok(allowed)
+    }
+
     pub async fn get_recommendations_for_session(
         tmdb: Arc<Tmdb>,
+        store: &dyn SessionStore,
         session_id: String,
+        exclude: &[(MediaType, i64)],
+        page: u32,
     ) -> Result<Vec<AsyncRecommendation>, Box<dyn std::error::Error>> {
-        let criteria = redis_helper::criteria_from_cache(&session_id).await?;
-
-        let recommendations = tmdb
-            .get_recommendations(
-                criteria.genres.expect("No genres for ID"),
-                criteria.watch_providers.expect("No watch providers for ID"),
-                criteria.runtime.expect("No runtime for ID"),
-                criteria.decade.expect("No decade for ID"),
-                criteria.feedback,
-            )
-            .await?;
+        let criteria = store.load(&session_id).await?;
+
+        let media = criteria.media.clone().unwrap_or_default();
+        let genres = criteria.genres.clone().expect("No genres for ID");
+        let watch_providers = criteria.watch_providers.clone().expect("No watch providers for ID");
+        let decade = criteria.decade.clone().expect("No decade for ID");
+
+        let feedback = criteria.keyword_profile.to_feedback(DISCOVER_KEYWORD_LIMIT);
+        let sort_by = criteria.sort_by.clone().unwrap_or_default();
+
+        // What movies and series have in common
+        let shared = |query: DiscoverQuery| {
+            query
+                .decade(&decade)
+                .providers(watch_providers.clone())
+                .feedback(feedback.clone())
+                .sort_by(sort_by.clone())
+                .languages(&criteria.languages)
+        };
+
+        let movies = match media.includes_movies() {
+            true => {
+                let movie_query = shared(DiscoverQuery::movies())
+                    .runtime(criteria.runtime.clone().expect("No runtime for ID").runtime())
+                    .genres(genres.clone(), Match::All)
+                    .certification(criteria.certification_limit().as_ref())
+                    .credits(&criteria.credits);
+                fetch_allowed(&criteria.languages, page, RECOMMENDATION_LIMIT, |page| {
+                    let query = movie_query.clone().page(page);
+                    let tmdb = Arc::clone(&tmdb);
+                    async move { Ok(tmdb.get_recommendations(&query).await?.results) }
+                })
+                .await?
+            }
+            false => vec![],
+        };
+
+        // discover/tv can't filter by content rating or credits, so series are left out rather than ignore them
+        let shows = match media.includes_series() && !criteria.family_mode && criteria.credits.is_empty() {
+            true => {
+                // TV genres are coarser than movie genres, so any one of them matching is enough
+                let tv_query = shared(DiscoverQuery::tv())
+                    .runtime(criteria.episode_length.clone().unwrap_or_default().runtime())
+                    .genres(tv_genre_ids(&genres), Match::Any);
+                let shows = fetch_allowed(&criteria.languages, page, RECOMMENDATION_LIMIT, |page| {
+                    let query = tv_query.clone().page(page);
+                    let tmdb = Arc::clone(&tmdb);
+                    async move { Ok(tmdb.get_recommendations(&query).await?.results) }
+                })
+                .await?;
+                filter_by_seasons(&tmdb, shows, criteria.seasons.as_ref()).await
+            }
+            false => vec![],
+        };
+
+        let owner = DataOwner::for_session(store, &session_id).await?;
+        let watch_history = history::load_history(store, &owner).await?;
+
         let mut index = 1;
 
         let mut movie_recommendations = vec![];
-
-        for movie in recommendations.results {
-            if index > 10 {
+        let mut keyword_handles = vec![];
+
+        // Discover has no way to exclude specific movies, so those are dropped here
+        let candidates: Vec<Movie> = interleave(movies, shows)
+            .into_iter()
+            .filter(|movie| !watch_history.contains(movie.id, movie.media_type) && !exclude.contains(&(movie.media_type, movie.id)))
+            .collect();
+
+        let collections = collections::collections_for(
+            &tmdb,
+            &candidates,
+            &criteria.languages.display_language(),
+        )
+        .await;
+        // Only movies belong to collections
+        let watched = |movie_id: i64| watch_history.contains(movie_id, MediaType::Movie);
+
+        for (movie, collection) in collections::arrange(
+            candidates,
+            &collections,
+            &watched,
+            criteria.skip_unseen_sequels,
+        ) {
+            if index > RECOMMENDATION_LIMIT {
                 break;
             }
             let temp_tmdb = Arc::clone(&tmdb);
+            let media_type = movie.media_type;
+            let movie_id = movie.id.to_string();
             let handle = tokio::spawn(async move {
-                let movie_id = movie.id.to_string();
-                temp_tmdb
-                    .get_watch_providers_by_id(&movie_id)
-                    .await
-                    .expect("Unable to call tmdb")
+                match temp_tmdb.get_watch_providers_for(&media_type, &movie_id).await {
+                    Ok(providers) => Some(providers),
+                    Err(err) => {
+                        println!("Error fetching watch providers for {}: {}", movie_id, err);
+                        None
+                    }
+                }
             });
+            // Keywords are only needed to explain the recommendation, so a failed lookup just means a shorter explanation
+            let temp_tmdb = Arc::clone(&tmdb);
+            let movie_id = movie.id;
+            keyword_handles.push(tokio::spawn(async move {
+                match temp_tmdb.get_keywords_for(&media_type, &movie_id).await {
+                    Ok(keyword_response) => keyword_response.keywords,
+                    Err(_) => vec![],
+                }
+            }));
             movie_recommendations.push(AsyncRecommendation {
                 movie,
                 async_providers: handle,
+                reasons: RecommendationReasons::default(),
+                collection,
             });
             index += 1;
         }
 
+        let genre_names: HashMap<i32, String> = match tmdb.get_genre_list(&criteria.languages.display_language()).await {
+            Ok(genre_list) => genre_list
+                .genres
+                .into_iter()
+                .map(|genre| (genre.id, genre.name))
+                .collect(),
+            Err(_) => HashMap::new(),
+        };
+
+        let mut movie_keywords: Vec<Vec<Keyword>> = vec![];
+        for handle in keyword_handles {
+            movie_keywords.push(handle.await.unwrap_or_default());
+        }
+
+        let liked_titles = get_liked_titles(&tmdb, &criteria.keyword_profile, &movie_keywords).await;
+
+        for (recommendation, keywords) in movie_recommendations.iter_mut().zip(&movie_keywords) {
+            recommendation.reasons = explanations::explain(
+                &recommendation.movie,
+                keywords,
+                &criteria,
+                &genre_names,
+                &liked_titles,
+            );
+        }
+
         Ok(movie_recommendations)
     }
 
+    /* TV has its own genre list - the combined genres (Action & Adventure, Sci-Fi & Fantasy, War & Politics) take the place of their movie halves */
+    pub fn tv_genre_ids(movie_genres: &[i32]) -> Vec<i32> {
+        let mut tv_genres: Vec<i32> = vec![];
+
+        for genre in movie_genres {
+            let tv_genre = match genre {
+                28 | 12 => Some(10759),
+                878 | 14 => Some(10765),
+                10752 => Some(10768),
+                16 | 35 | 80 | 99 | 18 | 10751 | 9648 | 37 => Some(*genre),
+                // Horror, Thriller, Romance, History and Music have no TV equivalent
+                _ => None,
+            };
+
+            if let Some(tv_genre) = tv_genre {
+                if !tv_genres.contains(&tv_genre) {
+                    tv_genres.push(tv_genre);
+                }
+            }
+        }
+
+        tv_genres
+    }
+
+    /* Alternates movies and series so "both" doesn't mean a page of one then the other */
+    pub fn interleave(movies: Vec<Movie>, shows: Vec<Movie>) -> Vec<Movie> {
+        let mut movies = movies.into_iter();
+        let mut shows = shows.into_iter();
+        let mut combined = vec![];
+
+        loop {
+            match (movies.next(), shows.next()) {
+                (None, None) => break,
+                (movie, show) => combined.extend(movie.into_iter().chain(show)),
+            }
+        }
+
+        combined
+    }
+
+    /* Shows whose details can't be fetched are dropped - there's no telling whether they'd match */
+    async fn filter_by_seasons(tmdb: &Arc<Tmdb>, shows: Vec<Movie>, seasons: Option<&SeasonCount>) -> Vec<Movie> {
+        let seasons = match seasons {
+            Some(seasons) => seasons.clone(),
+            None => return shows,
+        };
+
+        let mut handles = vec![];
+        for show in shows {
+            let temp_tmdb = Arc::clone(tmdb);
+            handles.push(tokio::spawn(async move {
+                let details = temp_tmdb.get_tv_by_id(&show.id).await.ok();
+                (show, details)
+            }));
+        }
+
+        let mut matching = vec![];
+        for handle in handles {
+            if let Ok((show, Some(details))) = handle.await {
+                if seasons.matches(details.number_of_seasons) {
+                    matching.push(show);
+                }
+            }
+        }
+
+        matching
+    }
+
+    /* Titles for the liked movies behind any matched keywords - feedback only ever records movie IDs */
+    async fn get_liked_titles(
+        tmdb: &Arc<Tmdb>,
+        profile: &KeywordProfile,
+        movie_keywords: &[Vec<Keyword>],
+    ) -> HashMap<i64, String> {
+        let mut source_ids: Vec<i64> = vec![];
+        for keywords in movie_keywords {
+            for id in explanations::liked_sources(profile, keywords) {
+                if !source_ids.contains(&id) {
+                    source_ids.push(id);
+                }
+            }
+        }
+
+        let mut handles = vec![];
+        for id in source_ids {
+            let temp_tmdb = Arc::clone(tmdb);
+            handles.push(tokio::spawn(async move {
+                match temp_tmdb.get_movie_by_id(&id).await {
+                    Ok(movie) => Some((movie.id, movie.title)),
+                    Err(_) => None,
+                }
+            }));
+        }
+
+        let mut titles: HashMap<i64, String> = HashMap::new();
+        for handle in handles {
+            if let Ok(Some((id, title))) = handle.await {
+                titles.insert(id, title);
+            }
+        }
+
+        titles
+    }
+
     pub async fn get_movies_from_title(
         movie_title: String,
         tmdb: Arc<Tmdb>,
@@ -80,14 +345,18 @@ cfg_if! {
         keyword_future: tokio::task::JoinHandle<KeywordResponse>,
     }
 
-    async fn get_keyword_futures(tmdb: &Arc<Tmdb>, id_list: Vec<i64>) -> Vec<AsyncFeedback> {
+    async fn get_keyword_futures(
+        tmdb: &Arc<Tmdb>,
+        media_type: MediaType,
+        id_list: Vec<i64>,
+    ) -> Vec<AsyncFeedback> {
         let mut futures: Vec<AsyncFeedback> = vec![];
 
         for id in id_list {
             let temp_tmdb = Arc::clone(&tmdb);
             let handle = tokio::spawn(async move {
                 temp_tmdb
-                    .get_keywords_for_id(&id)
+                    .get_keywords_for(&media_type, &id)
                     .await
                     .expect("Unable to call tmdb")
             });
@@ -113,82 +382,51 @@ cfg_if! {
         keywords_list
     }
 
-    async fn get_keyword_votes(keywords: Vec<Keyword>) -> HashMap<i64, i16> {
-        let mut votes: HashMap<i64, i16> = HashMap::new();
-        for keyword in keywords {
-            if votes.contains_key(&keyword.id) {
-                votes.insert(keyword.id, votes.get(&keyword.id).unwrap() + 1);
-            } else {
-                votes.insert(keyword.id, 1);
-            }
-        }
-
-        votes
-    }
-
-    async fn refine_keywords(
-        mut upvotes: HashMap<i64, i16>,
-        mut downvotes: HashMap<i64, i16>,
-    ) -> (HashMap<i64, i16>, HashMap<i64, i16>) {
-        let mut remove_upvotes: Vec<i64> = vec![];
-
-        for (id, count) in &mut upvotes {
-            if downvotes.contains_key(&id) {
-                if downvotes.get(&id).unwrap() < count {
-                    downvotes.remove(&id);
-                } else {
-                    // Can't modify a collection we're iterating over
-                    remove_upvotes.push(*id);
-                }
-            }
-        }
-
-        for id in remove_upvotes {
-            upvotes.remove(&id);
-        }
-
-        (upvotes, downvotes)
-    }
-
+    /* A plain like/dislike counts the same as the strongest rating either way */
     pub async fn process_feedback(
         tmdb: Arc<Tmdb>,
+        media_type: MediaType,
         thumbs_up_ids: Vec<i64>,
         thumbs_down_ids: Vec<i64>,
-    ) -> (Vec<i64>, Vec<i64>) {
-        let thumbs_up_future = get_keyword_futures(&tmdb, thumbs_up_ids);
-        let thumbs_down_future = get_keyword_futures(&tmdb, thumbs_down_ids);
-
-        let thumbs_up_keywords = get_keyword_list(thumbs_up_future.await);
-        let thumbs_down_keywords = get_keyword_list(thumbs_down_future.await);
-
-        let thumbs_up_votes = get_keyword_votes(thumbs_up_keywords.await).await;
-        let thumbs_down_votes = get_keyword_votes(thumbs_down_keywords.await).await;
-
-        let (refined_upvotes, refined_downvotes) =
-            refine_keywords(thumbs_up_votes, thumbs_down_votes).await;
-
-        let mut sorted_up_votes: Vec<_> = refined_upvotes.iter().collect();
-
-        sorted_up_votes.sort_by_key(|&(_, count)| std::cmp::Reverse(*count));
-
-        let mut sorted_down_votes: Vec<_> = refined_downvotes.iter().collect();
-
-        sorted_down_votes.sort_by_key(|&(_, count)| std::cmp::Reverse(*count));
-
-        let mut criteria_upvotes: Vec<i64> = vec![];
-        let mut criteria_downvotes: Vec<i64> = vec![];
-
-        println!("Upvotes: ");
-        for (&id, &count) in sorted_up_votes.iter().take(5) {
-            criteria_upvotes.push(id);
-        }
+    ) -> Vec<RatedKeywords> {
+        let ratings: Vec<(i64, u8)> = thumbs_up_ids
+            .into_iter()
+            .map(|id| (id, MAX_RATING))
+            .chain(thumbs_down_ids.into_iter().map(|id| (id, MIN_RATING)))
+            .collect();
+
+        process_rated_feedback(tmdb, media_type, ratings).await
+    }
 
-        println!("Downvotes: ");
-        for (&id, &count) in sorted_down_votes.iter().take(5) {
-            criteria_downvotes.push(id)
+    /* Takes (ID, 1-5 rating) pairs and looks up each one's keywords - neutral ratings carry no signal and are skipped */
+    pub async fn process_rated_feedback(
+        tmdb: Arc<Tmdb>,
+        media_type: MediaType,
+        ratings: Vec<(i64, u8)>,
+    ) -> Vec<RatedKeywords> {
+        let weights: HashMap<i64, i16> = ratings
+            .into_iter()
+            .map(|(id, rating)| (id, history::rating_weight(rating)))
+            .filter(|(_, weight)| *weight != 0)
+            .collect();
+
+        let keyword_futures =
+            get_keyword_futures(&tmdb, media_type, weights.keys().copied().collect()).await;
+
+        let mut rated_keywords: Vec<RatedKeywords> = vec![];
+
+        for keyword_future in keyword_futures {
+            let movie_id = keyword_future.movie_id;
+            let keywords = get_keyword_list(vec![keyword_future]).await;
+            rated_keywords.push(RatedKeywords {
+                movie_id,
+                media_type,
+                keyword_ids: keywords.into_iter().map(|keyword| keyword.id).collect(),
+                weight: weights[&movie_id] as f64,
+            });
         }
 
-        (criteria_upvotes, criteria_downvotes)
+        rated_keywords
     }
 
     #[cfg(test)]
@@ -197,6 +435,7 @@ cfg_if! {
 
         #[cfg(feature = "ssr")]
         use super::*;
+        use crate::session_store::MemorySessionStore;
 
         lazy_static! {
             static ref MOCK_TMDB_VALID: MockServer = MockServer::start();
@@ -209,7 +448,7 @@ cfg_if! {
                 watch_providers: Some(vec![8]),
                 runtime: Some(Runtime::from_string("Average")),
                 decade: Some(Decade::from_string("Recent")),
-                feedback: None,
+                ..Default::default()
             }
         }
 
@@ -251,11 +490,9 @@ cfg_if! {
                 downvote_map.insert(id.clone(), build_mock_endpoint(id, &api_key).await);
             }
 
-            let (mut criteria_upvotes, mut criteria_downvotes) =
-                process_feedback(tmdb, thumbs_up_ids, thumbs_down_ids).await;
+            let mut rated_keywords = process_feedback(tmdb, MediaType::Movie, thumbs_up_ids, thumbs_down_ids).await;
 
-            criteria_upvotes.sort();
-            criteria_downvotes.sort();
+            rated_keywords.sort_by_key(|rated| rated.movie_id);
 
             for (id, mock_endpoint) in upvote_map {
                 mock_endpoint.assert();
@@ -264,86 +501,51 @@ cfg_if! {
                 mock_endpoint.assert();
             }
 
-            assert!(!criteria_upvotes.is_empty());
-            assert!(!criteria_downvotes.is_empty());
+            assert_eq!(rated_keywords.len(), 6);
 
-            let expected_upvotes = vec![3210, 5678, 6523];
-            let expected_downvotes = vec![1111, 8888, 9999, 91011];
+            for rated in &rated_keywords {
+                assert!(!rated.keyword_ids.is_empty());
+                match rated.movie_id {
+                    123 | 456 | 789 => assert_eq!(rated.weight, 2.0),
+                    _ => assert_eq!(rated.weight, -2.0),
+                }
+            }
 
-            assert_eq!(criteria_upvotes, expected_upvotes);
-            assert_eq!(criteria_downvotes, expected_downvotes);
+            assert_eq!(rated_keywords[0].movie_id, 123);
+            assert_eq!(rated_keywords[0].keyword_ids, vec![8888, 5678, 9999]);
         }
 
         #[tokio::test]
-        async fn test_keyword_refinement() {
-            let mut upvotes: HashMap<i64, i16> = HashMap::new();
-            let mut downvotes: HashMap<i64, i16> = HashMap::new();
-
-            upvotes.insert(4444, 4);
-            upvotes.insert(3333, 3);
-            upvotes.insert(2222, 2);
-            upvotes.insert(1111, 1);
-
-            downvotes.insert(4444, 4);
-            downvotes.insert(2222, 1);
-            downvotes.insert(1111, 3);
-            downvotes.insert(3333, 2);
-
-            let (refined_upvotes, refined_downvotes) = refine_keywords(upvotes, downvotes).await;
-
-            assert!(!refined_upvotes.is_empty());
-            assert!(!refined_downvotes.is_empty());
-
-            // Upvotes should have 2222 and 3333
-            assert!(!refined_upvotes.contains_key(&4444));
-            assert!(refined_upvotes.contains_key(&3333));
-            assert_eq!(refined_upvotes.get(&3333).unwrap(), &3i16);
-            assert!(refined_upvotes.contains_key(&2222));
-            assert_eq!(refined_upvotes.get(&2222).unwrap(), &2i16);
-            assert!(!refined_upvotes.contains_key(&1111));
-
-            // Downvotes should have 4444 and 1111
-            assert!(refined_downvotes.contains_key(&4444));
-            assert_eq!(refined_downvotes.get(&4444).unwrap(), &4i16);
-            assert!(!refined_downvotes.contains_key(&2222));
-            assert!(refined_downvotes.contains_key(&1111));
-            assert_eq!(refined_downvotes.get(&1111).unwrap(), &3i16);
-            assert!(!refined_downvotes.contains_key(&3333));
-        }
+        async fn test_rated_keyword_process() {
+            let api_key = String::from("supersecret");
+            let tmdb = Tmdb::mock_shared_instance(api_key.clone(), MOCK_TMDB_VALID.base_url());
 
-        #[tokio::test]
-        async fn test_keyword_votes() {
-            let keywords = vec![
-                Keyword {
-                    id: 111,
-                    name: "testing".to_string(),
-                },
-                Keyword {
-                    id: 111,
-                    name: "testing".to_string(),
-                },
-                Keyword {
-                    id: 111,
-                    name: "testing".to_string(),
-                },
-                Keyword {
-                    id: 222,
-                    name: "foo".to_string(),
-                },
-                Keyword {
-                    id: 222,
-                    name: "foo".to_string(),
-                },
-                Keyword {
-                    id: 333,
-                    name: "bar".to_string(),
-                },
-            ];
-            let keyword_map: HashMap<i64, i16> = get_keyword_votes(keywords).await;
-
-            assert_eq!(keyword_map.get(&111).unwrap(), &3i16);
-            assert_eq!(keyword_map.get(&222).unwrap(), &2i16);
-            assert_eq!(keyword_map.get(&333).unwrap(), &1i16);
+            build_mock_endpoint(&123, &api_key).await;
+            build_mock_endpoint(&321, &api_key).await;
+
+            let mut rated_keywords =
+                process_rated_feedback(tmdb, MediaType::Movie, vec![(123, 5), (321, 2), (456, 3)]).await;
+
+            rated_keywords.sort_by_key(|rated| rated.movie_id);
+
+            // The neutral rating for 456 is skipped entirely
+            assert_eq!(
+                rated_keywords,
+                vec![
+                    RatedKeywords {
+                        movie_id: 123,
+                        media_type: MediaType::Movie,
+                        keyword_ids: vec![8888, 5678, 9999],
+                        weight: 2.0,
+                    },
+                    RatedKeywords {
+                        movie_id: 321,
+                        media_type: MediaType::Movie,
+                        keyword_ids: vec![1111, 8888],
+                        weight: -1.0,
+                    },
+                ]
+            );
         }
 
         #[tokio::test]
@@ -353,7 +555,7 @@ cfg_if! {
             let movie_ids = vec![82702, 62177];
 
             let future_response: Vec<AsyncFeedback> =
-                get_keyword_futures(&tmdb, movie_ids.clone()).await;
+                get_keyword_futures(&tmdb, MediaType::Movie, movie_ids.clone()).await;
 
             assert!(!future_response.is_empty());
 
@@ -368,7 +570,7 @@ cfg_if! {
 
             let movie_ids = vec![82702, 62177];
 
-            let future_response = get_keyword_futures(&tmdb, movie_ids.clone()).await;
+            let future_response = get_keyword_futures(&tmdb, MediaType::Movie, movie_ids.clone()).await;
 
             assert!(!future_response.is_empty());
 
@@ -391,18 +593,30 @@ cfg_if! {
 
             let criteria = get_criteria();
 
-            redis_helper::criteria_to_cache(&session_id, criteria)
+            let store = MemorySessionStore::new();
+
+            store
+                .save(&session_id, &criteria)
                 .await
-                .expect("Error interacting with redis");
+                .expect("Error interacting with session store");
 
-            let recommendations = get_recommendations_for_session(tmdb, session_id.clone()).await;
+            let recommendations =
+                get_recommendations_for_session(Arc::clone(&tmdb), &store, session_id.clone(), &[], 1).await;
 
             assert!(recommendations.is_ok());
             let recommendations = recommendations.unwrap();
 
             assert!(!recommendations.is_empty());
 
-            redis_helper::end_session(session_id).await;
+            let shown = (recommendations[0].movie.media_type, recommendations[0].movie.id);
+            let next_batch =
+                get_recommendations_for_session(tmdb, &store, session_id.clone(), &[shown], 1)
+                    .await
+                    .unwrap();
+
+            assert!(next_batch.iter().all(|recommendation| (recommendation.movie.media_type, recommendation.movie.id) != shown));
+
+            store.delete(&session_id).await.unwrap();
         }
 
         #[tokio::test]
@@ -415,13 +629,17 @@ cfg_if! {
 
             criteria.genres = None;
 
-            redis_helper::criteria_to_cache(&session_id, criteria)
+            let store = MemorySessionStore::new();
+
+            store
+                .save(&session_id, &criteria)
                 .await
-                .expect("Error interacting with redis");
+                .expect("Error interacting with session store");
 
-            let recommendations = get_recommendations_for_session(tmdb, session_id.clone()).await;
+            let recommendations =
+                get_recommendations_for_session(tmdb, &store, session_id.clone(), &[], 1).await;
 
-            redis_helper::end_session(session_id).await;
+            store.delete(&session_id).await.unwrap();
         }
 
         #[tokio::test]
@@ -434,12 +652,16 @@ cfg_if! {
 
             criteria.watch_providers = None;
 
-            redis_helper::criteria_to_cache(&session_id, criteria)
+            let store = MemorySessionStore::new();
+
+            store
+                .save(&session_id, &criteria)
                 .await
-                .expect("Error interacting with redis");
+                .expect("Error interacting with session store");
 
-            let recommendations = get_recommendations_for_session(tmdb, session_id.clone()).await;
-            redis_helper::end_session(session_id).await;
+            let recommendations =
+                get_recommendations_for_session(tmdb, &store, session_id.clone(), &[], 1).await;
+            store.delete(&session_id).await.unwrap();
         }
 
         #[tokio::test]
@@ -452,12 +674,16 @@ cfg_if! {
 
             criteria.runtime = None;
 
-            redis_helper::criteria_to_cache(&session_id, criteria)
+            let store = MemorySessionStore::new();
+
+            store
+                .save(&session_id, &criteria)
                 .await
-                .expect("Error interacting with redis");
+                .expect("Error interacting with session store");
 
-            let recommendations = get_recommendations_for_session(tmdb, session_id.clone()).await;
-            redis_helper::end_session(session_id).await;
+            let recommendations =
+                get_recommendations_for_session(tmdb, &store, session_id.clone(), &[], 1).await;
+            store.delete(&session_id).await.unwrap();
         }
 
         #[tokio::test]
@@ -470,12 +696,153 @@ cfg_if! {
 
             criteria.decade = None;
 
-            redis_helper::criteria_to_cache(&session_id, criteria)
+            let store = MemorySessionStore::new();
+
+            store
+                .save(&session_id, &criteria)
                 .await
-                .expect("Error interacting with redis");
+                .expect("Error interacting with session store");
+
+            let recommendations =
+                get_recommendations_for_session(tmdb, &store, session_id.clone(), &[], 1).await;
+            store.delete(&session_id).await.unwrap();
+        }
+
+        #[test]
+        fn test_language_filter() {
+            let languages = LanguageFilter {
+                display: Some(String::from("es-ES")),
+                include: vec![],
+                exclude: vec![String::from("en")],
+            };
+
+            assert!(languages.allows("ko"));
+            assert!(!languages.allows("en"));
+            // TMDB occasionally leaves it blank
+            assert!(languages.allows(""));
+
+            let languages = LanguageFilter {
+                include: vec![String::from("ko"), String::from("ja")],
+                ..Default::default()
+            };
+
+            assert!(languages.allows("ja"));
+            assert!(!languages.allows("fr"));
+        }
+
+        #[tokio::test]
+        async fn test_excluded_languages_read_more_pages() {
+            // Three English movies then one Korean one on every page, and nothing past page 3
+            let fetch_page = |page: u32| async move {
+                let movies: Vec<Movie> = match page {
+                    1..=3 => (0..4)
+                        .map(|index| Movie {
+                            original_language: String::from(if index == 3 { "ko" } else { "en" }),
+                            ..test_movie(page as i64 * 10 + index)
+                        })
+                        .collect(),
+                    _ => vec![],
+                };
+                Ok(movies)
+            };
+
+            let everything = fetch_allowed(&LanguageFilter::default(), 1, 4, fetch_page).await.unwrap();
+            assert_eq!(everything.len(), 4);
+
+            let no_english = LanguageFilter {
+                exclude: vec![String::from("en")],
+                ..Default::default()
+            };
+
+            let ids: Vec<i64> = fetch_allowed(&no_english, 1, 2, fetch_page)
+                .await
+                .unwrap()
+                .iter()
+                .map(|movie| movie.id)
+                .collect();
+            assert_eq!(ids, vec![13, 23]);
+
+            // Runs out of pages before there are enough
+            let ids: Vec<i64> = fetch_allowed(&no_english, 2, 10, fetch_page)
+                .await
+                .unwrap()
+                .iter()
+                .map(|movie| movie.id)
+                .collect();
+            assert_eq!(ids, vec![23, 33]);
+        }
+
+        #[test]
+        fn test_language_validation() {
+            let valid = LanguageFilter {
+                display: Some(String::from("pt-BR")),
+                include: vec![String::from("pt")],
+                exclude: vec![String::from("en")],
+            };
+            assert!(valid.validate().is_ok());
+            assert!(LanguageFilter::default().validate().is_ok());
+
+            // Anything that could smuggle extra parameters into a query string is rejected
+            for display in ["en&with_genres=27", "english", "EN-us", "en-"] {
+                let languages = LanguageFilter {
+                    display: Some(String::from(display)),
+                    ..Default::default()
+                };
+                assert!(languages.validate().is_err(), "{}", display);
+            }
+
+            let languages = LanguageFilter {
+                exclude: vec![String::from("en|fr")],
+                ..Default::default()
+            };
+            assert!(languages.validate().is_err());
+        }
+
+        #[test]
+        fn test_certification_limits() {
+            let mut criteria = get_criteria();
+            assert_eq!(criteria.certification_limit(), None);
+
+            criteria.family_mode = true;
+            assert_eq!(criteria.certification_limit(), Some(CertificationLimit::family_default()));
+
+            let limit = CertificationLimit {
+                country: String::from("DE"),
+                max: String::from("12"),
+            };
+            criteria.certification = Some(limit.clone());
+            assert_eq!(criteria.family_limit(), Some(limit.clone()));
+
+            // Outside family mode the limit only applies to the wizard
+            criteria.family_mode = false;
+            assert_eq!(criteria.certification_limit(), Some(limit));
+            assert_eq!(criteria.family_limit(), None);
+        }
+
+        #[test]
+        fn test_tv_genres() {
+            // Action and Adventure collapse into one TV genre, Horror has none
+            assert_eq!(tv_genre_ids(&[28, 12, 35, 27]), vec![10759, 35]);
+            assert!(tv_genre_ids(&[27, 53]).is_empty());
+        }
+
+        #[test]
+        fn test_interleave() {
+            let item = |id: i64, media_type: MediaType| Movie {
+                title: id.to_string(),
+                media_type,
+                ..test_movie(id)
+            };
+
+            let combined = interleave(
+                vec![item(1, MediaType::Movie), item(2, MediaType::Movie), item(3, MediaType::Movie)],
+                vec![item(10, MediaType::Tv)],
+            );
 
-            let recommendations = get_recommendations_for_session(tmdb, session_id.clone()).await;
-            redis_helper::end_session(session_id).await;
+            assert_eq!(
+                combined.iter().map(|movie| movie.id).collect::<Vec<_>>(),
+                vec![1, 10, 2, 3]
+            );
         }
 
         #[tokio::test]
@@ -0,0 +1,239 @@
+use cfg_if::cfg_if;
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+    use serde::{Deserialize, Serialize};
+
+    use crate::accounts::DataOwner;
+    use crate::session_store::{load_json, update_json, SessionStore, SessionStoreError};
+    use crate::*;
+
+    const WATCHLIST_KEY_PREFIX: &str = "watchlist:";
+    // Every owner with something saved, so background jobs can find watchlists without scanning keys
+    const WATCHLIST_OWNERS_KEY: &str = "watchlist_owners";
+
+    /* Saved movies are kept whole so the watchlist page doesn't need a TMDB lookup per title */
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+    #[serde(default)]
+    pub struct Watchlist {
+        pub movies: Vec<Movie>,
+    }
+
+    impl Watchlist {
+        pub fn add(&mut self, movie: Movie) {
+            if !self.contains(movie.id, movie.media_type) {
+                self.movies.push(movie);
+            }
+        }
+
+        /* Movie and series IDs overlap, so both have to match */
+        pub fn remove(&mut self, movie_id: i64, media_type: MediaType) {
+            self.movies
+                .retain(|movie| movie.id != movie_id || movie.media_type != media_type);
+        }
+
+        pub fn contains(&self, movie_id: i64, media_type: MediaType) -> bool {
+            self.movies
+                .iter()
+                .any(|movie| movie.id == movie_id && movie.media_type == media_type)
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+    #[serde(default)]
+    struct WatchlistOwners {
+        owners: Vec<String>,
+    }
+
+    async fn register_owner(store: &dyn SessionStore, owner: &DataOwner) -> Result<(), SessionStoreError> {
+        let id = owner.id().to_string();
+
+        update_json(store, WATCHLIST_OWNERS_KEY, None, |index: &mut WatchlistOwners| {
+            if !index.owners.contains(&id) {
+                index.owners.push(id.clone());
+            }
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn watchlist_owners(store: &dyn SessionStore) -> Result<Vec<DataOwner>, SessionStoreError> {
+        let index: WatchlistOwners = load_json(store, WATCHLIST_OWNERS_KEY)
+            .await?
+            .unwrap_or_default();
+
+        Ok(index
+            .owners
+            .iter()
+            .filter_map(|id| DataOwner::from_id(id))
+            .collect())
+    }
+
+    /* For owners whose watchlist has emptied or expired - saving again puts them back */
+    pub async fn forget_owners(store: &dyn SessionStore, owners: &[DataOwner]) -> Result<(), SessionStoreError> {
+        let ids: Vec<String> = owners.iter().map(|owner| owner.id().to_string()).collect();
+
+        update_json(store, WATCHLIST_OWNERS_KEY, None, |index: &mut WatchlistOwners| {
+            index.owners.retain(|id| !ids.contains(id));
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn load_watchlist(
+        store: &dyn SessionStore,
+        owner: &DataOwner,
+    ) -> Result<Watchlist, SessionStoreError> {
+        Ok(load_json(store, &owner.key(WATCHLIST_KEY_PREFIX))
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn add_to_watchlist(
+        store: &dyn SessionStore,
+        owner: &DataOwner,
+        movie: Movie,
+    ) -> Result<Watchlist, SessionStoreError> {
+        let watchlist = update_json(
+            store,
+            &owner.key(WATCHLIST_KEY_PREFIX),
+            owner.ttl_seconds(),
+            |watchlist: &mut Watchlist| watchlist.add(movie.clone()),
+        )
+        .await?;
+
+        register_owner(store, owner).await?;
+
+        Ok(watchlist)
+    }
+
+    /* In the order given - a franchise goes on in release order */
+    pub async fn add_all_to_watchlist(
+        store: &dyn SessionStore,
+        owner: &DataOwner,
+        movies: Vec<Movie>,
+    ) -> Result<Watchlist, SessionStoreError> {
+        let watchlist = update_json(
+            store,
+            &owner.key(WATCHLIST_KEY_PREFIX),
+            owner.ttl_seconds(),
+            |watchlist: &mut Watchlist| {
+                for movie in &movies {
+                    watchlist.add(movie.clone());
+                }
+            },
+        )
+        .await?;
+
+        register_owner(store, owner).await?;
+
+        Ok(watchlist)
+    }
+
+    pub async fn remove_from_watchlist(
+        store: &dyn SessionStore,
+        owner: &DataOwner,
+        movie_id: i64,
+        media_type: MediaType,
+    ) -> Result<Watchlist, SessionStoreError> {
+        update_json(
+            store,
+            &owner.key(WATCHLIST_KEY_PREFIX),
+            owner.ttl_seconds(),
+            |watchlist: &mut Watchlist| watchlist.remove(movie_id, media_type),
+        )
+        .await
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::session_store::MemorySessionStore;
+
+        #[tokio::test]
+        async fn add_and_remove() {
+            let store = MemorySessionStore::new();
+            let owner = DataOwner::session("abc");
+
+            add_to_watchlist(&store, &owner, test_movie(1)).await.unwrap();
+            add_to_watchlist(&store, &owner, test_movie(2)).await.unwrap();
+            // Saving the same movie twice is a no-op
+            add_to_watchlist(&store, &owner, test_movie(1)).await.unwrap();
+
+            let watchlist = load_watchlist(&store, &owner).await.unwrap();
+
+            assert_eq!(watchlist.movies, vec![test_movie(1), test_movie(2)]);
+
+            remove_from_watchlist(&store, &owner, 1, MediaType::Movie).await.unwrap();
+
+            let watchlist = load_watchlist(&store, &owner).await.unwrap();
+
+            assert_eq!(watchlist.movies, vec![test_movie(2)]);
+        }
+
+        #[tokio::test]
+        async fn movies_and_series_share_ids() {
+            let store = MemorySessionStore::new();
+            let owner = DataOwner::session("xyz");
+            let series = Movie {
+                media_type: MediaType::Tv,
+                ..test_movie(1)
+            };
+
+            add_to_watchlist(&store, &owner, test_movie(1)).await.unwrap();
+            add_to_watchlist(&store, &owner, series.clone()).await.unwrap();
+
+            let watchlist = load_watchlist(&store, &owner).await.unwrap();
+
+            assert_eq!(watchlist.movies, vec![test_movie(1), series.clone()]);
+
+            remove_from_watchlist(&store, &owner, 1, MediaType::Movie).await.unwrap();
+
+            let watchlist = load_watchlist(&store, &owner).await.unwrap();
+
+            assert_eq!(watchlist.movies, vec![series]);
+        }
+
+        #[tokio::test]
+        async fn add_all_keeps_order() {
+            let store = MemorySessionStore::new();
+            let owner = DataOwner::session("def");
+
+            add_to_watchlist(&store, &owner, test_movie(2)).await.unwrap();
+            add_all_to_watchlist(&store, &owner, vec![test_movie(1), test_movie(2), test_movie(3)])
+                .await
+                .unwrap();
+
+            let watchlist = load_watchlist(&store, &owner).await.unwrap();
+
+            assert_eq!(watchlist.movies, vec![test_movie(2), test_movie(1), test_movie(3)]);
+        }
+
+        #[tokio::test]
+        async fn owners_are_indexed() {
+            let store = MemorySessionStore::new();
+            let anonymous = DataOwner::session("ghi");
+            let signed_in = DataOwner::user("moviefan");
+
+            add_to_watchlist(&store, &anonymous, test_movie(1)).await.unwrap();
+            add_to_watchlist(&store, &signed_in, test_movie(1)).await.unwrap();
+            add_to_watchlist(&store, &signed_in, test_movie(2)).await.unwrap();
+
+            let ids = |owners: Vec<DataOwner>| {
+                owners.iter().map(|owner| owner.id().to_string()).collect::<Vec<_>>()
+            };
+
+            assert_eq!(
+                ids(watchlist_owners(&store).await.unwrap()),
+                vec!["session:ghi", "user:moviefan"]
+            );
+
+            forget_owners(&store, &[anonymous]).await.unwrap();
+
+            assert_eq!(ids(watchlist_owners(&store).await.unwrap()), vec!["user:moviefan"]);
+        }
+    }
+}
+}